@@ -169,7 +169,7 @@ function runPageScript(path, search) {
   }
 }
 
-import initWasm, { plot_equation } from '/graph/pkg/graph_wasm.js';
+import initWasm, { Defs, Equation, Form } from '/graph/pkg/graph_wasm.js';
 
 function escapeHtml(str) {
   return str.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;').replace(/"/g, '&quot;');
@@ -776,6 +776,11 @@ let offsetX = 0, offsetY = 0;
 let isDragging = false;
 let isInteracting = false;
 let lastMouseX, lastMouseY;
+let downX, downY;
+// Shift+drag picks the bounds of the integrals shown for analyzed
+// equations, as world corners { x0, y0, x1, y1 }.
+let integralBounds = null;
+let isSelecting = false;
 
 function loadGraph() {
   graphCanvas = document.getElementById("graph-canvas");
@@ -801,14 +806,51 @@ function loadGraph() {
   };
 
   graphCanvas.addEventListener("mousedown", (e) => {
+    lastMouseX = downX = e.clientX;
+    lastMouseY = downY = e.clientY;
+    if (e.shiftKey) {
+      const [x, y] = worldAt(e);
+      integralBounds = { x0: x, y0: y, x1: x, y1: y };
+      isSelecting = true;
+      drawGraph();
+      return;
+    }
     isDragging = true;
-    lastMouseX = e.clientX;
-    lastMouseY = e.clientY;
     startInteraction();
   });
 
+  // A click (not the end of a drag) on an intersection shows its exact
+  // coordinates; a click anywhere else hides them.
+  graphCanvas.addEventListener("click", (e) => {
+    if (Math.hypot(e.clientX - downX, e.clientY - downY) > 3) return;
+    if (e.shiftKey) {
+      // a shift+click with no drag clears the integrals
+      integralBounds = null;
+      drawGraph();
+      return;
+    }
+    const dpr = window.devicePixelRatio || 1;
+    const rect = graphCanvas.getBoundingClientRect();
+    const mx = (e.clientX - rect.left) * dpr;
+    const my = (e.clientY - rect.top) * dpr;
+    let best = null;
+    let bestDist = 10 * dpr;
+    for (const p of graphIntersections) {
+      const d = Math.hypot(p.x - mx, p.y - my);
+      if (d < bestDist) {
+        best = p;
+        bestDist = d;
+      }
+    }
+    selectedIntersection = best && { wx: best.wx, wy: best.wy };
+    drawGraph();
+  });
+
   const graphMouseMove = (e) => {
-    if (isDragging) {
+    if (isSelecting) {
+      [integralBounds.x1, integralBounds.y1] = worldAt(e);
+      drawGraph();
+    } else if (isDragging) {
       const dx = e.clientX - lastMouseX;
       const dy = e.clientY - lastMouseY;
       offsetX += dx;
@@ -821,6 +863,10 @@ function loadGraph() {
   window.addEventListener("mousemove", graphMouseMove);
 
   const graphMouseUp = () => {
+    if (isSelecting) {
+      isSelecting = false;
+      return;
+    }
     isDragging = false;
     endInteraction();
   };
@@ -864,6 +910,8 @@ function loadGraph() {
     offsetX = 0;
     offsetY = 0;
     scale = 40;
+    selectedIntersection = null;
+    integralBounds = null;
     drawGraph();
   });
 
@@ -892,12 +940,12 @@ function loadGraph() {
     window.removeEventListener("resize", resizeGraph);
     isDragging = false;
     isInteracting = false;
+    isSelecting = false;
+    integralBounds = null;
+    graphIntersections = [];
+    selectedIntersection = null;
     graphCtx = null;
-    cachedCollisionBuffer = null;
-    cachedCollisionW = 0;
-    cachedCollisionH = 0;
-    cachedCollisionBufW = 0;
-    cachedCollisionBufH = 0;
+    clearCompiledEquations();
   };
 }
 
@@ -907,6 +955,7 @@ function addEquationInput(container, value) {
   group.innerHTML = `
     <span class="prompt">></span>
     <input type="text" class="equation-input" value="${value.replace(/"/g, '&quot;')}" />
+    <button class="btn-analyze" title="Derivative, zeros, extrema and inflection points; shift+drag to integrate">f'</button>
     <button class="btn-delete" title="Delete equation">X</button>
   `;
   container.appendChild(group);
@@ -923,6 +972,12 @@ function addEquationInput(container, value) {
     }
   });
 
+  const analyzeBtn = group.querySelector(".btn-analyze");
+  analyzeBtn.addEventListener("click", () => {
+    group.classList.toggle("analyzing");
+    drawGraph();
+  });
+
   const deleteBtn = group.querySelector(".btn-delete");
   deleteBtn.addEventListener("click", () => {
     const index = Array.from(container.children).indexOf(group);
@@ -934,6 +989,15 @@ function addEquationInput(container, value) {
   if (value === "") input.focus();
 }
 
+// The world point under a mouse event.
+function worldAt(e) {
+  const rect = graphCanvas.getBoundingClientRect();
+  const dpr = window.devicePixelRatio || 1;
+  const cx = graphCanvas.width / dpr / 2 + offsetX;
+  const cy = graphCanvas.height / dpr / 2 + offsetY;
+  return [(e.clientX - rect.left - cx) / scale, (cy - (e.clientY - rect.top)) / scale];
+}
+
 function resizeGraph() {
   if (!graphCanvas) return;
   const container = document.getElementById("graph-container");
@@ -953,11 +1017,6 @@ function resizeGraph() {
 
 let graphRenderLoopId;
 let graphNeedsUpdate = false;
-let cachedCollisionBuffer = null;
-let cachedCollisionW = 0;
-let cachedCollisionH = 0;
-let cachedCollisionBufW = 0;
-let cachedCollisionBufH = 0;
 
 function startGraphRenderLoop() {
   if (graphRenderLoopId) cancelAnimationFrame(graphRenderLoopId);
@@ -986,7 +1045,10 @@ function drawGraphLogic() {
   const cy = (h / 2 + offsetY * dpr);
   const physicalScale = scale * dpr;
 
-  const step = isInteracting ? 12 : 3;
+  // Curves are contoured in `step` cells, refined down to `minStep` pixels
+  // near the curve.
+  const step = isInteracting ? 16 : 8;
+  const minStep = isInteracting ? 4 : 1;
 
   graphCtx.fillStyle = "#0a0a0a";
   graphCtx.fillRect(0, 0, w, h);
@@ -1021,26 +1083,10 @@ function drawGraphLogic() {
   graphCtx.lineTo(cx, h);
   graphCtx.stroke();
 
-  const bufW = Math.ceil(w / step);
-  const bufH = Math.ceil(h / step);
-  if (w !== cachedCollisionW || h !== cachedCollisionH) {
-    cachedCollisionBuffer = new Int8Array(bufW * bufH);
-    cachedCollisionW = w;
-    cachedCollisionH = h;
-    cachedCollisionBufW = bufW;
-    cachedCollisionBufH = bufH;
-  } else if (bufW !== cachedCollisionBufW || bufH !== cachedCollisionBufH) {
-    cachedCollisionBuffer = new Int8Array(bufW * bufH);
-    cachedCollisionBufW = bufW;
-    cachedCollisionBufH = bufH;
-  } else {
-    cachedCollisionBuffer.fill(0);
-  }
-  const collisionBuffer = cachedCollisionBuffer;
-
+  const defErrors = updateDefinitions();
   const allVars = new Set();
   equations.forEach(eq => {
-    extractVariables(eq).forEach(v => allVars.add(v));
+    freeVariables(eq).forEach(v => allVars.add(v));
   });
 
   Object.keys(variables).forEach(v => {
@@ -1056,67 +1102,277 @@ function drawGraphLogic() {
   const varValues = Object.values(variables);
 
   graphCtx.lineWidth = 2 * dpr;
+  const values = new Float64Array(varValues);
+  const live = new Set();
+  const eqGroups = document.getElementById("equation-list")?.children || [];
+  const plotted = [];
+  let analyzed = 0;
   equations.forEach((eq, index) => {
-    if (!eq.trim()) return;
+    const group = eqGroups[index];
+    if (!eq.trim() || graphDefs.lines.has(index) || defErrors.has(index)) {
+      if (group) showEquationError(group, eq, defErrors.get(index) || null);
+      return;
+    }
+    live.add(compiledKey(eq, varNames));
+    if (group) {
+      compileEquation(eq, varNames);
+      showEquationError(group, eq, compiledEquations.get(compiledKey(eq, varNames)).error);
+    }
 
     try {
-      plotEquation(eq, cx, cy, w, h, index + 1, collisionBuffer, varNames, varValues, step, physicalScale);
+      if (group && group.classList.contains("analyzing")) {
+        if (analyzeEquation(eq, varNames, values, cx, cy, w, h, step, minStep, physicalScale, analyzed)) analyzed++;
+      }
+      plotEquation(eq, cx, cy, w, h, varNames, values, step, minStep, physicalScale);
+      const compiled = compileEquation(eq, varNames);
+      if (compiled) plotted.push(compiled);
     } catch (e) {
       console.error(`Error plotting equation ${index + 1}:`, e);
     }
   });
 
+  // every pair of curves, solved exactly rather than read off the grid
+  for (let i = 0; i < plotted.length; i++) {
+    for (let j = i + 1; j < plotted.length; j++) {
+      try {
+        const hits = plotted[i].intersections(values, plotted[j], values, w, h, physicalScale, cx, cy, step);
+        const world = hits.get_world();
+        const pixels = hits.get_pixels();
+        hits.free();
+        for (let k = 0; k < world.length; k += 2) {
+          drawIntersection(pixels[k], pixels[k + 1], world[k], world[k + 1]);
+        }
+      } catch (e) {
+        console.warn("Wasm intersection error:", e);
+      }
+    }
+  }
+
+  for (const [key, entry] of compiledEquations) {
+    if (!live.has(key)) {
+      if (entry.eq) entry.eq.free();
+      if (entry.derivative) entry.derivative.free();
+      compiledEquations.delete(key);
+    }
+  }
+
   if (pendingIntersections.length > 0) {
     graphCtx.save();
     graphCtx.shadowColor = "#fff";
     graphCtx.shadowBlur = 15;
     graphCtx.fillStyle = "#fff";
+    graphCtx.strokeStyle = "#fff";
+    graphCtx.lineWidth = 2 * dpr;
     for (const p of pendingIntersections) {
       graphCtx.beginPath();
       graphCtx.arc(p.x, p.y, 4, 0, Math.PI * 2);
-      graphCtx.fill();
+      // points of one curve are rings, crossings are dots
+      if (p.kind) graphCtx.stroke();
+      else graphCtx.fill();
     }
     graphCtx.restore();
   }
+  graphIntersections = pendingIntersections;
   pendingIntersections = [];
+  drawSelectedIntersection(dpr);
 }
 
-function plotEquation(eqStr, cx, cy, w, h, eqId, collisionBuffer, varNames, varValues, step, pScale) {
+// The label of the clicked intersection, if it still exists after a redraw
+// (sliders move intersections, so it is looked up by position).
+function drawSelectedIntersection(dpr) {
+  if (!selectedIntersection) return;
+  const { wx, wy } = selectedIntersection;
+  const tolerance = 1e-6 * Math.max(1, Math.hypot(wx, wy));
+  const p = graphIntersections.find(p => Math.abs(p.wx - wx) <= tolerance && Math.abs(p.wy - wy) <= tolerance);
+  if (!p) {
+    selectedIntersection = null;
+    return;
+  }
+  const text = `${p.kind ? `${p.kind} ` : ""}(${formatCoord(p.wx)}, ${formatCoord(p.wy)})`;
+  drawLabel(text, p.x + 8 * dpr, p.y - 8 * dpr - 12 * dpr - 8 * dpr, dpr);
+}
+
+// White text in a boxed label with its top left at (bx, by).
+function drawLabel(text, bx, by, dpr) {
+  graphCtx.save();
+  graphCtx.font = `${12 * dpr}px monospace`;
+  const pad = 4 * dpr;
+  const tw = graphCtx.measureText(text).width;
+  graphCtx.fillStyle = "rgba(10, 10, 10, 0.85)";
+  graphCtx.strokeStyle = "#00ff41";
+  graphCtx.lineWidth = 1 * dpr;
+  graphCtx.fillRect(bx, by, tw + 2 * pad, 12 * dpr + 2 * pad);
+  graphCtx.strokeRect(bx, by, tw + 2 * pad, 12 * dpr + 2 * pad);
+  graphCtx.fillStyle = "#fff";
+  graphCtx.textBaseline = "top";
+  graphCtx.fillText(text, bx + pad, by + pad);
+  graphCtx.restore();
+}
+
+// Up to 10 significant digits, without trailing zeros or "-0".
+function formatCoord(v) {
+  const r = Number(v.toPrecision(10));
+  return Object.is(r, -0) ? "0" : String(r);
+}
+
+// Lines like "f(t) = t^2" or "k = 3" define names for the other lines
+// instead of being plotted. `key` is the text of every definition, so
+// editing one recompiles the equations that might use it. `source` is the
+// text of every line, so a redraw that changed none of them (a pan, a
+// slider) keeps the definitions, their errors and the free variables found
+// with them.
+const noDefs = () => ({ defs: null, key: "", source: null, lines: new Set(), errors: new Map(), free: new Map() });
+let graphDefs = noDefs();
+
+// Returns the definition errors by line index. A broken definition isn't
+// plotted either.
+function updateDefinitions() {
+  const source = equations.join("\u0000");
+  if (graphDefs.defs && graphDefs.source === source) return graphDefs.errors;
+  if (graphDefs.defs) graphDefs.defs.free();
+  const defs = new Defs();
+  const lines = new Set();
+  const errors = new Map();
+  equations.forEach((eq, index) => {
+    if (!eq.trim()) return;
+    try {
+      if (defs.define(eq)) lines.add(index);
+    } catch (e) {
+      errors.set(index, copyError(e, eq));
+    }
+  });
+  const key = [...lines].map(i => equations[i]).join("\u0000");
+  graphDefs = { defs, key, source, lines, errors, free: new Map() };
+  return errors;
+}
+
+// Slider names an equation needs, as the parser sees them ("ax" is a*x).
+function freeVariables(eqStr) {
+  if (!eqStr.trim() || !graphDefs.defs) return [];
+  let free = graphDefs.free.get(eqStr);
+  if (!free) {
+    try {
+      free = graphDefs.defs.free_variables(eqStr);
+    } catch (e) {
+      if (typeof e.free === "function") e.free();
+      free = [];
+    }
+    graphDefs.free.set(eqStr, free);
+  }
+  return free;
+}
+
+// An EquationError lives in wasm memory: copy it out and free it.
+function copyError(e, eqStr) {
+  const error = { start: e.start ?? 0, end: e.end ?? eqStr.length, message: e.message || String(e) };
+  if (typeof e.free === "function") e.free();
+  return error;
+}
+
+// Parsed equations, keyed by text, slider names and definitions, so moving a
+// slider only re-evaluates. Failures are cached too, with the span the error
+// points at.
+const compiledEquations = new Map();
+
+function compiledKey(eqStr, varNames) {
+  return `${eqStr}\u0000${varNames.join(",")}\u0001${graphDefs.key}`;
+}
+
+function compileEquation(eqStr, varNames) {
+  const key = compiledKey(eqStr, varNames);
+  let entry = compiledEquations.get(key);
+  if (!entry) {
+    try {
+      entry = { eq: Equation.with_defs(eqStr, varNames, graphDefs.defs), error: null };
+    } catch (e) {
+      entry = { eq: null, error: copyError(e, eqStr) };
+    }
+    compiledEquations.set(key, entry);
+  }
+  return entry.eq;
+}
+
+// f' of a compiled equation, made the first time it's asked for; null
+// unless the equation is y = f(x) or x = g(y).
+function derivativeOf(eqStr, varNames) {
+  const entry = compiledEquations.get(compiledKey(eqStr, varNames));
+  if (!entry || !entry.eq) return null;
+  if (entry.derivative === undefined) entry.derivative = entry.eq.derivative() ?? null;
+  return entry.derivative;
+}
+
+function clearCompiledEquations() {
+  for (const entry of compiledEquations.values()) {
+    if (entry.eq) entry.eq.free();
+    if (entry.derivative) entry.derivative.free();
+  }
+  compiledEquations.clear();
+  if (graphDefs.defs) graphDefs.defs.free();
+  graphDefs = noDefs();
+}
+
+// Underlines the part of the equation an error points at, with the message
+// below the input. `start`/`end` count characters, not UTF-16 units.
+function showEquationError(group, eqStr, error) {
+  let box = group.querySelector(".equation-error");
+  group.classList.toggle("invalid", !!error);
+  if (!error) {
+    if (box) box.remove();
+    return;
+  }
+  if (!box) {
+    box = document.createElement("div");
+    box.className = "equation-error";
+    group.appendChild(box);
+  }
+  const chars = Array.from(eqStr);
+  const bad = document.createElement("u");
+  // an empty span marks the gap, e.g. a missing ")" at the end
+  bad.textContent = chars.slice(error.start, error.end).join("") || " ";
+  const code = document.createElement("code");
+  code.append(chars.slice(0, error.start).join(""), bad, chars.slice(error.end).join(""));
+  box.replaceChildren(code, ` ${error.message}`);
+}
+
+function plotEquation(eqStr, cx, cy, w, h, varNames, varValues, step, minStep, pScale) {
   try {
-    let processedEq = eqStr;
-    varNames.forEach((name, i) => {
-      const val = varValues[i];
-      const re = new RegExp(`\\b${name}\\b`, 'g');
-      processedEq = processedEq.replace(re, `(${val})`);
-    });
+    const eq = compileEquation(eqStr, varNames);
+    if (!eq) return;
 
-    const result = plot_equation(
-      processedEq,
+    const result = eq.plot(
       w,
       h,
       Number(pScale),
       Number(cx),
       Number(cy),
       Number(step),
-      collisionBuffer,
-      eqId,
-      [],
-      new Float64Array([])
+      Number(minStep),
+      varValues
     );
 
     try {
-      const lines = result.get_lines();
+      // an inequality's region, under its edge
       graphCtx.beginPath();
-      graphCtx.strokeStyle = "#00ff41";
-      for (let i = 0; i < lines.length; i += 4) {
-        graphCtx.moveTo(lines[i], lines[i + 1]);
-        graphCtx.lineTo(lines[i + 2], lines[i + 3]);
+      if (tracePolylines(result.fill()) > 0) {
+        graphCtx.fillStyle = "rgba(0, 255, 65, 0.15)";
+        graphCtx.fill();
       }
+
+      // one path per curve, simplified to half a pixel
+      graphCtx.beginPath();
+      graphCtx.strokeStyle = "#00ff41";
+      graphCtx.lineJoin = "round";
+      tracePolylines(result.polylines(0.5));
       graphCtx.stroke();
 
-      const intersections = result.get_intersections();
-      for (let i = 0; i < intersections.length; i += 2) {
-        drawIntersection(intersections[i], intersections[i + 1]);
+      // the edge of a strict inequality isn't part of its region
+      graphCtx.beginPath();
+      if (tracePolylines(result.dashed_polylines(0.5)) > 0) {
+        const dpr = window.devicePixelRatio || 1;
+        graphCtx.save();
+        graphCtx.setLineDash([6 * dpr, 4 * dpr]);
+        graphCtx.stroke();
+        graphCtx.restore();
       }
     } finally {
       result.free();
@@ -1127,23 +1383,83 @@ function plotEquation(eqStr, cx, cy, w, h, eqId, collisionBuffer, varNames, varV
   }
 }
 
-function extractVariables(eq) {
-  const tokens = eq.match(/[a-zA-Z]+/g) || [];
-  const knowns = new Set([
-    "x", "y",
-    "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh",
-    "log", "ln", "exp", "sqrt", "abs", "floor", "ceil", "round", "sign",
-    "pi", "e", "max", "min", "pow"
-  ]);
-
-  const vars = [];
-  tokens.forEach(t => {
-    const low = t.toLowerCase();
-    if (!knowns.has(low)) {
-      vars.push(t);
+// For an equation switched on with its f' button: f' plotted faintly, the
+// zeros, extrema and inflection points in view as clickable rings, and the
+// integral between the shift+dragged bounds, shaded and labelled in row
+// `row` of the top left corner. Returns whether the equation could be
+// analyzed at all.
+function analyzeEquation(eqStr, varNames, values, cx, cy, w, h, step, minStep, pScale, row) {
+  const eq = compileEquation(eqStr, varNames);
+  const derivative = derivativeOf(eqStr, varNames);
+  if (!eq || !derivative) return false;
+  const dpr = window.devicePixelRatio || 1;
+  const ofX = eq.get_form() === Form.YOfX;
+
+  try {
+    const result = derivative.plot(w, h, pScale, cx, cy, step, minStep, values);
+    graphCtx.save();
+    graphCtx.beginPath();
+    graphCtx.strokeStyle = "rgba(0, 255, 65, 0.45)";
+    graphCtx.lineWidth = 1.5 * dpr;
+    graphCtx.lineJoin = "round";
+    tracePolylines(result.polylines(0.5));
+    result.free();
+    graphCtx.stroke();
+    graphCtx.restore();
+
+    // the visible stretch of the axis f runs along
+    const [t0, t1] = ofX ? [-cx / pScale, (w - cx) / pScale] : [(cy - h) / pScale, cy / pScale];
+    const analysis = eq.analyze(values, t0, t1);
+    const kinds = [
+      ["zero", analysis.get_zeros()],
+      ["min", analysis.get_minima()],
+      ["max", analysis.get_maxima()],
+      ["inflection", analysis.get_inflections()],
+    ];
+    analysis.free();
+    for (const [kind, points] of kinds) {
+      for (let k = 0; k < points.length; k += 2) {
+        drawIntersection(cx + points[k] * pScale, cy - points[k + 1] * pScale, points[k], points[k + 1], kind);
+      }
+    }
+
+    if (integralBounds) {
+      const { x0, y0, x1, y1 } = integralBounds;
+      const [a, b] = ofX ? [x0, x1] : [y0, y1];
+      const integral = eq.integrate(values, a, b, pScale, cx, cy);
+      const value = integral.value();
+      graphCtx.beginPath();
+      if (tracePolylines(integral.region()) > 0) {
+        graphCtx.fillStyle = "rgba(0, 255, 65, 0.3)";
+        graphCtx.fill();
+      }
+      integral.free();
+      const text = `${eqStr}: ∫ from ${formatCoord(a)} to ${formatCoord(b)} = ${Number.isNaN(value) ? "undefined" : formatCoord(value)}`;
+      drawLabel(text, 8 * dpr, 8 * dpr + row * 24 * dpr, dpr);
+    }
+  } catch (e) {
+    console.warn("Wasm analysis error:", e);
+  }
+  return true;
+}
+
+// Adds each polyline to the current path, and frees them. Returns how many
+// there were.
+function tracePolylines(polylines) {
+  const points = polylines.get_points();
+  const offsets = polylines.get_offsets();
+  const closed = polylines.get_closed();
+  polylines.free();
+  for (let k = 0; k < closed.length; k++) {
+    const start = offsets[k];
+    const end = offsets[k + 1];
+    graphCtx.moveTo(points[2 * start], points[2 * start + 1]);
+    for (let i = start + 1; i < end; i++) {
+      graphCtx.lineTo(points[2 * i], points[2 * i + 1]);
     }
-  });
-  return vars;
+    if (closed[k]) graphCtx.closePath();
+  }
+  return closed.length;
 }
 
 function updateVariableControls() {
@@ -1187,7 +1503,12 @@ function updateVariableControls() {
 
 
 let pendingIntersections = [];
-
-function drawIntersection(x, y) {
-  pendingIntersections.push({ x, y });
+// Last frame's intersections, in pixels (x, y) and world coordinates (wx, wy),
+// along with the zeros, extrema and inflection points of analyzed equations,
+// which have a `kind`.
+let graphIntersections = [];
+let selectedIntersection = null;
+
+function drawIntersection(x, y, wx, wy, kind = null) {
+  pendingIntersections.push({ x, y, wx, wy, kind });
 }
@@ -0,0 +1,266 @@
+// === Analysis ===
+// Features of an explicit function over a stretch of its axis, found on its
+// derivatives (see diff.rs) rather than on the plotted segments. Zeros are
+// where f changes sign, extrema where f' does (a minimum from - to +) and
+// inflection points where f'' does; each sign change is bisected to the
+// precision of t, and poles, where the sign flips through infinity, are
+// dropped. An extremum where f is zero is a zero that only touches the
+// axis. A definite integral is adaptive Simpson over equal pieces, and
+// comes with the region between the curve and the axis to shade.
+
+use crate::equation::{Equation, Form};
+use crate::intersect::bisect;
+use crate::polyline::Polylines;
+use wasm_bindgen::prelude::*;
+
+// Steps the range is searched for sign changes in.
+const SAMPLES: usize = 1024;
+// |f| below this at an extremum makes it a zero.
+const TOUCH: f64 = 1e-10;
+// Pieces an integral starts from...
+const PIECES: usize = 64;
+// ...each halved at most this many times...
+const DEPTH: u32 = 18;
+// ...until the estimate settles to this fraction of the integral of |f|.
+const TOLERANCE: f64 = 1e-10;
+// Pixels the shaded region may reach from the origin, so a pole doesn't
+// take the canvas path to infinity.
+const CLAMP: f64 = 1e5;
+
+// Flat world [x, y, ...] points of each kind, in order along the axis.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    zeros: Vec<f64>,
+    minima: Vec<f64>,
+    maxima: Vec<f64>,
+    inflections: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Analysis {
+    pub fn get_zeros(&self) -> Vec<f64> {
+        self.zeros.clone()
+    }
+
+    pub fn get_minima(&self) -> Vec<f64> {
+        self.minima.clone()
+    }
+
+    pub fn get_maxima(&self) -> Vec<f64> {
+        self.maxima.clone()
+    }
+
+    pub fn get_inflections(&self) -> Vec<f64> {
+        self.inflections.clone()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Integral {
+    value: f64,
+    // pixel polygons between the curve and the axis
+    region: Polylines,
+}
+
+#[wasm_bindgen]
+impl Integral {
+    // NaN where f isn't defined across the range, or doesn't settle (a
+    // pole).
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn region(&self) -> Polylines {
+        self.region.clone()
+    }
+}
+
+// Where `h` changes sign from t0 to t1, each with whether it rises there.
+fn sign_changes(h: impl Fn(f64) -> f64, t0: f64, t1: f64) -> Vec<(f64, bool)> {
+    let mut out = vec![];
+    // the last sample where h was defined and not zero, and the samples
+    // since where it was zero
+    let mut last: Option<(f64, f64)> = None;
+    let mut zeros = vec![];
+    for i in 0..=SAMPLES {
+        let t = t0 + (t1 - t0) * i as f64 / SAMPLES as f64;
+        let ht = h(t);
+        if ht.is_nan() {
+            (last, zeros) = (None, vec![]);
+            continue;
+        }
+        if ht == 0.0 {
+            zeros.push(t);
+            continue;
+        }
+        if let Some((a, ha)) = last {
+            if (ha < 0.0) != (ht < 0.0) {
+                let root = match zeros.as_slice() {
+                    [] => bisect(&h, a, ha, t, ht),
+                    [z] => Some(*z),
+                    // flat in between, so no one point
+                    _ => None,
+                };
+                if let Some(r) = root {
+                    out.push((r, ha < 0.0));
+                }
+            }
+        }
+        (last, zeros) = (Some((t, ht)), vec![]);
+    }
+    out
+}
+
+fn push(list: &mut Vec<f64>, (x, y): (f64, f64)) {
+    if x.is_finite() && y.is_finite() {
+        list.extend([x, y]);
+    }
+}
+
+// None unless `eq` is explicit.
+pub(crate) fn analyze(eq: &Equation, values: &[f64], t0: f64, t1: f64) -> Option<Analysis> {
+    let d1 = eq.differentiate()?;
+    let d2 = d1.differentiate()?;
+    let mut out = Analysis::default();
+    let mut zeros: Vec<f64> = sign_changes(|t| eq.solve(t, values), t0, t1)
+        .into_iter()
+        .map(|(t, _)| t)
+        .collect();
+    for (t, rising) in sign_changes(|t| d1.solve(t, values), t0, t1) {
+        let list = if rising {
+            &mut out.minima
+        } else {
+            &mut out.maxima
+        };
+        push(list, eq.point(t, values));
+        if eq.solve(t, values).abs() < TOUCH && !zeros.iter().any(|z| (z - t).abs() < TOUCH) {
+            zeros.push(t);
+        }
+    }
+    zeros.sort_by(f64::total_cmp);
+    if t1 < t0 {
+        zeros.reverse();
+    }
+    for t in zeros {
+        push(&mut out.zeros, eq.point(t, values));
+    }
+    for (t, _) in sign_changes(|t| d2.solve(t, values), t0, t1) {
+        push(&mut out.inflections, eq.point(t, values));
+    }
+    Some(out)
+}
+
+// Simpson's rule over a..b, from (t, f(t)) at the ends and the middle.
+fn simpson(a: (f64, f64), m: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0) / 6.0 * (a.1 + 4.0 * m.1 + b.1)
+}
+
+// Halves a..b until the halves add up to `whole`, give or take `eps`.
+fn adapt(
+    f: &impl Fn(f64) -> f64,
+    a: (f64, f64),
+    m: (f64, f64),
+    b: (f64, f64),
+    whole: f64,
+    eps: f64,
+    depth: u32,
+) -> f64 {
+    let at = |t: f64| (t, f(t));
+    let (l, r) = (at((a.0 + m.0) / 2.0), at((m.0 + b.0) / 2.0));
+    let (left, right) = (simpson(a, l, m), simpson(m, r, b));
+    let delta = left + right - whole;
+    if !delta.is_finite() {
+        return f64::NAN;
+    }
+    if delta.abs() <= 15.0 * eps {
+        return left + right + delta / 15.0;
+    }
+    if depth == 0 {
+        // still off by its own size: not converging, so likely a pole
+        return if delta.abs() > (left + right).abs() {
+            f64::NAN
+        } else {
+            left + right + delta / 15.0
+        };
+    }
+    adapt(f, a, l, m, left, eps / 2.0, depth - 1) + adapt(f, m, r, b, right, eps / 2.0, depth - 1)
+}
+
+// The integral of `eq` from a to b along its axis, with the region in
+// pixels. None unless `eq` is explicit and a and b are finite.
+pub(crate) fn integrate(
+    eq: &Equation,
+    values: &[f64],
+    a: f64,
+    b: f64,
+    scale: f64,
+    center_x: f64,
+    center_y: f64,
+) -> Option<Integral> {
+    if !matches!(eq.form(), Form::YOfX | Form::XOfY) || !a.is_finite() || !b.is_finite() {
+        return None;
+    }
+    let f = |t| eq.solve(t, values);
+    let at = |t: f64| (t, f(t));
+    let width = (b - a) / PIECES as f64;
+    let value: f64 = (0..PIECES)
+        .map(|i| {
+            let lo = a + width * i as f64;
+            let hi = if i + 1 == PIECES { b } else { lo + width };
+            let (p, m, q) = (at(lo), at(lo + (hi - lo) / 2.0), at(hi));
+            let whole = simpson(p, m, q);
+            let size = (hi - lo).abs() * (p.1.abs() + m.1.abs() + q.1.abs()) / 3.0;
+            adapt(&f, p, m, q, whole, size * TOLERANCE, DEPTH)
+        })
+        .sum();
+
+    let pixel = |t: f64, v: f64| {
+        let (x, y) = if eq.form() == Form::YOfX {
+            (t, v)
+        } else {
+            (v, t)
+        };
+        (
+            (center_x + x * scale).clamp(-CLAMP, CLAMP),
+            (center_y - y * scale).clamp(-CLAMP, CLAMP),
+        )
+    };
+    // a sample a pixel, in one polygon per stretch that is defined and on
+    // one side of the axis
+    let n = ((b - a).abs() * scale).ceil().clamp(2.0, 4096.0) as usize;
+    let mut region = Polylines::new();
+    let mut close = |run: &[(f64, f64)]| {
+        if let [(first, _), .., (last, _)] = run {
+            let mut polygon = vec![pixel(*first, 0.0)];
+            polygon.extend(run.iter().map(|&(t, v)| pixel(t, v)));
+            polygon.push(pixel(*last, 0.0));
+            region.push_polygon(&polygon);
+        }
+    };
+    let mut run: Vec<(f64, f64)> = vec![];
+    for i in 0..=n {
+        let t = a + (b - a) * i as f64 / n as f64;
+        let v = f(t);
+        if !v.is_finite() {
+            close(&run);
+            run.clear();
+            continue;
+        }
+        if let Some(&(s, u)) = run.last() {
+            if u * v < 0.0 {
+                let cross = (s + (t - s) * u / (u - v), 0.0);
+                run.push(cross);
+                close(&run);
+                run = vec![cross];
+            }
+        }
+        run.push((t, v));
+    }
+    close(&run);
+    Some(Integral {
+        value: if value.is_finite() { value } else { f64::NAN },
+        region,
+    })
+}
@@ -0,0 +1,229 @@
+// === Expression trees ===
+// What the parser hands to the compiler. Names are gone by this point:
+// variables are numbered slots, built-in constants are numbers and user
+// definitions have been inlined, so a tree can be evaluated or rewritten
+// without any context beyond the slot values.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl BinOp {
+    pub fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            BinOp::Add => a + b,
+            BinOp::Sub => a - b,
+            BinOp::Mul => a * b,
+            BinOp::Div => a / b,
+            BinOp::Pow => a.powf(b),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Func {
+    Sqrt,
+    Cbrt,
+    Exp,
+    Ln,
+    Log,
+    Log2,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Sin,
+    Cos,
+    Tan,
+    Sec,
+    Csc,
+    Cot,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    // two arguments
+    Atan2,
+    Pow,
+    Mod,
+    Min,
+    Max,
+}
+
+const FUNCS: [(&str, Func); 35] = [
+    ("sqrt", Func::Sqrt),
+    ("cbrt", Func::Cbrt),
+    ("exp", Func::Exp),
+    ("ln", Func::Ln),
+    ("log", Func::Log),
+    ("log2", Func::Log2),
+    ("abs", Func::Abs),
+    ("sign", Func::Sign),
+    ("signum", Func::Sign),
+    ("floor", Func::Floor),
+    ("ceil", Func::Ceil),
+    ("round", Func::Round),
+    ("sin", Func::Sin),
+    ("cos", Func::Cos),
+    ("tan", Func::Tan),
+    ("sec", Func::Sec),
+    ("csc", Func::Csc),
+    ("cot", Func::Cot),
+    ("asin", Func::Asin),
+    ("acos", Func::Acos),
+    ("atan", Func::Atan),
+    ("sinh", Func::Sinh),
+    ("cosh", Func::Cosh),
+    ("tanh", Func::Tanh),
+    ("asinh", Func::Asinh),
+    ("acosh", Func::Acosh),
+    ("atanh", Func::Atanh),
+    ("arcsin", Func::Asin),
+    ("arccos", Func::Acos),
+    ("arctan", Func::Atan),
+    ("atan2", Func::Atan2),
+    ("pow", Func::Pow),
+    ("mod", Func::Mod),
+    ("min", Func::Min),
+    ("max", Func::Max),
+];
+
+impl Func {
+    pub fn lookup(name: &str) -> Option<Func> {
+        FUNCS.iter().find(|(n, _)| *n == name).map(|&(_, f)| f)
+    }
+
+    pub fn name(self) -> &'static str {
+        FUNCS
+            .iter()
+            .find(|&&(_, f)| f == self)
+            .map_or("?", |(n, _)| n)
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Func::Atan2 | Func::Pow | Func::Mod | Func::Min | Func::Max => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn unary(self) -> fn(f64) -> f64 {
+        match self {
+            Func::Sqrt => f64::sqrt,
+            Func::Cbrt => f64::cbrt,
+            Func::Exp => f64::exp,
+            Func::Ln => f64::ln,
+            Func::Log => f64::log10,
+            Func::Log2 => f64::log2,
+            Func::Abs => f64::abs,
+            Func::Sign => |a| if a == 0.0 { 0.0 } else { a.signum() },
+            Func::Floor => f64::floor,
+            Func::Ceil => f64::ceil,
+            Func::Round => f64::round,
+            Func::Sin => f64::sin,
+            Func::Cos => f64::cos,
+            Func::Tan => f64::tan,
+            Func::Sec => |a| 1.0 / a.cos(),
+            Func::Csc => |a| 1.0 / a.sin(),
+            Func::Cot => |a| 1.0 / a.tan(),
+            Func::Asin => f64::asin,
+            Func::Acos => f64::acos,
+            Func::Atan => f64::atan,
+            Func::Sinh => f64::sinh,
+            Func::Cosh => f64::cosh,
+            Func::Tanh => f64::tanh,
+            Func::Asinh => f64::asinh,
+            Func::Acosh => f64::acosh,
+            Func::Atanh => f64::atanh,
+            _ => |_| f64::NAN,
+        }
+    }
+
+    pub fn binary(self) -> fn(f64, f64) -> f64 {
+        match self {
+            Func::Atan2 => f64::atan2,
+            Func::Pow => f64::powf,
+            // takes the sign of the divisor, like a clock
+            Func::Mod => |a, b| a - b * (a / b).floor(),
+            Func::Min => f64::min,
+            Func::Max => f64::max,
+            _ => |_, _| f64::NAN,
+        }
+    }
+}
+
+// Named constants, looked up after variables and definitions.
+pub fn constant(name: &str) -> Option<f64> {
+    use std::f64::consts;
+    match name {
+        "pi" | "π" => Some(consts::PI),
+        "tau" | "τ" => Some(consts::TAU),
+        "e" => Some(consts::E),
+        "phi" | "φ" => Some(1.618_033_988_749_895),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Num(f64),
+    Var(usize),
+    Neg(Box<Node>),
+    Bin(BinOp, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+impl Node {
+    pub fn bin(op: BinOp, a: Node, b: Node) -> Node {
+        Node::Bin(op, Box::new(a), Box::new(b))
+    }
+
+    // Straight tree walk; the compiled `Program` is the fast path.
+    pub fn eval(&self, vars: &[f64]) -> f64 {
+        match self {
+            Node::Num(v) => *v,
+            Node::Var(i) => vars.get(*i).copied().unwrap_or(f64::NAN),
+            Node::Neg(a) => -a.eval(vars),
+            Node::Bin(op, a, b) => op.apply(a.eval(vars), b.eval(vars)),
+            Node::Call(f, args) => match args.as_slice() {
+                [a] => f.unary()(a.eval(vars)),
+                [a, b] => f.binary()(a.eval(vars), b.eval(vars)),
+                _ => f64::NAN,
+            },
+        }
+    }
+
+    pub fn uses(&self, slot: usize) -> bool {
+        match self {
+            Node::Num(_) => false,
+            Node::Var(i) => *i == slot,
+            Node::Neg(a) => a.uses(slot),
+            Node::Bin(_, a, b) => a.uses(slot) || b.uses(slot),
+            Node::Call(_, args) => args.iter().any(|a| a.uses(slot)),
+        }
+    }
+
+    // Replaces every `Var(i)` with `with[i]`.
+    pub fn substitute(&self, with: &[Node]) -> Node {
+        match self {
+            Node::Num(v) => Node::Num(*v),
+            Node::Var(i) => with.get(*i).cloned().unwrap_or(Node::Num(f64::NAN)),
+            Node::Neg(a) => Node::Neg(Box::new(a.substitute(with))),
+            Node::Bin(op, a, b) => Node::bin(*op, a.substitute(with), b.substitute(with)),
+            Node::Call(f, args) => {
+                Node::Call(*f, args.iter().map(|a| a.substitute(with)).collect())
+            }
+        }
+    }
+}
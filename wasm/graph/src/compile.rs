@@ -0,0 +1,158 @@
+// === Compiled expressions ===
+// A tree is flattened once into stack-machine code and then run for every
+// sample. Constant subtrees are folded and small integer powers become
+// multiplications, since "x^2" is by far the most common power.
+
+use crate::ast::{BinOp, Node};
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Num(f64),
+    Arg(usize),
+    Param(usize),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Powi(i32),
+    Unary(fn(f64) -> f64),
+    Binary(fn(f64, f64) -> f64),
+}
+
+// Deep enough for anything typed by hand; deeper programs spill to the heap.
+const STACK: usize = 32;
+
+#[derive(Clone, Debug)]
+pub struct Program {
+    code: Vec<Op>,
+    nargs: usize,
+    depth: usize,
+}
+
+impl Program {
+    // Slots below `nargs` are the per-sample arguments (x and y for an
+    // equation); the rest are parameters.
+    pub fn new(node: &Node, nargs: usize) -> Program {
+        let mut p = Program {
+            code: Vec::new(),
+            nargs,
+            depth: 0,
+        };
+        let folded = fold(node);
+        p.emit(&folded, 0);
+        p
+    }
+
+    fn emit(&mut self, node: &Node, sp: usize) {
+        self.depth = self.depth.max(sp + 1);
+        match node {
+            Node::Num(v) => self.code.push(Op::Num(*v)),
+            Node::Var(i) if *i < self.nargs => self.code.push(Op::Arg(*i)),
+            Node::Var(i) => self.code.push(Op::Param(*i - self.nargs)),
+            Node::Neg(a) => {
+                self.emit(a, sp);
+                self.code.push(Op::Neg);
+            }
+            Node::Bin(BinOp::Pow, a, b) if small_int(b).is_some() => {
+                self.emit(a, sp);
+                self.code.push(Op::Powi(small_int(b).unwrap()));
+            }
+            Node::Bin(op, a, b) => {
+                self.emit(a, sp);
+                self.emit(b, sp + 1);
+                self.code.push(match op {
+                    BinOp::Add => Op::Add,
+                    BinOp::Sub => Op::Sub,
+                    BinOp::Mul => Op::Mul,
+                    BinOp::Div => Op::Div,
+                    BinOp::Pow => Op::Pow,
+                });
+            }
+            Node::Call(f, args) => {
+                for (k, a) in args.iter().enumerate() {
+                    self.emit(a, sp + k);
+                }
+                self.code.push(match args.len() {
+                    1 => Op::Unary(f.unary()),
+                    _ => Op::Binary(f.binary()),
+                });
+            }
+        }
+    }
+
+    // A parameter without a value is NaN, which plots nothing.
+    pub fn eval(&self, args: &[f64], params: &[f64]) -> f64 {
+        let mut small = [0.0; STACK];
+        let mut big = Vec::new();
+        let stack: &mut [f64] = if self.depth <= STACK {
+            &mut small
+        } else {
+            big.resize(self.depth, 0.0);
+            &mut big
+        };
+        let mut sp = 0;
+        for op in &self.code {
+            match *op {
+                Op::Num(v) => {
+                    stack[sp] = v;
+                    sp += 1;
+                }
+                Op::Arg(i) => {
+                    stack[sp] = args.get(i).copied().unwrap_or(f64::NAN);
+                    sp += 1;
+                }
+                Op::Param(i) => {
+                    stack[sp] = params.get(i).copied().unwrap_or(f64::NAN);
+                    sp += 1;
+                }
+                Op::Neg => stack[sp - 1] = -stack[sp - 1],
+                Op::Powi(n) => stack[sp - 1] = stack[sp - 1].powi(n),
+                Op::Unary(f) => stack[sp - 1] = f(stack[sp - 1]),
+                op => {
+                    sp -= 1;
+                    let (a, b) = (stack[sp - 1], stack[sp]);
+                    stack[sp - 1] = match op {
+                        Op::Add => a + b,
+                        Op::Sub => a - b,
+                        Op::Mul => a * b,
+                        Op::Div => a / b,
+                        Op::Pow => a.powf(b),
+                        Op::Binary(f) => f(a, b),
+                        _ => unreachable!(),
+                    };
+                }
+            }
+        }
+        stack[0]
+    }
+}
+
+fn small_int(node: &Node) -> Option<i32> {
+    match node {
+        Node::Num(v) if v.fract() == 0.0 && v.abs() <= 16.0 => Some(*v as i32),
+        _ => None,
+    }
+}
+
+// Evaluates every subtree that doesn't depend on a slot.
+fn fold(node: &Node) -> Node {
+    let folded = match node {
+        Node::Num(_) | Node::Var(_) => return node.clone(),
+        Node::Neg(a) => Node::Neg(Box::new(fold(a))),
+        Node::Bin(op, a, b) => Node::bin(*op, fold(a), fold(b)),
+        Node::Call(f, args) => Node::Call(*f, args.iter().map(fold).collect()),
+    };
+    let constant = match &folded {
+        Node::Neg(a) => matches!(**a, Node::Num(_)),
+        Node::Bin(_, a, b) => matches!(**a, Node::Num(_)) && matches!(**b, Node::Num(_)),
+        Node::Call(_, args) => args.iter().all(|a| matches!(a, Node::Num(_))),
+        _ => false,
+    };
+    if constant {
+        Node::Num(folded.eval(&[]))
+    } else {
+        folded
+    }
+}
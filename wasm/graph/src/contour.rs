@@ -0,0 +1,208 @@
+// === Contouring ===
+// Marching squares on a quadtree. The view is cut into `step`-pixel cells,
+// and a cell is split in four while it is bigger than `min_step` and might
+// hold part of the curve: a sign change at its corners or center, or a
+// value near zero for how fast f changes across it. Away from the curve a
+// cell costs one sample per corner; near it, cells shrink to `min_step`, so
+// thin features and tight loops survive a coarse `step`.
+//
+// For a region (where f > 0) every leaf also fills its inside: a whole
+// tile if all of it is inside, else the polygon the contour cuts off.
+
+use crate::PlotResult;
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+struct Contour<F> {
+    func: F,
+    scale: f64,
+    center_x: f64,
+    center_y: f64,
+    min_step: f64,
+    fill: bool,
+    result: PlotResult,
+}
+
+fn get_t(v1: f64, v2: f64) -> f64 {
+    if (v2 - v1).abs() < 1e-9 {
+        0.5
+    } else {
+        (0.0 - v1) / (v2 - v1)
+    }
+}
+
+// Corners are [top-left, top-right, bottom-right, bottom-left].
+fn worth_splitting(corners: [f64; 4], center: f64) -> bool {
+    let vals = [corners[0], corners[1], corners[2], corners[3], center];
+    let defined = vals.iter().filter(|v| !v.is_nan()).count();
+    if defined < vals.len() {
+        // the edge of the domain (e.g. sqrt(x) at x = 0) may hide the curve
+        return defined > 0;
+    }
+    let positive = vals.iter().filter(|&&v| v > 0.0).count();
+    if positive != 0 && positive != vals.len() {
+        return true;
+    }
+    let lo = vals.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = vals.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let near = vals.iter().map(|v| v.abs()).fold(f64::INFINITY, f64::min);
+    near < hi - lo
+}
+
+impl<F: Fn(f64, f64) -> f64> Contour<F> {
+    fn sample(&self, px: f64, py: f64) -> f64 {
+        (self.func)(
+            (px - self.center_x) / self.scale,
+            (self.center_y - py) / self.scale,
+        )
+    }
+
+    fn cell(&mut self, px: f64, py: f64, size: f64, corners: [f64; 4]) {
+        let [tl, tr, br, bl] = corners;
+        if corners.iter().all(|v| v.is_nan()) {
+            return;
+        }
+        let half = size / 2.0;
+        let center = self.sample(px + half, py + half);
+        if half < self.min_step || !worth_splitting(corners, center) {
+            self.leaf(px, py, size, corners, center);
+            return;
+        }
+        let top = self.sample(px + half, py);
+        let right = self.sample(px + size, py + half);
+        let bottom = self.sample(px + half, py + size);
+        let left = self.sample(px, py + half);
+        self.cell(px, py, half, [tl, top, center, left]);
+        self.cell(px + half, py, half, [top, tr, right, center]);
+        self.cell(px + half, py + half, half, [center, right, br, bottom]);
+        self.cell(px, py + half, half, [left, center, bottom, bl]);
+    }
+
+    fn leaf(&mut self, px: f64, py: f64, size: f64, corners: [f64; 4], center: f64) {
+        let [tl, tr, br, bl] = corners;
+        if corners.iter().any(|v| v.is_nan()) {
+            return;
+        }
+        if self.fill {
+            self.fill(px, py, size, corners);
+        }
+        let mut case = 0;
+        if tl > 0.0 {
+            case |= 8;
+        }
+        if tr > 0.0 {
+            case |= 4;
+        }
+        if br > 0.0 {
+            case |= 2;
+        }
+        if bl > 0.0 {
+            case |= 1;
+        }
+        use Edge::*;
+        let segments: &[(Edge, Edge)] = match case {
+            1 | 14 => &[(Left, Bottom)],
+            2 | 13 => &[(Bottom, Right)],
+            3 | 12 => &[(Left, Right)],
+            4 | 11 => &[(Top, Right)],
+            6 | 9 => &[(Top, Bottom)],
+            7 | 8 => &[(Left, Top)],
+            // saddles: the center decides which corners connect
+            5 if center > 0.0 => &[(Left, Bottom), (Top, Right)],
+            5 => &[(Left, Top), (Bottom, Right)],
+            10 if center > 0.0 => &[(Left, Top), (Bottom, Right)],
+            10 => &[(Left, Bottom), (Top, Right)],
+            _ => return,
+        };
+        let point = |e: Edge| match e {
+            Top => (px + get_t(tl, tr) * size, py),
+            Right => (px + size, py + get_t(tr, br) * size),
+            Bottom => (px + get_t(bl, br) * size, py + size),
+            Left => (px, py + get_t(tl, bl) * size),
+        };
+        for &(a, b) in segments {
+            let ((x1, y1), (x2, y2)) = (point(a), point(b));
+            self.result.lines.extend_from_slice(&[x1, y1, x2, y2]);
+        }
+    }
+
+    // The inside of a leaf: its inside corners and where the edges between
+    // them cross zero, in order around the cell.
+    fn fill(&mut self, px: f64, py: f64, size: f64, corners: [f64; 4]) {
+        let at = [
+            (px, py),
+            (px + size, py),
+            (px + size, py + size),
+            (px, py + size),
+        ];
+        let mut polygon = Vec::with_capacity(5);
+        for i in 0..4 {
+            let j = (i + 1) % 4;
+            let (a, b) = (corners[i], corners[j]);
+            if a > 0.0 {
+                polygon.push(at[i]);
+            }
+            if (a > 0.0) != (b > 0.0) {
+                let t = get_t(a, b);
+                let (p, q) = (at[i], at[j]);
+                polygon.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+        if polygon.len() >= 3 {
+            self.result.fill.push_polygon(&polygon);
+        }
+    }
+}
+
+// Contours the zero set of `func` over a w x h pixel view. With `fill`,
+// also fills where `func` > 0.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn march(
+    func: impl Fn(f64, f64) -> f64,
+    w: i32,
+    h: i32,
+    scale: f64,
+    center_x: i32,
+    center_y: i32,
+    step: i32,
+    min_step: i32,
+    fill: bool,
+) -> PlotResult {
+    let step = step.max(1);
+    let mut c = Contour {
+        func,
+        scale,
+        center_x: center_x as f64,
+        center_y: center_y as f64,
+        min_step: min_step.clamp(1, step) as f64,
+        fill,
+        result: PlotResult::new(),
+    };
+    let size = step as f64;
+    let cols = (w / step + 2) as usize;
+
+    // the coarse grid is sampled a row at a time
+    let row = |c: &Contour<_>, py: f64| -> Vec<f64> {
+        (0..cols).map(|i| c.sample(i as f64 * size, py)).collect()
+    };
+    let mut top = row(&c, 0.0);
+    let mut py = 0;
+    while py < h {
+        let bottom = row(&c, (py + step) as f64);
+        let mut i = 0;
+        while (i as i32) * step < w {
+            let corners = [top[i], top[i + 1], bottom[i + 1], bottom[i]];
+            c.cell(i as f64 * size, py as f64, size, corners);
+            i += 1;
+        }
+        top = bottom;
+        py += step;
+    }
+    c.result
+}
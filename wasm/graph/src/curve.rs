@@ -0,0 +1,139 @@
+// === Sampled curves ===
+// A curve given by one parameter, like y = f(x), is sampled along it rather
+// than contoured. The parameter range is cut into pieces, and a piece is
+// halved while its midpoint strays from the chord or the chord is long,
+// down to `min_width`. A piece still long at that width is a jump (tan(x)
+// at pi/2, 1/x at 0) if narrowing in on it doesn't shorten it, and the
+// curve is broken there instead of joined across.
+
+use crate::PlotResult;
+
+// A piece is straight enough when its midpoint is this close (in pixels)
+// to the chord...
+const FLAT: f64 = 0.25;
+// ...and the chord is at most this long, so no jump hides inside it.
+const SPAN: f64 = 8.0;
+
+struct Sampler<F> {
+    point: F,
+    w: f64,
+    h: f64,
+    min_width: f64,
+    result: PlotResult,
+}
+
+fn finite(p: (f64, f64)) -> bool {
+    p.0.is_finite() && p.1.is_finite()
+}
+
+fn dist(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+// Distance from `p` to the line through a and b.
+fn off_chord(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let len = dist(a, b);
+    if len == 0.0 {
+        return dist(a, p);
+    }
+    ((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)).abs() / len
+}
+
+impl<F: Fn(f64) -> (f64, f64)> Sampler<F> {
+    // Bit per view edge the point is beyond.
+    fn outside(&self, p: (f64, f64)) -> u8 {
+        (p.0 < -SPAN) as u8
+            | ((p.0 > self.w + SPAN) as u8) << 1
+            | ((p.1 < -SPAN) as u8) << 2
+            | ((p.1 > self.h + SPAN) as u8) << 3
+    }
+
+    fn piece(&mut self, a: f64, pa: (f64, f64), b: f64, pb: (f64, f64)) {
+        if !finite(pa) && !finite(pb) {
+            return;
+        }
+        let m = a + (b - a) / 2.0;
+        let pm = (self.point)(m);
+        let ends = finite(pa) && finite(pb);
+        if ends && finite(pm) {
+            // nothing to see off the side of the view
+            let off = self.outside(pa) & self.outside(pm) & self.outside(pb);
+            if off != 0 || (dist(pa, pb) <= SPAN && off_chord(pm, pa, pb) <= FLAT) {
+                self.emit(pa, pb);
+                return;
+            }
+        }
+        if (b - a) / 2.0 < self.min_width {
+            // undefined ends are where the domain stops; drop the sliver
+            if ends && finite(pm) && !self.jumps(a, pa, b, pb) {
+                self.emit(pa, pb);
+            }
+            return;
+        }
+        self.piece(a, pa, m, pm);
+        self.piece(m, pm, b, pb);
+    }
+
+    // Follows the longer half of a-b down to the precision of t. Along a
+    // continuous curve the chord shrinks with the piece; across a jump it
+    // doesn't.
+    fn jumps(&self, mut a: f64, mut pa: (f64, f64), mut b: f64, mut pb: (f64, f64)) -> bool {
+        let gap = dist(pa, pb);
+        for _ in 0..64 {
+            let m = a + (b - a) / 2.0;
+            if m <= a || m >= b {
+                break;
+            }
+            let pm = (self.point)(m);
+            if !finite(pm) {
+                return true;
+            }
+            if dist(pa, pm) > dist(pm, pb) {
+                (b, pb) = (m, pm);
+            } else {
+                (a, pa) = (m, pm);
+            }
+        }
+        dist(pa, pb) > gap * 1e-3
+    }
+
+    fn emit(&mut self, a: (f64, f64), b: (f64, f64)) {
+        self.result.lines.extend_from_slice(&[a.0, a.1, b.0, b.1]);
+    }
+}
+
+// Samples `point` (parameter to pixel) from t0 to t1 in `pieces` equal
+// pieces, each refined down to `min_width` of the parameter.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn sample(
+    point: impl Fn(f64) -> (f64, f64),
+    t0: f64,
+    t1: f64,
+    pieces: usize,
+    min_width: f64,
+    w: i32,
+    h: i32,
+) -> PlotResult {
+    let mut s = Sampler {
+        point,
+        w: w as f64,
+        h: h as f64,
+        min_width,
+        result: PlotResult::new(),
+    };
+    let pieces = pieces.max(1);
+    let width = (t1 - t0) / pieces as f64;
+    let mut a = t0;
+    let mut pa = (s.point)(a);
+    for i in 1..=pieces {
+        let b = if i == pieces {
+            t1
+        } else {
+            t0 + width * i as f64
+        };
+        let pb = (s.point)(b);
+        s.piece(a, pa, b, pb);
+        (a, pa) = (b, pb);
+    }
+    s.result
+}
@@ -0,0 +1,159 @@
+// === Definitions ===
+// Lines like "f(t) = t^2 - a" or "k = 3" name something other equations
+// can use. Definitions are inlined wherever they're used, so a definition
+// only sees names defined before it, and never itself. Names a definition
+// leaves free (the `a` above) are resolved at each use, so they bind to the
+// using equation's sliders.
+
+use crate::ast::{constant, Func, Node};
+use crate::error::Error;
+use crate::parse::{check_equals, lex, shape, Parser, Shape, Tok, Token};
+use wasm_bindgen::prelude::*;
+
+struct Def {
+    name: String,
+    arity: usize,
+    // slots past `arity` are the free names, in order
+    body: Node,
+    free: Vec<String>,
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Defs {
+    defs: Vec<Def>,
+}
+
+// A definition is "name = ..." or "name(a, b) = ...", where the name isn't x,
+// y or a built-in. A bare name containing x or y ("xy = 1") reads as an
+// equation instead, and "r = ..." is a polar curve.
+fn head(toks: &[Token]) -> Option<(&Token, Vec<String>, usize)> {
+    let name = match &toks.first()?.tok {
+        Tok::Name(n) => n,
+        _ => return None,
+    };
+    if name == "x" || name == "y" || Func::lookup(name).is_some() || constant(name).is_some() {
+        return None;
+    }
+    if toks.get(1)?.is('=') {
+        if name == "r" {
+            return None;
+        }
+        let plain = !name.contains('_');
+        return (!plain || !name.contains(['x', 'y'])).then(|| (&toks[0], Vec::new(), 2));
+    }
+    if !toks[1].is('(') {
+        return None;
+    }
+    let mut args: Vec<String> = Vec::new();
+    let mut i = 2;
+    loop {
+        match &toks.get(i)?.tok {
+            Tok::Name(a) if !args.contains(a) && Func::lookup(a).is_none() => args.push(a.clone()),
+            _ => return None,
+        }
+        i += 1;
+        if toks.get(i)?.is(')') {
+            break;
+        }
+        if !toks[i].is(',') {
+            return None;
+        }
+        i += 1;
+    }
+    toks.get(i + 1)?.is('=').then(|| (&toks[0], args, i + 2))
+}
+
+impl Defs {
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        self.defs.iter().position(|d| d.name == name)
+    }
+
+    pub(crate) fn arity(&self, i: usize) -> usize {
+        self.defs[i].arity
+    }
+
+    pub(crate) fn body(&self, i: usize) -> (&Node, &[String]) {
+        (&self.defs[i].body, &self.defs[i].free)
+    }
+
+    // Parses `toks` as a definition; None if it's an equation after all.
+    fn parse(&self, toks: &[Token]) -> Result<Option<Def>, Error> {
+        check_equals(toks)?;
+        let Some((name_tok, args, at)) = head(toks) else {
+            return Ok(None);
+        };
+        let Tok::Name(name) = &name_tok.tok else {
+            return Ok(None);
+        };
+        let arity = args.len();
+        let mut p = Parser::new(toks, args, self, true);
+        let body = p.body(at)?;
+        let free = p.slots.split_off(arity);
+        if arity == 0 && free.iter().any(|n| n == "x" || n == "y") {
+            return Ok(None);
+        }
+        if free.contains(name) {
+            let msg = format!("'{}' can't refer to itself", name);
+            return Err(Error::syntax(name_tok.start, name_tok.end, msg));
+        }
+        Ok(Some(Def {
+            name: name.clone(),
+            arity,
+            body,
+            free,
+        }))
+    }
+}
+
+#[wasm_bindgen]
+impl Defs {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Defs {
+        Defs::default()
+    }
+
+    // Adds `src` if it's a definition; false means it's an equation to plot.
+    pub fn define(&mut self, src: &str) -> Result<bool, Error> {
+        let toks = lex(src)?;
+        let Some(def) = self.parse(&toks)? else {
+            return Ok(false);
+        };
+        if self.position(&def.name).is_some() {
+            let msg = format!("'{}' is already defined", def.name);
+            return Err(Error::syntax(toks[0].start, toks[0].end, msg));
+        }
+        self.defs.push(def);
+        Ok(true)
+    }
+
+    // The names in `src` that are neither x, y, the parameter of a curve,
+    // defined nor built in: the sliders it needs. Unknown functions are
+    // still an error.
+    pub fn free_variables(&self, src: &str) -> Result<Vec<String>, Error> {
+        let toks = lex(src)?;
+        let free = match self.parse(&toks)? {
+            Some(def) => def.free,
+            None => {
+                let shape = shape(&toks, &[]);
+                let mut p = Parser::new(&toks, shape.slots(), self, true);
+                match shape {
+                    Shape::Equation => {
+                        p.equation()?;
+                    }
+                    Shape::Curve => {
+                        p.curve()?;
+                    }
+                    Shape::Polar => {
+                        p.polar()?;
+                    }
+                    Shape::Region => {
+                        p.region()?;
+                    }
+                }
+                p.slots.split_off(shape.slots().len())
+            }
+        };
+        Ok(free.into_iter().filter(|n| n != "x" && n != "y").collect())
+    }
+}
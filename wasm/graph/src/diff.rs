@@ -0,0 +1,196 @@
+// === Derivatives ===
+// Symbolic differentiation of a tree with respect to one slot. The result
+// is an ordinary tree, so it compiles and evaluates like anything typed in.
+// Terms that are plainly zero or one are dropped as the tree is built,
+// which keeps second derivatives from growing out of hand; the compiler
+// folds whatever constants remain. Step functions (floor, sign, ...) have
+// derivative zero wherever they have one at all.
+
+use crate::ast::{BinOp, Func, Node};
+use std::f64::consts::{LN_10, LN_2};
+
+fn num(v: f64) -> Node {
+    Node::Num(v)
+}
+
+fn is(node: &Node, v: f64) -> bool {
+    *node == Node::Num(v)
+}
+
+fn neg(a: Node) -> Node {
+    match a {
+        Node::Num(v) => num(-v),
+        Node::Neg(a) => *a,
+        a => Node::Neg(Box::new(a)),
+    }
+}
+
+fn add(a: Node, b: Node) -> Node {
+    if is(&a, 0.0) {
+        b
+    } else if is(&b, 0.0) {
+        a
+    } else {
+        Node::bin(BinOp::Add, a, b)
+    }
+}
+
+fn sub(a: Node, b: Node) -> Node {
+    if is(&b, 0.0) {
+        a
+    } else if is(&a, 0.0) {
+        neg(b)
+    } else {
+        Node::bin(BinOp::Sub, a, b)
+    }
+}
+
+fn mul(a: Node, b: Node) -> Node {
+    if is(&a, 0.0) || is(&b, 0.0) {
+        num(0.0)
+    } else if is(&a, 1.0) {
+        b
+    } else if is(&b, 1.0) {
+        a
+    } else {
+        Node::bin(BinOp::Mul, a, b)
+    }
+}
+
+fn div(a: Node, b: Node) -> Node {
+    if is(&a, 0.0) {
+        num(0.0)
+    } else if is(&b, 1.0) {
+        a
+    } else {
+        Node::bin(BinOp::Div, a, b)
+    }
+}
+
+fn pow(a: Node, b: Node) -> Node {
+    if is(&b, 1.0) {
+        a
+    } else {
+        Node::bin(BinOp::Pow, a, b)
+    }
+}
+
+fn call(f: Func, a: Node) -> Node {
+    Node::Call(f, vec![a])
+}
+
+fn square(a: Node) -> Node {
+    pow(a, num(2.0))
+}
+
+// d(a^b), for any a and b.
+fn power(a: &Node, b: &Node, da: Node, db: Node, slot: usize) -> Node {
+    if !b.uses(slot) {
+        // b a^(b - 1) a'
+        let lower = match b {
+            Node::Num(v) => num(v - 1.0),
+            b => sub(b.clone(), num(1.0)),
+        };
+        return mul(mul(b.clone(), pow(a.clone(), lower)), da);
+    }
+    let ln_a = call(Func::Ln, a.clone());
+    if !a.uses(slot) {
+        // a^b ln(a) b'
+        return mul(mul(pow(a.clone(), b.clone()), ln_a), db);
+    }
+    // a^b (b' ln(a) + b a' / a)
+    let inner = add(mul(db, ln_a), div(mul(b.clone(), da), a.clone()));
+    mul(pow(a.clone(), b.clone()), inner)
+}
+
+impl Node {
+    pub fn derivative(&self, slot: usize) -> Node {
+        match self {
+            Node::Num(_) => num(0.0),
+            Node::Var(i) => num(if *i == slot { 1.0 } else { 0.0 }),
+            Node::Neg(a) => neg(a.derivative(slot)),
+            Node::Bin(op, a, b) => {
+                let (da, db) = (a.derivative(slot), b.derivative(slot));
+                let (a, b) = (a.as_ref(), b.as_ref());
+                match op {
+                    BinOp::Add => add(da, db),
+                    BinOp::Sub => sub(da, db),
+                    BinOp::Mul => add(mul(da, b.clone()), mul(a.clone(), db)),
+                    BinOp::Div if !b.uses(slot) => div(da, b.clone()),
+                    BinOp::Div => div(
+                        sub(mul(da, b.clone()), mul(a.clone(), db)),
+                        square(b.clone()),
+                    ),
+                    BinOp::Pow => power(a, b, da, db, slot),
+                }
+            }
+            Node::Call(f, args) => match args.as_slice() {
+                [a] => mul(unary(*f, a), a.derivative(slot)),
+                [a, b] => binary(*f, a, b, slot),
+                _ => num(f64::NAN),
+            },
+        }
+    }
+}
+
+// f'(u), to be multiplied by u'.
+fn unary(f: Func, u: &Node) -> Node {
+    let u = || u.clone();
+    match f {
+        Func::Sqrt => div(num(0.5), call(Func::Sqrt, u())),
+        Func::Cbrt => div(num(1.0), mul(num(3.0), square(call(Func::Cbrt, u())))),
+        Func::Exp => call(Func::Exp, u()),
+        Func::Ln => div(num(1.0), u()),
+        Func::Log => div(num(1.0), mul(u(), num(LN_10))),
+        Func::Log2 => div(num(1.0), mul(u(), num(LN_2))),
+        Func::Abs => call(Func::Sign, u()),
+        Func::Sign | Func::Floor | Func::Ceil | Func::Round => num(0.0),
+        Func::Sin => call(Func::Cos, u()),
+        Func::Cos => neg(call(Func::Sin, u())),
+        Func::Tan => square(call(Func::Sec, u())),
+        Func::Sec => mul(call(Func::Sec, u()), call(Func::Tan, u())),
+        Func::Csc => neg(mul(call(Func::Csc, u()), call(Func::Cot, u()))),
+        Func::Cot => neg(square(call(Func::Csc, u()))),
+        Func::Asin => div(num(1.0), call(Func::Sqrt, sub(num(1.0), square(u())))),
+        Func::Acos => div(num(-1.0), call(Func::Sqrt, sub(num(1.0), square(u())))),
+        Func::Atan => div(num(1.0), add(num(1.0), square(u()))),
+        Func::Sinh => call(Func::Cosh, u()),
+        Func::Cosh => call(Func::Sinh, u()),
+        Func::Tanh => div(num(1.0), square(call(Func::Cosh, u()))),
+        Func::Asinh => div(num(1.0), call(Func::Sqrt, add(square(u()), num(1.0)))),
+        Func::Acosh => div(num(1.0), call(Func::Sqrt, sub(square(u()), num(1.0)))),
+        Func::Atanh => div(num(1.0), sub(num(1.0), square(u()))),
+        _ => num(f64::NAN),
+    }
+}
+
+fn binary(f: Func, a: &Node, b: &Node, slot: usize) -> Node {
+    let (da, db) = (a.derivative(slot), b.derivative(slot));
+    match f {
+        // (b a' - a b') / (a^2 + b^2), for atan2(a, b)
+        Func::Atan2 => div(
+            sub(mul(b.clone(), da), mul(a.clone(), db)),
+            add(square(a.clone()), square(b.clone())),
+        ),
+        Func::Pow => power(a, b, da, db, slot),
+        // a - b floor(a / b)
+        Func::Mod => {
+            let whole = call(Func::Floor, div(a.clone(), b.clone()));
+            sub(da, mul(db, whole))
+        }
+        // min and max follow whichever argument they pick: with
+        // s = sign(a - b), (a' + b' -/+ s (a' - b')) / 2
+        Func::Min | Func::Max => {
+            let s = call(Func::Sign, sub(a.clone(), b.clone()));
+            let swing = mul(s, sub(da.clone(), db.clone()));
+            let sum = add(da, db);
+            let both = if f == Func::Min {
+                sub(sum, swing)
+            } else {
+                add(sum, swing)
+            };
+            div(both, num(2.0))
+        }
+        _ => num(f64::NAN),
+    }
+}
@@ -0,0 +1,226 @@
+// === Equations ===
+// An equation is parsed and compiled once, against the names of the
+// parameters (slider variables) it may use and any definitions. Plotting
+// then only supplies the parameter values, so dragging a slider
+// re-evaluates but never re-parses. "lhs = rhs" is plotted as the zero set
+// of lhs - rhs, unless one side is y (or x) alone and the other doesn't use
+// it: then it's a function of the other axis, and is sampled along it.
+// Parametric "(x(t), y(t))" and polar "r = f(θ)" curves are sampled along
+// their parameter, over 0..2π unless the line gives a range. Inequalities
+// are a region: each is turned around to read f > 0 (or f >= 0), and the
+// region of several is where the least of them is positive. An explicit
+// form keeps the tree of its function, which differentiates into another.
+
+use crate::ast::{BinOp, Func, Node};
+use crate::compile::Program;
+use crate::defs::Defs;
+use crate::error::Error;
+use crate::parse::{check_equals, lex, shape, Parser, Shape};
+use std::f64::consts::TAU;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    Implicit,
+    // y = f(x)
+    YOfX,
+    // x = g(y)
+    XOfY,
+    // (x(t), y(t))
+    Parametric,
+    // r = f(θ)
+    Polar,
+    // inequalities
+    Region,
+}
+
+#[wasm_bindgen]
+pub struct Equation {
+    tree: Node,
+    program: Program,
+    params: Vec<String>,
+    form: Form,
+    // f or g for an explicit form, x(t) and y(t) for a parametric curve,
+    // r(θ) for a polar one, one f per inequality of a region
+    parts: Vec<Program>,
+    // whether each inequality of a region is strict
+    strict: Vec<bool>,
+    // where a curve's parameter runs, over the parameters only
+    range: [Program; 2],
+    // the tree of f or g for an explicit form, to differentiate
+    f: Option<Node>,
+}
+
+// Which variable, if any, the equation gives as a function of the other.
+fn explicit(lhs: &Node, rhs: &Node) -> Option<(Form, Node)> {
+    for (side, other) in [(lhs, rhs), (rhs, lhs)] {
+        match side {
+            Node::Var(1) if !other.uses(1) => return Some((Form::YOfX, other.clone())),
+            Node::Var(0) if !other.uses(0) => return Some((Form::XOfY, other.clone())),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Equation {
+    pub fn parse(src: &str, params: &[String]) -> Result<Equation, Error> {
+        Equation::parse_with(src, params, &Defs::new())
+    }
+
+    pub fn parse_with(src: &str, params: &[String], defs: &Defs) -> Result<Equation, Error> {
+        let toks = lex(src)?;
+        check_equals(&toks)?;
+        let shape = shape(&toks, params);
+        let mut slots = shape.slots();
+        let nargs = slots.len();
+        slots.extend(params.iter().cloned());
+        let mut p = Parser::new(&toks, slots, defs, false);
+        let mut strict = Vec::new();
+        // curves have no implicit form
+        let (form, tree, parts, range) = match shape {
+            Shape::Equation => {
+                let (lhs, rhs) = p.equation()?;
+                let (form, parts) = match rhs.as_ref().and_then(|rhs| explicit(&lhs, rhs)) {
+                    Some((form, f)) => (form, vec![f]),
+                    None => (Form::Implicit, vec![]),
+                };
+                let tree = match rhs {
+                    Some(rhs) => Node::bin(BinOp::Sub, lhs, rhs),
+                    None => lhs,
+                };
+                (form, tree, parts, None)
+            }
+            Shape::Curve => {
+                let (x, y, range) = p.curve()?;
+                (Form::Parametric, Node::Num(f64::NAN), vec![x, y], range)
+            }
+            Shape::Polar => {
+                let (r, range) = p.polar()?;
+                (Form::Polar, Node::Num(f64::NAN), vec![r], range)
+            }
+            Shape::Region => {
+                let (parts, kinds): (Vec<Node>, Vec<bool>) = p.region()?.into_iter().unzip();
+                strict = kinds;
+                let tree = parts[1..].iter().fold(parts[0].clone(), |min, f| {
+                    Node::Call(Func::Min, vec![min, f.clone()])
+                });
+                (Form::Region, tree, parts, None)
+            }
+        };
+        let (lo, hi) = range.unwrap_or((Node::Num(0.0), Node::Num(TAU)));
+        let f = match form {
+            Form::YOfX | Form::XOfY => Some(parts[0].clone()),
+            _ => None,
+        };
+        Ok(Equation {
+            program: Program::new(&tree, nargs),
+            tree,
+            params: params.to_vec(),
+            form,
+            parts: parts.iter().map(|n| Program::new(n, nargs)).collect(),
+            strict,
+            range: [Program::new(&lo, nargs), Program::new(&hi, nargs)],
+            f,
+        })
+    }
+
+    // "y = f'(x)" for "y = f(x)", "x = g'(y)" for "x = g(y)", with the same
+    // parameters; None for any other form.
+    pub(crate) fn differentiate(&self) -> Option<Equation> {
+        let f = self.f.as_ref()?;
+        // the slot of the free axis, and of the one it gives
+        let (slot, of) = if self.form == Form::YOfX {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        let d = f.derivative(slot);
+        let tree = Node::bin(BinOp::Sub, Node::Var(of), d.clone());
+        Some(Equation {
+            program: Program::new(&tree, 2),
+            tree,
+            params: self.params.clone(),
+            form: self.form,
+            parts: vec![Program::new(&d, 2)],
+            strict: vec![],
+            range: self.range.clone(),
+            f: Some(d),
+        })
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    // lhs - rhs, over slots x, y and then the parameters; for a region, the
+    // least f. NaN for a parametric or polar curve.
+    pub fn tree(&self) -> &Node {
+        &self.tree
+    }
+
+    // `values` line up with the parameter names given to `parse`. NaN where
+    // the expression is undefined.
+    pub fn eval(&self, x: f64, y: f64, values: &[f64]) -> f64 {
+        self.program.eval(&[x, y], values)
+    }
+
+    pub fn form(&self) -> Form {
+        self.form
+    }
+
+    // y at x for "y = f(x)", x at y for "x = g(y)"; NaN for any other
+    // form.
+    pub fn solve(&self, t: f64, values: &[f64]) -> f64 {
+        match self.form {
+            Form::YOfX | Form::XOfY => self.parts[0].eval(&[t, t], values),
+            _ => f64::NAN,
+        }
+    }
+
+    // Whether the equation is a parametric or polar curve, plotted by
+    // `point` over `range` rather than by `eval`.
+    pub fn is_curve(&self) -> bool {
+        matches!(self.form, Form::Parametric | Form::Polar)
+    }
+
+    // The world point at parameter `t`: x (or y) for an explicit form, t or
+    // θ for a curve. NaN for an implicit equation or a region.
+    pub fn point(&self, t: f64, values: &[f64]) -> (f64, f64) {
+        match self.form {
+            Form::Implicit | Form::Region => (f64::NAN, f64::NAN),
+            Form::YOfX => (t, self.solve(t, values)),
+            Form::XOfY => (self.solve(t, values), t),
+            Form::Parametric => (
+                self.parts[0].eval(&[t], values),
+                self.parts[1].eval(&[t], values),
+            ),
+            Form::Polar => {
+                let r = self.parts[0].eval(&[t, t], values);
+                (r * t.cos(), r * t.sin())
+            }
+        }
+    }
+
+    // Whether the edge of a region at (x, y) belongs to a strict inequality,
+    // and so isn't part of the region. That's the inequality nearest to
+    // failing there.
+    pub fn strict_at(&self, x: f64, y: f64, values: &[f64]) -> bool {
+        let least = self
+            .parts
+            .iter()
+            .map(|f| f.eval(&[x, y], values))
+            .zip(&self.strict)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        matches!(least, Some((_, true)))
+    }
+
+    // Where a curve's parameter runs, for the given parameter values.
+    pub fn range(&self, values: &[f64]) -> (f64, f64) {
+        (
+            self.range[0].eval(&[], values),
+            self.range[1].eval(&[], values),
+        )
+    }
+}
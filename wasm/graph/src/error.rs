@@ -0,0 +1,59 @@
+// === Errors ===
+// Everything that can go wrong between the text box and a compiled
+// equation. Thrown to JS as an `EquationError`.
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax,
+    UnknownFunction,
+    UnknownVariable,
+    MultipleEquals,
+}
+
+// `start..end` counts characters of the source, so the frontend can
+// underline the offending part. An empty span points between characters,
+// e.g. at the end of the input for a missing ")".
+#[wasm_bindgen(js_name = EquationError, getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    #[wasm_bindgen(readonly)]
+    pub kind: ErrorKind,
+    #[wasm_bindgen(readonly)]
+    pub start: usize,
+    #[wasm_bindgen(readonly)]
+    pub end: usize,
+    #[wasm_bindgen(readonly)]
+    pub message: String,
+}
+
+impl Error {
+    pub(crate) fn new(
+        kind: ErrorKind,
+        start: usize,
+        end: usize,
+        message: impl Into<String>,
+    ) -> Error {
+        Error {
+            kind,
+            start,
+            end,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn syntax(start: usize, end: usize, message: impl Into<String>) -> Error {
+        Error::new(ErrorKind::Syntax, start, end, message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,385 @@
+// === Intersections ===
+// Where two curves cross. Between two implicit curves f = 0 and g = 0, the
+// cells of the view where both f and g change sign are candidates; a
+// candidate is quartered while its quarters still hold both sign changes,
+// which drops curves that only pass near each other, and the survivors seed
+// Newton's method on (f, g). A parametric curve p(t) meets g = 0 where
+// g(p(t)) changes sign along it, and two parametric curves where their
+// sampled polylines cross, refined by Newton's method on p(t) - q(s). A point
+// is kept only if the refinement converges close to where it started, so
+// asymptotes, where a function flips sign without a root, don't count.
+
+use wasm_bindgen::prelude::*;
+
+// Candidates are refined to cells this small (in pixels) before Newton.
+const MIN_CELL: f64 = 1.0;
+// Points closer than this (in pixels) are the same intersection.
+const SAME: f64 = 0.5;
+// Steps of the parameter a curve is searched in.
+const SAMPLES: usize = 2048;
+
+// Flat [x, y, ...] in world and in pixel coordinates, in the same order.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Intersections {
+    world: Vec<f64>,
+    pixels: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl Intersections {
+    pub fn len(&self) -> u32 {
+        (self.world.len() / 2) as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.world.is_empty()
+    }
+
+    pub fn get_world(&self) -> Vec<f64> {
+        self.world.clone()
+    }
+
+    pub fn get_pixels(&self) -> Vec<f64> {
+        self.pixels.clone()
+    }
+}
+
+// A w x h pixel view with the world origin at (center_x, center_y).
+#[derive(Clone, Copy)]
+pub(crate) struct View {
+    pub w: f64,
+    pub h: f64,
+    pub scale: f64,
+    pub center_x: f64,
+    pub center_y: f64,
+}
+
+impl View {
+    fn world(&self, px: f64, py: f64) -> (f64, f64) {
+        (
+            (px - self.center_x) / self.scale,
+            (self.center_y - py) / self.scale,
+        )
+    }
+
+    fn pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.center_x + x * self.scale,
+            self.center_y - y * self.scale,
+        )
+    }
+}
+
+// Points found so far, in world coordinates.
+struct Found {
+    view: View,
+    points: Vec<(f64, f64)>,
+}
+
+impl Found {
+    fn new(view: View) -> Found {
+        Found {
+            view,
+            points: Vec::new(),
+        }
+    }
+
+    // Keeps (x, y) if it's in the view and not one already found.
+    fn add(&mut self, x: f64, y: f64) {
+        let (px, py) = self.view.pixel(x, y);
+        if !(0.0..=self.view.w).contains(&px) || !(0.0..=self.view.h).contains(&py) {
+            return;
+        }
+        let same = SAME / self.view.scale;
+        if !self
+            .points
+            .iter()
+            .any(|p| (p.0 - x).abs() < same && (p.1 - y).abs() < same)
+        {
+            self.points.push((x, y));
+        }
+    }
+
+    fn finish(self) -> Intersections {
+        let mut out = Intersections::default();
+        for (x, y) in self.points {
+            let (px, py) = self.view.pixel(x, y);
+            out.world.extend_from_slice(&[x, y]);
+            out.pixels.extend_from_slice(&[px, py]);
+        }
+        out
+    }
+}
+
+// Newton's method on a map of two variables to two values, from (a, b),
+// with a central-difference Jacobian. `da` and `db` are how far each
+// variable may move (a cell, a sample step); it gives up past 1.5 times
+// that.
+fn newton(
+    func: impl Fn(f64, f64) -> (f64, f64),
+    a0: f64,
+    b0: f64,
+    da: f64,
+    db: f64,
+) -> Option<(f64, f64)> {
+    let (ha, hb) = (da * 1e-4, db * 1e-4);
+    let (mut a, mut b) = (a0, b0);
+    for _ in 0..40 {
+        let (f, g) = func(a, b);
+        let (fa1, ga1) = func(a + ha, b);
+        let (fa0, ga0) = func(a - ha, b);
+        let (fb1, gb1) = func(a, b + hb);
+        let (fb0, gb0) = func(a, b - hb);
+        let (fa, ga) = ((fa1 - fa0) / (2.0 * ha), (ga1 - ga0) / (2.0 * ha));
+        let (fb, gb) = ((fb1 - fb0) / (2.0 * hb), (gb1 - gb0) / (2.0 * hb));
+        let det = fa * gb - fb * ga;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let step_a = (f * gb - fb * g) / det;
+        let step_b = (fa * g - f * ga) / det;
+        a -= step_a;
+        b -= step_b;
+        if !a.is_finite() || !b.is_finite() {
+            return None;
+        }
+        if (a - a0).abs() > da * 1.5 || (b - b0).abs() > db * 1.5 {
+            return None;
+        }
+        if step_a.abs() < da * 1e-9 && step_b.abs() < db * 1e-9 {
+            return Some((a, b));
+        }
+    }
+    None
+}
+
+// True if the corners have both signs; NaN corners never do.
+fn crosses(v: [f64; 4]) -> bool {
+    if v.iter().any(|v| v.is_nan()) {
+        return false;
+    }
+    let positive = v.iter().filter(|&&v| v > 0.0).count();
+    positive != 0 && positive != 4
+}
+
+struct Search<F, G> {
+    f: F,
+    g: G,
+    view: View,
+    found: Found,
+}
+
+impl<F: Fn(f64, f64) -> f64, G: Fn(f64, f64) -> f64> Search<F, G> {
+    fn corners(&self, func: impl Fn(f64, f64) -> f64, px: f64, py: f64, size: f64) -> [f64; 4] {
+        [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)].map(|(dx, dy)| {
+            let (x, y) = self.view.world(px + dx, py + dy);
+            func(x, y)
+        })
+    }
+
+    fn cell(&mut self, px: f64, py: f64, size: f64) {
+        if !crosses(self.corners(&self.f, px, py, size))
+            || !crosses(self.corners(&self.g, px, py, size))
+        {
+            return;
+        }
+        if size > MIN_CELL {
+            let half = size / 2.0;
+            for (dx, dy) in [(0.0, 0.0), (half, 0.0), (half, half), (0.0, half)] {
+                self.cell(px + dx, py + dy, half);
+            }
+            return;
+        }
+        let (x, y) = self.view.world(px + size / 2.0, py + size / 2.0);
+        let cell = size / self.view.scale;
+        let (f, g) = (&self.f, &self.g);
+        if let Some((x, y)) = newton(|x, y| (f(x, y), g(x, y)), x, y, cell, cell) {
+            self.found.add(x, y);
+        }
+    }
+}
+
+// Crossings of f = 0 and g = 0 in the view. `step` is the size of the
+// first candidate cells, in pixels.
+pub(crate) fn intersect(
+    f: impl Fn(f64, f64) -> f64,
+    g: impl Fn(f64, f64) -> f64,
+    view: View,
+    step: i32,
+) -> Intersections {
+    let mut s = Search {
+        f,
+        g,
+        view,
+        found: Found::new(view),
+    };
+    let step = step.max(1);
+    for py in (0..view.h as i32).step_by(step as usize) {
+        for px in (0..view.w as i32).step_by(step as usize) {
+            s.cell(px as f64, py as f64, step as f64);
+        }
+    }
+    s.found.finish()
+}
+
+pub(crate) fn valid((t0, t1): (f64, f64)) -> bool {
+    t0 < t1 && t0.is_finite() && t1.is_finite()
+}
+
+// Where the curve `p` (t from t0 to t1) crosses g = 0 in the view.
+pub(crate) fn intersect_curve(
+    p: impl Fn(f64) -> (f64, f64),
+    (t0, t1): (f64, f64),
+    g: impl Fn(f64, f64) -> f64,
+    view: View,
+) -> Intersections {
+    let mut found = Found::new(view);
+    if !valid((t0, t1)) {
+        return found.finish();
+    }
+    let h = |t: f64| {
+        let (x, y) = p(t);
+        g(x, y)
+    };
+    let dt = (t1 - t0) / SAMPLES as f64;
+    let (mut a, mut ha) = (t0, h(t0));
+    if ha == 0.0 {
+        let (x, y) = p(a);
+        found.add(x, y);
+    }
+    for i in 1..=SAMPLES {
+        let b = if i == SAMPLES { t1 } else { t0 + dt * i as f64 };
+        let hb = h(b);
+        let root = if hb == 0.0 {
+            Some(b)
+        } else if ha * hb < 0.0 {
+            bisect(h, a, ha, b, hb)
+        } else {
+            None
+        };
+        if let Some(t) = root {
+            let (x, y) = p(t);
+            found.add(x, y);
+        }
+        (a, ha) = (b, hb);
+    }
+    found.finish()
+}
+
+// Narrows a sign change of `h` on a..b down to the precision of t. A root
+// ends with h near zero; a pole, where h flips sign through infinity, ends
+// with it bigger than at either end, and isn't one.
+pub(crate) fn bisect(
+    h: impl Fn(f64) -> f64,
+    mut a: f64,
+    mut ha: f64,
+    mut b: f64,
+    hb: f64,
+) -> Option<f64> {
+    let bound = ha.abs().max(hb.abs());
+    loop {
+        let m = a + (b - a) / 2.0;
+        if m <= a || m >= b {
+            break;
+        }
+        let hm = h(m);
+        if hm.is_nan() {
+            return None;
+        }
+        if hm == 0.0 {
+            return Some(m);
+        }
+        if (hm < 0.0) == (ha < 0.0) {
+            (a, ha) = (m, hm);
+        } else {
+            b = m;
+        }
+    }
+    (ha.abs() <= bound).then_some(a)
+}
+
+// The curve sampled in `SAMPLES` steps, as (t, pixel) pairs.
+fn samples(
+    p: impl Fn(f64) -> (f64, f64),
+    (t0, t1): (f64, f64),
+    view: View,
+) -> Vec<(f64, (f64, f64))> {
+    (0..=SAMPLES)
+        .map(|i| {
+            let t = t0 + (t1 - t0) * i as f64 / SAMPLES as f64;
+            let (x, y) = p(t);
+            (t, view.pixel(x, y))
+        })
+        .collect()
+}
+
+// Where segments a-b and c-d cross, as fractions along each.
+fn segments_cross(
+    a: (f64, f64),
+    b: (f64, f64),
+    c: (f64, f64),
+    d: (f64, f64),
+) -> Option<(f64, f64)> {
+    let (r, s) = ((b.0 - a.0, b.1 - a.1), (d.0 - c.0, d.1 - c.1));
+    let den = r.0 * s.1 - r.1 * s.0;
+    if den == 0.0 || !den.is_finite() {
+        return None;
+    }
+    let (qp0, qp1) = (c.0 - a.0, c.1 - a.1);
+    let u = (qp0 * s.1 - qp1 * s.0) / den;
+    let v = (qp0 * r.1 - qp1 * r.0) / den;
+    ((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)).then_some((u, v))
+}
+
+// Where the curves `p` and `q` cross in the view.
+pub(crate) fn intersect_curves(
+    p: impl Fn(f64) -> (f64, f64),
+    p_range: (f64, f64),
+    q: impl Fn(f64) -> (f64, f64),
+    q_range: (f64, f64),
+    view: View,
+) -> Intersections {
+    let mut found = Found::new(view);
+    if !valid(p_range) || !valid(q_range) {
+        return found.finish();
+    }
+    let (ps, qs) = (samples(&p, p_range, view), samples(&q, q_range, view));
+    let bounds =
+        |a: (f64, f64), b: (f64, f64)| (a.0.min(b.0), a.0.max(b.0), a.1.min(b.1), a.1.max(b.1));
+    let visible = |(x0, x1, y0, y1): (f64, f64, f64, f64)| {
+        x1 >= 0.0 && x0 <= view.w && y1 >= 0.0 && y0 <= view.h
+    };
+    // only segments that reach into the view
+    let segs = |s: &[(f64, (f64, f64))]| -> Vec<usize> {
+        (0..s.len() - 1)
+            .filter(|&i| visible(bounds(s[i].1, s[i + 1].1)))
+            .collect()
+    };
+    let (pi, qi) = (segs(&ps), segs(&qs));
+    let (dt, ds) = (ps[1].0 - ps[0].0, qs[1].0 - qs[0].0);
+    let diff = |t: f64, s: f64| {
+        let (a, b) = (p(t), q(s));
+        (a.0 - b.0, a.1 - b.1)
+    };
+    for &i in &pi {
+        let (a, b) = (ps[i].1, ps[i + 1].1);
+        let (ax0, ax1, ay0, ay1) = bounds(a, b);
+        for &j in &qi {
+            let (c, d) = (qs[j].1, qs[j + 1].1);
+            let (cx0, cx1, cy0, cy1) = bounds(c, d);
+            if ax1 < cx0 || cx1 < ax0 || ay1 < cy0 || cy1 < ay0 {
+                continue;
+            }
+            let Some((u, v)) = segments_cross(a, b, c, d) else {
+                continue;
+            };
+            let (t, s) = (ps[i].0 + u * dt, qs[j].0 + v * ds);
+            if let Some((t, _)) = newton(diff, t, s, dt, ds) {
+                let (x, y) = p(t);
+                found.add(x, y);
+            }
+        }
+    }
+    found.finish()
+}
@@ -1,7 +1,31 @@
-use meval::Expr;
+use analysis::{analyze, integrate};
+use contour::march;
+use curve::sample;
+use intersect::{intersect, intersect_curve, intersect_curves, valid, View};
 use std::f64;
 use wasm_bindgen::prelude::*;
 
+mod analysis;
+mod ast;
+mod compile;
+mod contour;
+mod curve;
+mod defs;
+mod diff;
+mod equation;
+mod error;
+mod intersect;
+mod parse;
+mod polyline;
+
+pub use analysis::{Analysis, Integral};
+pub use ast::{BinOp, Func, Node};
+pub use defs::Defs;
+pub use equation::{Equation, Form};
+pub use error::{Error, ErrorKind};
+pub use intersect::Intersections;
+pub use polyline::Polylines;
+
 #[wasm_bindgen(start)]
 pub fn main_js() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
@@ -10,8 +34,11 @@ pub fn main_js() -> Result<(), JsValue> {
 
 #[wasm_bindgen]
 pub struct PlotResult {
-    lines: Vec<f64>,         // [x1, y1, x2, y2, ...]
-    intersections: Vec<f64>, // [x, y, ...]
+    lines: Vec<f64>, // [x1, y1, x2, y2, ...]
+    // a region's edge where it isn't part of the region, like `lines`
+    dashed: Vec<f64>,
+    // a region's inside, as tiles and polygons along the edge
+    fill: Polylines,
 }
 
 #[wasm_bindgen]
@@ -20,7 +47,8 @@ impl PlotResult {
     pub fn new() -> PlotResult {
         PlotResult {
             lines: Vec::new(),
-            intersections: Vec::new(),
+            dashed: Vec::new(),
+            fill: Polylines::new(),
         }
     }
 
@@ -28,20 +56,39 @@ impl PlotResult {
         self.lines.clone()
     }
 
-    pub fn get_intersections(&self) -> Vec<f64> {
-        self.intersections.clone()
+    // The segments joined into polylines, simplified to within `epsilon`
+    // pixels (0 keeps every point).
+    pub fn polylines(&self, epsilon: f64) -> Polylines {
+        Polylines::stitch(&self.lines, epsilon)
+    }
+
+    pub fn get_dashed(&self) -> Vec<f64> {
+        self.dashed.clone()
+    }
+
+    // `dashed` joined like `polylines`.
+    pub fn dashed_polylines(&self, epsilon: f64) -> Polylines {
+        Polylines::stitch(&self.dashed, epsilon)
+    }
+
+    // Every polygon is closed; together they cover the region without
+    // overlapping.
+    pub fn fill(&self) -> Polylines {
+        self.fill.clone()
     }
 }
 
-fn get_t(v1: f64, v2: f64) -> f64 {
-    if (v2 - v1).abs() < 1e-9 {
-        0.5
-    } else {
-        (0.0 - v1) / (v2 - v1)
+impl Default for PlotResult {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+// One-shot form of `Equation::plot`: parses `eq_str` on every call. Throws
+// an `EquationError` on a parse error or on names that aren't x, y or listed
+// in `vars_names`.
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments, clippy::boxed_local)]
 pub fn plot_equation(
     eq_str: &str,
     w: i32,
@@ -49,195 +96,201 @@ pub fn plot_equation(
     scale: f64,
     center_x: i32,
     center_y: i32,
-    step: i32, // LOD Step
-    collision_buffer: &mut [i8],
-    eq_id: i8,
-    _vars_names: Box<[JsValue]>, // List of variable names ["a", "b"]
-    _vars_values: Box<[f64]>,    // List of values [1.0, -0.5]
-) -> PlotResult {
-    let mut result = PlotResult::new();
-
-    let clean_eq = eq_str.to_string();
-
-    let parts: Vec<&str> = clean_eq.splitn(2, '=').collect();
-    let expr_str = if parts.len() > 1 {
-        format!("({}) - ({})", parts[0], parts[1])
-    } else {
-        parts[0].to_string()
-    };
-
-    let expr: Expr = match expr_str.parse() {
-        Ok(e) => e,
-        Err(_) => return result,
-    };
-
-    let func = match expr.bind2("x", "y") {
-        Ok(f) => f,
-        Err(_) => return result,
-    };
-
-    let step_f = step as f64;
-    let grid_cols = (w / step) + 2;
-
-    let mut current_row_vals: Vec<f64> = vec![0.0; grid_cols as usize];
-    let mut next_row_vals: Vec<f64> = vec![0.0; grid_cols as usize];
-
-    for i in 0..grid_cols {
-        let px = i * step;
-        let wx = (px - center_x) as f64 / scale;
-        let wy = (center_y - 0) as f64 / scale;
-        current_row_vals[i as usize] = func(wx, wy);
-    }
-
-    let mut py = 0;
-    while py < h {
-        let next_py = py + step;
-        let wy_next = (center_y - next_py) as f64 / scale;
-        for i in 0..grid_cols {
-            let px = i * step;
-            let wx = (px - center_x) as f64 / scale;
-            next_row_vals[i as usize] = func(wx, wy_next);
-        }
+    step: i32,                  // LOD Step
+    min_step: i32,              // smallest cell near the curve
+    vars_names: Box<[JsValue]>, // List of variable names ["a", "b"]
+    vars_values: Box<[f64]>,    // List of values [1.0, -0.5]
+) -> Result<PlotResult, Error> {
+    let names: Vec<String> = vars_names.iter().filter_map(|v| v.as_string()).collect();
+    let eq = Equation::parse(eq_str, &names)?;
+    Ok(eq.plot(
+        w,
+        h,
+        scale,
+        center_x,
+        center_y,
+        step,
+        min_step,
+        &vars_values,
+    ))
+}
 
-        let mut px = 0;
-        let mut i = 0;
-        while px < w {
-            let tl = current_row_vals[i];
-            let tr = current_row_vals[i + 1];
-            let bl = next_row_vals[i];
-            let br = next_row_vals[i + 1];
-
-            if tl.is_nan() || tr.is_nan() || bl.is_nan() || br.is_nan() {
-                px += step;
-                i += 1;
-                continue;
-            }
+#[wasm_bindgen]
+impl Equation {
+    // `params` names the slider variables the equation may use.
+    #[wasm_bindgen(constructor)]
+    pub fn new(src: &str, params: Vec<String>) -> Result<Equation, Error> {
+        Equation::parse(src, &params)
+    }
 
-            let mut case = 0;
-            if tl > 0.0 {
-                case |= 8;
-            }
-            if tr > 0.0 {
-                case |= 4;
-            }
-            if br > 0.0 {
-                case |= 2;
-            }
-            if bl > 0.0 {
-                case |= 1;
-            }
+    // Like `new`, for an equation that may use the names in `defs`.
+    pub fn with_defs(src: &str, params: Vec<String>, defs: &Defs) -> Result<Equation, Error> {
+        Equation::parse_with(src, &params, defs)
+    }
 
-            if case != 0 && case != 15 {
-                let off_top = get_t(tl, tr) * step_f;
-                let off_right = get_t(tr, br) * step_f;
-                let off_bottom = get_t(bl, br) * step_f;
-                let off_left = get_t(tl, bl) * step_f;
-
-                let p_top_x = px as f64 + off_top;
-                let p_top_y = py as f64;
-
-                let p_right_x = px as f64 + step_f;
-                let p_right_y = py as f64 + off_right;
-
-                let p_bottom_x = px as f64 + off_bottom;
-                let p_bottom_y = py as f64 + step_f;
-
-                let p_left_x = px as f64;
-                let p_left_y = py as f64 + off_left;
-
-                let wx_center = ((px + step / 2) - center_x) as f64 / scale;
-                let wy_center = (center_y - (py + step / 2)) as f64 / scale;
-                let center_val = func(wx_center, wy_center);
-
-                match case {
-                    1 => push_line(
-                        &mut result.lines,
-                        p_left_x,
-                        p_left_y,
-                        p_bottom_x,
-                        p_bottom_y,
-                    ),
-                    2 => push_line(
-                        &mut result.lines,
-                        p_bottom_x,
-                        p_bottom_y,
-                        p_right_x,
-                        p_right_y,
-                    ),
-                    3 => push_line(&mut result.lines, p_left_x, p_left_y, p_right_x, p_right_y),
-                    4 => push_line(&mut result.lines, p_top_x, p_top_y, p_right_x, p_right_y),
-                    5 => {
-                        if center_val > 0.0 {
-                            push_line(&mut result.lines, p_left_x, p_left_y, p_bottom_x, p_bottom_y);
-                            push_line(&mut result.lines, p_top_x, p_top_y, p_right_x, p_right_y);
-                        } else {
-                            push_line(&mut result.lines, p_left_x, p_left_y, p_top_x, p_top_y);
-                            push_line(&mut result.lines, p_bottom_x, p_bottom_y, p_right_x, p_right_y);
-                        }
-                    }
-                    6 => push_line(&mut result.lines, p_top_x, p_top_y, p_bottom_x, p_bottom_y),
-                    7 => push_line(&mut result.lines, p_left_x, p_left_y, p_top_x, p_top_y),
-                    8 => push_line(&mut result.lines, p_left_x, p_left_y, p_top_x, p_top_y),
-                    9 => push_line(&mut result.lines, p_top_x, p_top_y, p_bottom_x, p_bottom_y),
-                    10 => {
-                        if center_val > 0.0 {
-                            push_line(&mut result.lines, p_left_x, p_left_y, p_top_x, p_top_y);
-                            push_line(&mut result.lines, p_bottom_x, p_bottom_y, p_right_x, p_right_y);
-                        } else {
-                            push_line(&mut result.lines, p_left_x, p_left_y, p_bottom_x, p_bottom_y);
-                            push_line(&mut result.lines, p_top_x, p_top_y, p_right_x, p_right_y);
-                        }
-                    }
-                    11 => push_line(&mut result.lines, p_top_x, p_top_y, p_right_x, p_right_y),
-                    12 => push_line(&mut result.lines, p_left_x, p_left_y, p_right_x, p_right_y),
-                    13 => push_line(
-                        &mut result.lines,
-                        p_bottom_x,
-                        p_bottom_y,
-                        p_right_x,
-                        p_right_y,
-                    ),
-                    14 => push_line(
-                        &mut result.lines,
-                        p_left_x,
-                        p_left_y,
-                        p_bottom_x,
-                        p_bottom_y,
-                    ),
-                    _ => {}
+    // Plots the equation over a w x h pixel view. Implicit equations and
+    // regions are contoured in `step` cells refined down to `min_step` near
+    // the curve; explicit ones are sampled every `step` pixels along their
+    // axis, and curves in 2048 / `step` pieces of their range, both refined
+    // well below `min_step`. `values` holds the current parameter values,
+    // in the order the names were given.
+    #[allow(clippy::too_many_arguments)]
+    pub fn plot(
+        &self,
+        w: i32,
+        h: i32,
+        scale: f64,
+        center_x: i32,
+        center_y: i32,
+        step: i32,     // LOD Step
+        min_step: i32, // smallest cell near the curve
+        values: &[f64],
+    ) -> PlotResult {
+        let (cx, cy) = (center_x as f64, center_y as f64);
+        let step = step.max(1);
+        // as a fraction of a step
+        let fine = min_step.clamp(1, step) as f64 / (16.0 * step as f64);
+        let pixel = |(x, y): (f64, f64)| (cx + x * scale, cy - y * scale);
+        match self.form() {
+            Form::Implicit => march(
+                |x, y| self.eval(x, y, values),
+                w,
+                h,
+                scale,
+                center_x,
+                center_y,
+                step,
+                min_step,
+                false,
+            ),
+            Form::Region => {
+                let mut result = march(
+                    |x, y| self.eval(x, y, values),
+                    w,
+                    h,
+                    scale,
+                    center_x,
+                    center_y,
+                    step,
+                    min_step,
+                    true,
+                );
+                let lines = std::mem::take(&mut result.lines);
+                for l in lines.chunks_exact(4) {
+                    let (x, y) = ((l[0] + l[2]) / 2.0, (l[1] + l[3]) / 2.0);
+                    let out = if self.strict_at((x - cx) / scale, (cy - y) / scale, values) {
+                        &mut result.dashed
+                    } else {
+                        &mut result.lines
+                    };
+                    out.extend_from_slice(l);
                 }
-
-                let center_px = px + (step / 2);
-                let center_py = py + (step / 2);
-                let buf_w = (w + step - 1) / step;
-                let bx = center_px / step;
-                let by = center_py / step;
-                let idx = (by * buf_w + bx) as usize;
-
-                if idx < collision_buffer.len() {
-                    let existing = collision_buffer[idx];
-                    if existing != 0 && existing != eq_id {
-                        result.intersections.push(center_px as f64);
-                        result.intersections.push(center_py as f64);
-                    }
-                    collision_buffer[idx] = eq_id;
+                result
+            }
+            Form::YOfX => sample(
+                |px| (px, cy - self.solve((px - cx) / scale, values) * scale),
+                0.0,
+                w as f64,
+                ((w + step - 1) / step) as usize,
+                step as f64 * fine,
+                w,
+                h,
+            ),
+            Form::XOfY => sample(
+                |py| (cx + self.solve((cy - py) / scale, values) * scale, py),
+                0.0,
+                h as f64,
+                ((h + step - 1) / step) as usize,
+                step as f64 * fine,
+                w,
+                h,
+            ),
+            Form::Parametric | Form::Polar => {
+                let (t0, t1) = self.range(values);
+                if !valid((t0, t1)) {
+                    return PlotResult::new();
                 }
+                let pieces = (2048 / step).max(1) as usize;
+                sample(
+                    |t| pixel(self.point(t, values)),
+                    t0,
+                    t1,
+                    pieces,
+                    (t1 - t0) / pieces as f64 * fine,
+                    w,
+                    h,
+                )
             }
-
-            px += step;
-            i += 1;
         }
+    }
 
-        std::mem::swap(&mut current_row_vals, &mut next_row_vals);
-        py += step;
+    pub fn get_form(&self) -> Form {
+        self.form()
     }
 
-    result
-}
+    // "y = f'(x)" for "y = f(x)" (or "x = g'(y)" for "x = g(y)"), as an
+    // equation to plot like any other, taking the same `values`. Undefined
+    // for any other form.
+    pub fn derivative(&self) -> Option<Equation> {
+        self.differentiate()
+    }
+
+    // Zeros, minima, maxima and inflection points of an explicit equation
+    // where its axis runs from t0 to t1, like the visible range. Undefined
+    // for any other form.
+    pub fn analyze(&self, values: &[f64], t0: f64, t1: f64) -> Option<Analysis> {
+        analyze(self, values, t0, t1)
+    }
+
+    // The integral of an explicit equation along its axis from a to b, with
+    // the region between the curve and the axis in view pixels. Undefined
+    // for any other form.
+    pub fn integrate(
+        &self,
+        values: &[f64],
+        a: f64,
+        b: f64,
+        scale: f64,
+        center_x: i32,
+        center_y: i32,
+    ) -> Option<Integral> {
+        integrate(self, values, a, b, scale, center_x as f64, center_y as f64)
+    }
 
-fn push_line(lines: &mut Vec<f64>, x1: f64, y1: f64, x2: f64, y2: f64) {
-    lines.push(x1);
-    lines.push(y1);
-    lines.push(x2);
-    lines.push(y2);
+    // Where this curve crosses `other` in the view, solved to full
+    // precision rather than read off the plotting grid. `step` is the grid
+    // two implicit curves are searched on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn intersections(
+        &self,
+        values: &[f64],
+        other: &Equation,
+        other_values: &[f64],
+        w: i32,
+        h: i32,
+        scale: f64,
+        center_x: i32,
+        center_y: i32,
+        step: i32,
+    ) -> Intersections {
+        let view = View {
+            w: w as f64,
+            h: h as f64,
+            scale,
+            center_x: center_x as f64,
+            center_y: center_y as f64,
+        };
+        let f = |x, y| self.eval(x, y, values);
+        let g = |x, y| other.eval(x, y, other_values);
+        let p = |t| self.point(t, values);
+        let q = |t| other.point(t, other_values);
+        match (self.is_curve(), other.is_curve()) {
+            (false, false) => intersect(f, g, view, step),
+            (true, false) => intersect_curve(p, self.range(values), g, view),
+            (false, true) => intersect_curve(q, other.range(other_values), f, view),
+            (true, true) => {
+                intersect_curves(p, self.range(values), q, other.range(other_values), view)
+            }
+        }
+    }
 }
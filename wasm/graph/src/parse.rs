@@ -0,0 +1,725 @@
+// === Parser ===
+// Hand-written recursive descent over a small math grammar:
+//
+//   equation := expr ("=" expr)?
+//   curve    := "(" expr "," expr ")" range?      parametric, in t
+//   polar    := ("r" "=" expr | expr "=" "r") range?      in theta
+//   range    := "," expr ("<" | "<=") name ("<" | "<=") expr
+//   region   := chain ("and" chain)*
+//   chain    := expr (("<" | "<=" | ">" | ">=") expr)+
+//   expr     := term (("+" | "-") term)*
+//   term     := unary (("*" | "/") unary | power)*    juxtaposition multiplies
+//   unary    := ("-" | "+") unary | power
+//   power    := primary ("^" unary)?
+//   primary  := number | name | name "(" args ")" | name "|" expr "|"
+//             | "(" expr ")" | "|" expr "|"
+//   number   := digits ("." digits)? (("e" | "E") ("+" | "-")? digits)?
+//
+// Names are resolved while parsing, against the slots (x, y, parameters,
+// function arguments), the user's definitions and the built-ins. A run of
+// letters that isn't a name as a whole is split into the longest names it
+// starts with, so "2xy" is 2*x*y and "xsin(x)" is x*sin(x). Names with a
+// subscript ("a_1", "v_max") are never split.
+//
+// Which of equation, curve, polar and region a line is decides what x, y,
+// t and theta mean in it, so it's told apart by `shape` before names are
+// resolved. "and" is a keyword, never a product of a, n and d.
+
+use crate::ast::{constant, BinOp, Func, Node};
+use crate::defs::Defs;
+use crate::error::{Error, ErrorKind};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Tok {
+    Num(f64),
+    Name(String),
+    Op(char),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Token {
+    pub tok: Tok,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn is(&self, c: char) -> bool {
+        self.tok == Tok::Op(c)
+    }
+
+    fn is_and(&self) -> bool {
+        matches!(&self.tok, Tok::Name(n) if n == "and")
+    }
+
+    // For "<", "<=", ">" and ">=": whether it reads "less", and whether
+    // it's strict.
+    fn relation(&self) -> Option<(bool, bool)> {
+        match self.tok {
+            Tok::Op('<') => Some((true, true)),
+            Tok::Op('≤') => Some((true, false)),
+            Tok::Op('>') => Some((false, true)),
+            Tok::Op('≥') => Some((false, false)),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn lex(src: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+        let tok = if c.is_whitespace() {
+            i += 1;
+            continue;
+        } else if digit(i) || (c == '.' && digit(i + 1)) {
+            while digit(i) {
+                i += 1;
+            }
+            if chars.get(i) == Some(&'.') {
+                i += 1;
+                while digit(i) {
+                    i += 1;
+                }
+            }
+            // an exponent needs its digits, so "2e" and "2ex" stay products
+            let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+            if matches!(chars.get(i), Some('e' | 'E')) && digit(i + 1 + sign) {
+                i += 1 + sign;
+                while digit(i) {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            Tok::Num(text.parse().unwrap_or(f64::NAN))
+        } else if c.is_alphabetic() {
+            while chars.get(i).is_some_and(|c| c.is_alphabetic()) {
+                i += 1;
+            }
+            // digits only belong to the name for "atan2" and friends
+            let mut j = i;
+            while digit(j) {
+                j += 1;
+            }
+            if j > i && Func::lookup(&chars[start..j].iter().collect::<String>()).is_some() {
+                i = j;
+            }
+            if chars.get(i) == Some(&'_') && chars.get(i + 1).is_some_and(|c| c.is_alphanumeric()) {
+                i += 1;
+                while chars.get(i).is_some_and(|c| c.is_alphanumeric()) {
+                    i += 1;
+                }
+            }
+            Tok::Name(chars[start..i].iter().collect())
+        } else if "<>".contains(c) && chars.get(i + 1) == Some(&'=') {
+            i += 2;
+            Tok::Op(if c == '<' { '≤' } else { '≥' })
+        } else if "+-*/^(),|=<>≤≥".contains(c) {
+            i += 1;
+            Tok::Op(c)
+        } else {
+            return Err(Error::syntax(i, i + 1, format!("unexpected '{}'", c)));
+        };
+        out.push(Token { tok, start, end: i });
+    }
+    Ok(out)
+}
+
+// A lone "=" splits an equation; a second one is an error on its own.
+pub(crate) fn check_equals(toks: &[Token]) -> Result<(), Error> {
+    match toks.iter().filter(|t| t.is('=')).nth(1) {
+        Some(t) => Err(Error::new(
+            ErrorKind::MultipleEquals,
+            t.start,
+            t.end,
+            "more than one '='",
+        )),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Shape {
+    Equation,
+    // a leading "(a, b)", or any line with a range
+    Curve,
+    // one side of the "=" is a lone r, and the other isn't in x and y
+    Polar,
+    // inequalities
+    Region,
+}
+
+impl Shape {
+    // The names the shape gives meaning to, in slot order.
+    pub fn slots(self) -> Vec<String> {
+        let names: &[&str] = match self {
+            Shape::Equation => &["x", "y"],
+            Shape::Curve => &["t"],
+            Shape::Polar => &["θ", "theta"],
+            Shape::Region => &["x", "y"],
+        };
+        names.iter().map(|n| n.to_string()).collect()
+    }
+}
+
+// The bounds a curve's parameter runs between.
+pub(crate) type Range = (Node, Node);
+
+// `params` are the slider names: a slider called r makes "r = ..." an
+// ordinary equation.
+pub(crate) fn shape(toks: &[Token], params: &[String]) -> Shape {
+    if toks.first().is_some_and(|t| t.is('(')) {
+        let mut depth = 0;
+        for t in toks {
+            if t.is('(') {
+                depth += 1;
+            } else if t.is(')') {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if depth == 1 && t.is(',') {
+                return Shape::Curve;
+            }
+        }
+    }
+    let lone_r = |side: &[Token]| matches!(side, [t] if t.tok == Tok::Name("r".into()));
+    let slider = |n: &str| params.iter().any(|p| p == n);
+    // x and y on their own, or in a run of letters that gets split up
+    let in_xy = |side: &[Token]| {
+        side.iter().any(|t| match &t.tok {
+            Tok::Name(n) if n == "x" || n == "y" => true,
+            Tok::Name(n) => {
+                !n.contains('_')
+                    && !slider(n)
+                    && constant(n).is_none()
+                    && Func::lookup(n).is_none()
+                    && n.contains(['x', 'y'])
+            }
+            _ => false,
+        })
+    };
+    // a range starts at the first comma outside brackets
+    let mut depth = 0;
+    let comma = toks.iter().position(|t| {
+        if t.is('(') {
+            depth += 1;
+        } else if t.is(')') {
+            depth -= 1;
+        }
+        depth == 0 && t.is(',')
+    });
+    let head = &toks[..comma.unwrap_or(toks.len())];
+    if let Some(eq) = head.iter().position(|t| t.is('=')).filter(|_| !slider("r")) {
+        let (lhs, rhs) = (&head[..eq], &head[eq + 1..]);
+        if (lone_r(lhs) && !in_xy(rhs)) || (lone_r(rhs) && !in_xy(lhs)) {
+            return Shape::Polar;
+        }
+    }
+    // only curves take a range, so its "<"s don't make a region
+    if comma.is_some() {
+        return Shape::Curve;
+    }
+    if head.iter().any(|t| t.relation().is_some()) {
+        return Shape::Region;
+    }
+    Shape::Equation
+}
+
+// What a name can stand for, in lookup order.
+#[derive(Clone, Copy)]
+enum Known {
+    Slot(usize),
+    Def(usize),
+    Const(f64),
+    Func(Func),
+}
+
+pub(crate) struct Parser<'a> {
+    toks: &'a [Token],
+    pos: usize,
+    // nesting of "|...|" since the last bracket, to tell opening bars from
+    // closing ones
+    abs: usize,
+    defs: &'a Defs,
+    pub slots: Vec<String>,
+    // an open parser turns unknown variables into new slots instead of
+    // reporting them
+    open: bool,
+    unknown: Vec<(String, usize, usize, bool)>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(toks: &'a [Token], slots: Vec<String>, defs: &'a Defs, open: bool) -> Parser<'a> {
+        Parser {
+            toks,
+            pos: 0,
+            abs: 0,
+            defs,
+            slots,
+            open,
+            unknown: Vec::new(),
+        }
+    }
+
+    // Parses `lhs = rhs` (or a bare expression) to the end of the input.
+    pub fn equation(&mut self) -> Result<(Node, Option<Node>), Error> {
+        let lhs = self.expr()?;
+        let rhs = if self.eat('=') {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        self.finish()?;
+        Ok((lhs, rhs))
+    }
+
+    // Parses "(x(t), y(t))" and its range to the end of the input. The
+    // parameter is slot 0.
+    pub fn curve(&mut self) -> Result<(Node, Node, Option<Range>), Error> {
+        self.expect('(')?;
+        let x = self.expr()?;
+        self.expect(',')?;
+        let y = self.expr()?;
+        self.expect(')')?;
+        let range = self.range(1)?;
+        self.finish()?;
+        Ok((x, y, range))
+    }
+
+    // Parses "r = f" (or "f = r") and its range to the end of the input.
+    // The angle is slots 0 and 1, "θ" and "theta".
+    pub fn polar(&mut self) -> Result<(Node, Option<Range>), Error> {
+        let r = if self.peek().is_some_and(|t| t.tok == Tok::Name("r".into())) {
+            self.pos += 1;
+            self.expect('=')?;
+            self.expr()?
+        } else {
+            let r = self.expr()?;
+            self.expect('=')?;
+            self.pos += 1;
+            r
+        };
+        let range = self.range(2)?;
+        self.finish()?;
+        Ok((r, range))
+    }
+
+    // Parses inequalities joined by "and" to the end of the input. Each
+    // relation becomes (f, strict), true where f > 0 (or f >= 0), so
+    // "0 < x < 2" is two of them.
+    pub fn region(&mut self) -> Result<Vec<(Node, bool)>, Error> {
+        let mut out = Vec::new();
+        loop {
+            let mut lhs = self.expr()?;
+            let mut chained = false;
+            while let Some((less, strict)) = self.peek().and_then(|t| t.relation()) {
+                self.pos += 1;
+                let rhs = self.expr()?;
+                let f = if less {
+                    Node::bin(BinOp::Sub, rhs.clone(), lhs)
+                } else {
+                    Node::bin(BinOp::Sub, lhs, rhs.clone())
+                };
+                out.push((f, strict));
+                lhs = rhs;
+                chained = true;
+            }
+            if !chained {
+                let (start, end) = self.next_span();
+                return Err(Error::syntax(start, end, "expected '<', '<=', '>' or '>='"));
+            }
+            if !self.peek().is_some_and(|t| t.is_and()) {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.finish()?;
+        Ok(out)
+    }
+
+    // ", lo <= t <= hi", for a parameter whose names are the first `names`
+    // slots. The bounds can't use it.
+    fn range(&mut self, names: usize) -> Result<Option<Range>, Error> {
+        if !self.eat(',') {
+            return Ok(None);
+        }
+        let lo = self.bound(names)?;
+        self.less()?;
+        let param = match self.peek().map(|t| &t.tok) {
+            Some(Tok::Name(n)) => self.slots[..names].contains(n),
+            _ => false,
+        };
+        if !param {
+            let (start, end) = self.next_span();
+            let msg = format!("the range needs '{}' in the middle", self.slots[0]);
+            return Err(Error::syntax(start, end, msg));
+        }
+        self.pos += 1;
+        self.less()?;
+        let hi = self.bound(names)?;
+        Ok(Some((lo, hi)))
+    }
+
+    fn less(&mut self) -> Result<(), Error> {
+        if self.eat('<') || self.eat('≤') {
+            return Ok(());
+        }
+        let (start, end) = self.next_span();
+        Err(Error::syntax(start, end, "a range reads: lo <= t <= hi"))
+    }
+
+    fn bound(&mut self, names: usize) -> Result<Node, Error> {
+        let start = self.peek().map_or(self.here(), |t| t.start);
+        let node = self.expr()?;
+        if (0..names).any(|s| node.uses(s)) {
+            let msg = format!("the range can't depend on '{}'", self.slots[0]);
+            return Err(Error::syntax(start, self.here(), msg));
+        }
+        Ok(node)
+    }
+
+    // Parses a whole expression starting at token `pos`.
+    pub fn body(&mut self, pos: usize) -> Result<Node, Error> {
+        self.pos = pos;
+        let node = self.expr()?;
+        self.finish()?;
+        Ok(node)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if let Some(t) = self.toks.get(self.pos) {
+            return Err(self.unexpected(t));
+        }
+        let Some((_, start, end, call)) = self.unknown.first().cloned() else {
+            return Ok(());
+        };
+        // one message for every unknown name, spanning the first
+        let mut names: Vec<String> = self.unknown.iter().map(|u| u.0.clone()).collect();
+        names.sort();
+        names.dedup();
+        let kind = if call {
+            ErrorKind::UnknownFunction
+        } else {
+            ErrorKind::UnknownVariable
+        };
+        let message = match names.as_slice() {
+            [name] if call => format!("unknown function '{}'", name),
+            [name] => format!("unknown variable '{}'", name),
+            _ => format!("unknown symbols: {}", names.join(", ")),
+        };
+        Err(Error::new(kind, start, end, message))
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.toks.get(self.pos)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek().is_some_and(|t| t.is(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Where "missing" errors point: just past the last token read.
+    fn here(&self) -> usize {
+        match self.pos {
+            0 => 0,
+            p => self.toks[p - 1].end,
+        }
+    }
+
+    // The next token, or the gap at the end of the input.
+    fn next_span(&self) -> (usize, usize) {
+        self.peek()
+            .map_or((self.here(), self.here()), |t| (t.start, t.end))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            let at = self.here();
+            Err(Error::syntax(at, at, format!("missing '{}'", c)))
+        }
+    }
+
+    fn unexpected(&self, t: &Token) -> Error {
+        let what = match &t.tok {
+            Tok::Num(_) => "number".to_string(),
+            Tok::Name(n) => format!("'{}'", n),
+            Tok::Op('≤') => "'<='".to_string(),
+            Tok::Op('≥') => "'>='".to_string(),
+            Tok::Op(c) => format!("'{}'", c),
+        };
+        Error::syntax(t.start, t.end, format!("unexpected {}", what))
+    }
+
+    fn expr(&mut self) -> Result<Node, Error> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                BinOp::Add
+            } else if self.eat('-') {
+                BinOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Node::bin(op, lhs, self.term()?);
+        }
+    }
+
+    fn term(&mut self) -> Result<Node, Error> {
+        let mut lhs = self.unary()?;
+        loop {
+            lhs = if self.eat('*') {
+                Node::bin(BinOp::Mul, lhs, self.unary()?)
+            } else if self.eat('/') {
+                Node::bin(BinOp::Div, lhs, self.unary()?)
+            } else if self.juxtaposed() {
+                Node::bin(BinOp::Mul, lhs, self.power()?)
+            } else {
+                return Ok(lhs);
+            };
+        }
+    }
+
+    // Does the next token start an implicitly multiplied operand?
+    fn juxtaposed(&self) -> bool {
+        match self.peek().map(|t| &t.tok) {
+            Some(Tok::Name(n)) => n != "and",
+            Some(Tok::Num(_) | Tok::Op('(')) => true,
+            // inside bars, a bar closes
+            Some(Tok::Op('|')) => self.abs == 0,
+            _ => false,
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, Error> {
+        if self.eat('-') {
+            Ok(Node::Neg(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Node, Error> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            Ok(Node::bin(BinOp::Pow, base, self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, Error> {
+        let Some(t) = self.peek() else {
+            let at = self.here();
+            return Err(Error::syntax(at, at, "expression ends early"));
+        };
+        self.pos += 1;
+        match &t.tok {
+            Tok::Num(v) => Ok(Node::Num(*v)),
+            Tok::Name(name) if name != "and" => self.name(name, t.start, t.end),
+            Tok::Op('(') => {
+                let abs = std::mem::replace(&mut self.abs, 0);
+                let inner = self.expr()?;
+                self.expect(')')?;
+                self.abs = abs;
+                Ok(inner)
+            }
+            Tok::Op('|') => {
+                self.abs += 1;
+                let inner = self.expr()?;
+                self.expect('|')?;
+                self.abs -= 1;
+                Ok(Node::Call(Func::Abs, vec![inner]))
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected(t))
+            }
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Known> {
+        if let Some(i) = self.slots.iter().position(|s| s == name) {
+            return Some(Known::Slot(i));
+        }
+        if let Some(i) = self.defs.position(name) {
+            return Some(Known::Def(i));
+        }
+        constant(name)
+            .map(Known::Const)
+            .or_else(|| Func::lookup(name).map(Known::Func))
+    }
+
+    fn name(&mut self, name: &str, start: usize, end: usize) -> Result<Node, Error> {
+        let call = self.peek().is_some_and(|t| t.is('('));
+        // a function takes a bar group without brackets: abs|x|, sqrt|x|
+        let bar = self.peek().is_some_and(|t| t.is('|'));
+        let defs = self.defs;
+        let calls = |k: Known| call || (bar && takes_args(defs, k));
+        if let Some(k) = self.lookup(name) {
+            return self.resolve(k, name, start, end, calls(k));
+        }
+        let chars: Vec<char> = name.chars().collect();
+        if name.contains('_') || chars.len() == 1 {
+            return self.unknown(name, start, end, false);
+        }
+        // longest known prefix first; letters nothing starts with stand alone
+        let mut pieces = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let found = (i + 1..=chars.len()).rev().find_map(|j| {
+                let piece: String = chars[i..j].iter().collect();
+                self.lookup(&piece).map(|k| (j, piece, Some(k)))
+            });
+            let (j, piece, k) = found.unwrap_or((i + 1, chars[i].to_string(), None));
+            pieces.push((start + i, start + j, piece, k));
+            i = j;
+        }
+        if call && pieces.iter().any(|p| p.3.is_none()) {
+            return self.unknown(name, start, end, true);
+        }
+        let last = pieces.len() - 1;
+        let mut node: Option<Node> = None;
+        for (n, (s, e, piece, k)) in pieces.into_iter().enumerate() {
+            let next = match k {
+                Some(k) => self.resolve(k, &piece, s, e, calls(k) && n == last)?,
+                None => self.unknown(&piece, s, e, false)?,
+            };
+            node = Some(match node {
+                Some(lhs) => Node::bin(BinOp::Mul, lhs, next),
+                None => next,
+            });
+        }
+        Ok(node.unwrap())
+    }
+
+    fn resolve(
+        &mut self,
+        k: Known,
+        name: &str,
+        start: usize,
+        end: usize,
+        call: bool,
+    ) -> Result<Node, Error> {
+        match k {
+            Known::Slot(i) => Ok(Node::Var(i)),
+            Known::Const(v) => Ok(Node::Num(v)),
+            Known::Def(i) if self.defs.arity(i) == 0 => self.inline(i, Vec::new(), start, end),
+            _ if !call => Err(Error::syntax(
+                start,
+                end,
+                format!("'{}' needs parentheses: {}(...)", name, name),
+            )),
+            Known::Func(f) => {
+                let (mut args, close) = self.args()?;
+                let variadic = matches!(f, Func::Min | Func::Max) && !args.is_empty();
+                if !variadic && args.len() != f.arity() {
+                    return Err(arity(name, f.arity(), start, close));
+                }
+                let mut node = args.remove(0);
+                for a in args {
+                    node = Node::Call(f, vec![node, a]);
+                }
+                // abs|x| is the bars on their own, not abs of them
+                let bars = matches!((f, &node), (Func::Abs, Node::Call(Func::Abs, _)));
+                if f.arity() == 1 && !bars {
+                    node = Node::Call(f, vec![node]);
+                }
+                Ok(node)
+            }
+            Known::Def(i) => {
+                let (args, close) = self.args()?;
+                let n = self.defs.arity(i);
+                if args.len() != n {
+                    return Err(arity(name, n, start, close));
+                }
+                self.inline(i, args, start, end)
+            }
+        }
+    }
+
+    // `(a, b, ...)`, or one `|a|`; returns the arguments and where the
+    // call ends.
+    fn args(&mut self) -> Result<(Vec<Node>, usize), Error> {
+        if self.peek().is_some_and(|t| t.is('|')) {
+            let arg = self.primary()?;
+            return Ok((vec![arg], self.here()));
+        }
+        self.expect('(')?;
+        let abs = std::mem::replace(&mut self.abs, 0);
+        let mut args = vec![self.expr()?];
+        while self.eat(',') {
+            args.push(self.expr()?);
+        }
+        self.expect(')')?;
+        self.abs = abs;
+        Ok((args, self.here()))
+    }
+
+    // Substitutes a definition's body. Names it left free are resolved
+    // here, as if they had been typed in place of the call.
+    fn inline(
+        &mut self,
+        def: usize,
+        args: Vec<Node>,
+        start: usize,
+        end: usize,
+    ) -> Result<Node, Error> {
+        let defs = self.defs;
+        let (body, free) = defs.body(def);
+        let mut with = args;
+        for name in free {
+            let node = match self.lookup(name) {
+                Some(k @ (Known::Slot(_) | Known::Const(_))) => {
+                    self.resolve(k, name, start, end, false)?
+                }
+                Some(Known::Def(i)) if self.defs.arity(i) == 0 => {
+                    self.inline(i, Vec::new(), start, end)?
+                }
+                _ => self.unknown(name, start, end, false)?,
+            };
+            with.push(node);
+        }
+        Ok(body.substitute(&with))
+    }
+
+    fn unknown(&mut self, name: &str, start: usize, end: usize, call: bool) -> Result<Node, Error> {
+        if call {
+            // keep going, to report unknown names in the arguments as well
+            self.unknown.push((name.to_string(), start, end, true));
+            self.args()?;
+        } else if self.open {
+            self.slots.push(name.to_string());
+            return Ok(Node::Var(self.slots.len() - 1));
+        } else {
+            self.unknown.push((name.to_string(), start, end, false));
+        }
+        Ok(Node::Num(f64::NAN))
+    }
+}
+
+fn takes_args(defs: &Defs, k: Known) -> bool {
+    match k {
+        Known::Func(_) => true,
+        Known::Def(i) => defs.arity(i) > 0,
+        _ => false,
+    }
+}
+
+fn arity(name: &str, n: usize, start: usize, end: usize) -> Error {
+    let s = if n == 1 { "" } else { "s" };
+    Error::syntax(start, end, format!("'{}' takes {} argument{}", name, n, s))
+}
@@ -0,0 +1,181 @@
+// === Polylines ===
+// Marching squares emits one loose segment per cell. Neighboring cells
+// compute the same crossing for the edge they share, so segments are joined
+// wherever their endpoints meet, into ordered polylines the frontend can
+// stroke as one path. Douglas-Peucker then drops points that sit within
+// `epsilon` pixels of the simplified line.
+
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Endpoints this close (in pixels) are the same point.
+const SNAP: f64 = 1e-4;
+
+// Flat, so JS gets three typed arrays: polyline `i` is points
+// `offsets[i]..offsets[i + 1]` of `points` ([x, y, x, y, ...]), and
+// `closed[i]` is 1 for a loop. A loop doesn't repeat its first point.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Polylines {
+    points: Vec<f64>,
+    offsets: Vec<u32>,
+    closed: Vec<u8>,
+}
+
+fn key(x: f64, y: f64) -> (i64, i64) {
+    ((x / SNAP).round() as i64, (y / SNAP).round() as i64)
+}
+
+impl Polylines {
+    pub(crate) fn new() -> Polylines {
+        Polylines {
+            offsets: vec![0],
+            ..Polylines::default()
+        }
+    }
+
+    // Adds a closed polygon.
+    pub(crate) fn push_polygon(&mut self, points: &[(f64, f64)]) {
+        for &(x, y) in points {
+            self.points.push(x);
+            self.points.push(y);
+        }
+        self.offsets.push((self.points.len() / 2) as u32);
+        self.closed.push(1);
+    }
+
+    // `lines` is [x1, y1, x2, y2, ...] segments; `epsilon` <= 0 keeps every
+    // point.
+    pub fn stitch(lines: &[f64], epsilon: f64) -> Polylines {
+        let segs: Vec<[f64; 4]> = lines
+            .chunks_exact(4)
+            .map(|s| [s[0], s[1], s[2], s[3]])
+            .filter(|s| key(s[0], s[1]) != key(s[2], s[3]))
+            .collect();
+        let mut ends: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, s) in segs.iter().enumerate() {
+            ends.entry(key(s[0], s[1])).or_default().push(i);
+            ends.entry(key(s[2], s[3])).or_default().push(i);
+        }
+        let mut used = vec![false; segs.len()];
+        // follows unused segments from `at`, pushing the far end of each
+        let walk = |used: &mut Vec<bool>, mut at: (f64, f64), out: &mut Vec<(f64, f64)>| loop {
+            let next = ends[&key(at.0, at.1)].iter().copied().find(|&i| !used[i]);
+            let Some(i) = next else { return };
+            used[i] = true;
+            let s = segs[i];
+            at = if key(s[0], s[1]) == key(at.0, at.1) {
+                (s[2], s[3])
+            } else {
+                (s[0], s[1])
+            };
+            out.push(at);
+        };
+
+        let mut out = Polylines::new();
+        for i in 0..segs.len() {
+            if used[i] {
+                continue;
+            }
+            used[i] = true;
+            let s = segs[i];
+            let mut forward = vec![(s[0], s[1]), (s[2], s[3])];
+            walk(&mut used, (s[2], s[3]), &mut forward);
+            let mut line = vec![];
+            walk(&mut used, (s[0], s[1]), &mut line);
+            line.reverse();
+            line.extend(forward);
+
+            let (first, last) = (line[0], line[line.len() - 1]);
+            let closed = line.len() > 3 && key(first.0, first.1) == key(last.0, last.1);
+            if closed {
+                line.pop();
+            }
+            let line = if epsilon > 0.0 {
+                simplify(&line, epsilon, closed)
+            } else {
+                line
+            };
+            for (x, y) in line {
+                out.points.push(x);
+                out.points.push(y);
+            }
+            out.offsets.push((out.points.len() / 2) as u32);
+            out.closed.push(closed as u8);
+        }
+        out
+    }
+}
+
+#[wasm_bindgen]
+impl Polylines {
+    pub fn len(&self) -> u32 {
+        self.closed.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+
+    pub fn get_points(&self) -> Vec<f64> {
+        self.points.clone()
+    }
+
+    pub fn get_offsets(&self) -> Vec<u32> {
+        self.offsets.clone()
+    }
+
+    pub fn get_closed(&self) -> Vec<u8> {
+        self.closed.clone()
+    }
+}
+
+// Distance from `p` to the segment a-b.
+fn distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    };
+    (p.0 - a.0 - t * dx).hypot(p.1 - a.1 - t * dy)
+}
+
+// Douglas-Peucker, with an explicit stack so long curves can't overflow
+// it. A loop is treated as a path that returns to its first point.
+fn simplify(line: &[(f64, f64)], epsilon: f64, closed: bool) -> Vec<(f64, f64)> {
+    let mut pts = line.to_vec();
+    if closed {
+        pts.push(line[0]);
+    }
+    let n = pts.len();
+    if n < 3 {
+        return line.to_vec();
+    }
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    let mut stack = vec![(0, n - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let far = (a + 1..b)
+            .map(|i| (i, distance(pts[i], pts[a], pts[b])))
+            .max_by(|p, q| p.1.total_cmp(&q.1));
+        if let Some((i, d)) = far {
+            if d > epsilon {
+                keep[i] = true;
+                stack.push((a, i));
+                stack.push((i, b));
+            }
+        }
+    }
+    let mut out: Vec<(f64, f64)> = (0..n).filter(|&i| keep[i]).map(|i| pts[i]).collect();
+    if closed {
+        out.pop();
+        // a loop needs three corners to stay a loop
+        if out.len() < 3 {
+            return line.to_vec();
+        }
+    }
+    out
+}
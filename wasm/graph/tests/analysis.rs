@@ -0,0 +1,153 @@
+mod common;
+
+use common::{area, names, SCALE};
+use graph_wasm::{Equation, Form};
+use std::f64::consts::PI;
+
+fn eq(src: &str) -> Equation {
+    Equation::parse(src, &names(&["a"])).unwrap()
+}
+
+// The x of each point.
+fn xs(points: Vec<f64>) -> Vec<f64> {
+    points.chunks(2).map(|p| p[0]).collect()
+}
+
+fn close(got: &[f64], want: &[f64], eps: f64) -> bool {
+    got.len() == want.len() && got.iter().zip(want).all(|(g, w)| (g - w).abs() < eps)
+}
+
+// f' by hand.
+type Derivative = fn(f64) -> f64;
+
+#[test]
+fn derivatives_follow_the_tree() {
+    let cases: [(&str, Derivative); 9] = [
+        ("y = x^3 - 3x", |x| 3.0 * x * x - 3.0),
+        ("y = sin(2x) + a", |x| 2.0 * (2.0 * x).cos()),
+        ("y = exp(a x)", |x| 0.5 * (0.5 * x).exp()),
+        ("y = ln(x) / x", |x| (1.0 - x.ln()) / (x * x)),
+        ("y = x^x", |x| x.powf(x) * (x.ln() + 1.0)),
+        ("y = 2^x", |x| 2f64.powf(x) * 2f64.ln()),
+        ("y = sqrt(1 + x^2)", |x| x / (1.0 + x * x).sqrt()),
+        ("y = atan2(x, 1)", |x| 1.0 / (1.0 + x * x)),
+        ("y = max(x, x^2)", |x| if x > 1.0 { 2.0 * x } else { 1.0 }),
+    ];
+    for (src, want) in cases {
+        let d = eq(src).derivative().unwrap();
+        assert_eq!(d.form(), Form::YOfX);
+        for x in [0.3, 0.7, 1.5, 2.5] {
+            let got = d.solve(x, &[0.5]);
+            assert!((got - want(x)).abs() < 1e-9, "{src} at {x}: {got}");
+        }
+    }
+
+    // along y for x = g(y), and twice over
+    let d = eq("x = y^4").derivative().unwrap().derivative().unwrap();
+    assert_eq!(d.form(), Form::XOfY);
+    assert!((d.solve(2.0, &[]) - 48.0).abs() < 1e-9);
+    assert!((d.eval(48.0, 2.0, &[])).abs() < 1e-9);
+
+    assert!(eq("x^2 + y^2 = 1").derivative().is_none());
+    assert!(eq("y < x").derivative().is_none());
+}
+
+#[test]
+fn features_of_a_cubic() {
+    let a = eq("y = x^3 - 3x").analyze(&[], -5.0, 5.0).unwrap();
+    let r = 3f64.sqrt();
+    assert!(close(&xs(a.get_zeros()), &[-r, 0.0, r], 1e-12));
+    assert_eq!(a.get_minima().len(), 2);
+    assert!((a.get_minima()[0] - 1.0).abs() < 1e-12);
+    assert!((a.get_minima()[1] + 2.0).abs() < 1e-12);
+    assert!(close(&xs(a.get_maxima()), &[-1.0], 1e-12));
+    assert!((a.get_maxima()[1] - 2.0).abs() < 1e-12);
+    assert!(close(&xs(a.get_inflections()), &[0.0], 1e-12));
+
+    // only what's in the range
+    let a = eq("y = x^3 - 3x").analyze(&[], 0.5, 5.0).unwrap();
+    assert!(close(&xs(a.get_zeros()), &[r], 1e-12));
+    assert!(a.get_maxima().is_empty() && a.get_inflections().is_empty());
+
+    let a = eq("y = sin(x)").analyze(&[], -0.1, 2.0 * PI + 0.1).unwrap();
+    assert!(close(&xs(a.get_zeros()), &[0.0, PI, 2.0 * PI], 1e-12));
+    assert!(close(&xs(a.get_maxima()), &[PI / 2.0], 1e-12));
+    assert!(close(&xs(a.get_minima()), &[1.5 * PI], 1e-12));
+    assert!(close(&xs(a.get_inflections()), &[0.0, PI, 2.0 * PI], 1e-12));
+}
+
+#[test]
+fn touching_zeros_kinks_and_poles() {
+    // zeros that don't cross, found as extrema
+    let a = eq("y = (x - 1)^2").analyze(&[], -3.0, 3.0).unwrap();
+    assert!(close(&xs(a.get_zeros()), &[1.0], 1e-9));
+    assert!(close(&xs(a.get_minima()), &[1.0], 1e-9));
+
+    // a kink is an extremum without a flat derivative
+    let a = eq("y = abs(x - 0.3)").analyze(&[], -3.0, 3.0).unwrap();
+    assert!(close(&xs(a.get_minima()), &[0.3], 1e-12));
+    assert!(close(&xs(a.get_zeros()), &[0.3], 1e-12));
+
+    // tan flips sign through its poles, which are none of these
+    let a = eq("y = tan(x)").analyze(&[], -3.0, 3.0).unwrap();
+    assert!(close(&xs(a.get_zeros()), &[0.0], 1e-12));
+    assert!(close(&xs(a.get_inflections()), &[0.0], 1e-12));
+    assert!(a.get_minima().is_empty() && a.get_maxima().is_empty());
+
+    // x = g(y) runs along y
+    let a = eq("x = y^2 - 1").analyze(&[], -3.0, 3.0).unwrap();
+    let zeros = a.get_zeros();
+    assert!(close(&zeros, &[0.0, -1.0, 0.0, 1.0], 1e-12), "{zeros:?}");
+    assert!(close(&a.get_minima(), &[-1.0, 0.0], 1e-12));
+
+    // a flat stretch has no one extremum
+    let a = eq("y = floor(x)").analyze(&[], -3.0, 3.0).unwrap();
+    assert!(a.get_minima().is_empty() && a.get_maxima().is_empty());
+
+    assert!(eq("(cos(t), sin(t))").analyze(&[], 0.0, 1.0).is_none());
+}
+
+#[test]
+fn definite_integrals() {
+    let integrate = |src: &str, a, b| eq(src).integrate(&[2.0], a, b, SCALE, 100, 100).unwrap();
+    let i = integrate("y = x^2", 0.0, 3.0);
+    assert!((i.value() - 9.0).abs() < 1e-9, "{}", i.value());
+    assert!(
+        (area(&i.region()) - 9.0).abs() < 0.01,
+        "{}",
+        area(&i.region())
+    );
+    assert_eq!(i.region().len(), 1);
+
+    // backwards is negative, and the region is the same
+    let i = integrate("y = x^2", 3.0, 0.0);
+    assert!((i.value() + 9.0).abs() < 1e-9);
+    assert!((area(&i.region()) - 9.0).abs() < 0.01);
+
+    // below the axis counts against, but shades all the same
+    let i = integrate("y = sin(a x)", 0.0, PI);
+    assert!(i.value().abs() < 1e-9, "{}", i.value());
+    assert!(
+        (area(&i.region()) - 2.0).abs() < 0.01,
+        "{}",
+        area(&i.region())
+    );
+
+    let i = integrate("y = exp(-x^2)", -10.0, 10.0);
+    assert!((i.value() - PI.sqrt()).abs() < 1e-9, "{}", i.value());
+
+    // along y, out to the y axis
+    let i = integrate("x = y^3", 0.0, 2.0);
+    assert!((i.value() - 4.0).abs() < 1e-9);
+    assert!((area(&i.region()) - 4.0).abs() < 0.01);
+
+    // undefined partway, or across a pole
+    let i = integrate("y = sqrt(x)", -1.0, 1.0);
+    assert!(i.value().is_nan());
+    assert_eq!(i.region().len(), 1);
+    assert!(integrate("y = 1 / x", -1.0, 2.0).value().is_nan());
+
+    assert!(eq("x^2 + y^2 = 1")
+        .integrate(&[], 0.0, 1.0, SCALE, 100, 100)
+        .is_none());
+}
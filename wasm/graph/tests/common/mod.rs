@@ -0,0 +1,74 @@
+// Shared by the integration tests: a W x H view centered on the origin, at
+// SCALE pixels a unit. Not every test uses every helper.
+#![allow(dead_code)]
+
+use graph_wasm::{Equation, Intersections, PlotResult, Polylines};
+
+pub const W: i32 = 200;
+pub const H: i32 = 200;
+pub const SCALE: f64 = 20.0;
+
+pub fn names(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| s.to_string()).collect()
+}
+
+// `src`, which has no parameters.
+pub fn parse(src: &str) -> Equation {
+    Equation::parse(src, &[]).unwrap()
+}
+
+// `eq` over the view, in 8-pixel cells refined down to 1.
+pub fn plot(eq: &Equation, values: &[f64]) -> PlotResult {
+    plot_at(eq, SCALE, 8, 1, values)
+}
+
+// `eq` over the view at another zoom and cell size.
+pub fn plot_at(eq: &Equation, scale: f64, step: i32, min_step: i32, values: &[f64]) -> PlotResult {
+    eq.plot(W, H, scale, W / 2, H / 2, step, min_step, values)
+}
+
+// Where `a` crosses `b` in the view, seeded from `step` cells.
+pub fn intersect(a: &Equation, b: &Equation, step: i32) -> Intersections {
+    a.intersections(&[], b, &[], W, H, SCALE, W / 2, H / 2, step)
+}
+
+// Each polyline as a list of pixel points.
+pub fn points(p: &Polylines) -> Vec<Vec<(f64, f64)>> {
+    let (pts, offs) = (p.get_points(), p.get_offsets());
+    offs.windows(2)
+        .map(|o| {
+            (o[0] as usize..o[1] as usize)
+                .map(|k| (pts[2 * k], pts[2 * k + 1]))
+                .collect()
+        })
+        .collect()
+}
+
+// The polylines of `src` over the view, as lists of pixel points.
+pub fn curves(src: &str) -> Vec<Vec<(f64, f64)>> {
+    points(&plot(&parse(src), &[]).polylines(0.0))
+}
+
+// A pixel point in world units.
+pub fn world((x, y): (f64, f64)) -> (f64, f64) {
+    ((x - W as f64 / 2.0) / SCALE, (H as f64 / 2.0 - y) / SCALE)
+}
+
+// Total area of the polygons, in world units.
+pub fn area(p: &Polylines) -> f64 {
+    let pixels: f64 = points(p)
+        .iter()
+        .map(|poly| {
+            let n = poly.len();
+            (0..n)
+                .map(|i| {
+                    let (a, b) = (poly[i], poly[(i + 1) % n]);
+                    a.0 * b.1 - b.0 * a.1
+                })
+                .sum::<f64>()
+                .abs()
+                / 2.0
+        })
+        .sum();
+    pixels / (SCALE * SCALE)
+}
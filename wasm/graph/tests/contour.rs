@@ -0,0 +1,42 @@
+mod common;
+
+use common::{parse, plot_at, W};
+
+// Length inside the view; the last row and column of cells overhang it.
+fn length(lines: &[f64]) -> f64 {
+    let inside = |v: f64| (0.0..=W as f64).contains(&v);
+    lines
+        .chunks(4)
+        .filter(|l| l.iter().all(|&v| inside(v)))
+        .map(|l| (l[2] - l[0]).hypot(l[3] - l[1]))
+        .sum()
+}
+
+#[test]
+fn loops_smaller_than_a_cell_survive() {
+    // a circle of radius 5px, inside a single 16px cell
+    let eq = parse("x^2 + y^2 = 0.0025");
+    let coarse = plot_at(&eq, 100.0, 16, 16, &[]).get_lines();
+    assert!(coarse.is_empty(), "coarse grid saw it");
+    let lines = plot_at(&eq, 100.0, 16, 1, &[]).get_lines();
+    assert!(!lines.is_empty(), "refinement missed the loop");
+    for p in lines.chunks(2) {
+        let r = (p[0] - 100.0).hypot(p[1] - 100.0);
+        assert!((r - 5.0).abs() < 0.5, "point {:?} at radius {}", p, r);
+    }
+}
+
+#[test]
+fn refined_curves_match_a_fine_grid() {
+    let eq = parse("sin(x*y) = 0.5");
+    let length = |step, min_step| length(&plot_at(&eq, 20.0, step, min_step, &[]).get_lines());
+    let (fine, coarse, adaptive) = (length(1, 1), length(16, 16), length(16, 1));
+    // the tight outer bands alias away on the coarse grid
+    assert!(coarse < fine * 0.8, "{} vs {}", coarse, fine);
+    assert!(
+        (adaptive - fine).abs() < fine * 0.02,
+        "{} vs {}",
+        adaptive,
+        fine
+    );
+}
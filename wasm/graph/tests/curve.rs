@@ -0,0 +1,155 @@
+mod common;
+
+use common::{intersect, names, parse, plot, points, world};
+use graph_wasm::{Defs, Equation, Form};
+use std::f64::consts::PI;
+
+// The polylines of `src` as lists of world points, and whether each is
+// closed. Its free variables are sliders, set to `values`.
+fn trace(src: &str, values: &[f64]) -> Vec<(Vec<(f64, f64)>, bool)> {
+    let params = Defs::new().free_variables(src).unwrap();
+    let lines = plot(&Equation::parse(src, &params).unwrap(), values).polylines(0.0);
+    points(&lines)
+        .into_iter()
+        .zip(lines.get_closed())
+        .map(|(l, c)| (l.into_iter().map(world).collect(), c == 1))
+        .collect()
+}
+
+fn crossings(a: &str, b: &str) -> Vec<(f64, f64)> {
+    let hits = intersect(&parse(a), &parse(b), 8);
+    let mut pts: Vec<(f64, f64)> = hits.get_world().chunks(2).map(|p| (p[0], p[1])).collect();
+    pts.sort_by(|p, q| p.0.total_cmp(&q.0).then(p.1.total_cmp(&q.1)));
+    pts
+}
+
+fn close(p: (f64, f64), q: (f64, f64)) -> bool {
+    (p.0 - q.0).abs() < 1e-9 && (p.1 - q.1).abs() < 1e-9
+}
+
+#[test]
+fn parametric_curves_follow_t() {
+    let form = |src| Equation::parse(src, &[]).unwrap().form();
+    assert_eq!(form("(cos(t), sin(t))"), Form::Parametric);
+    assert_eq!(form("(x + 1) = y^2"), Form::Implicit);
+
+    // a full turn by default, and a loop
+    let circle = trace("(2cos(t), 2sin(t))", &[]);
+    assert_eq!(circle.len(), 1);
+    assert!(circle[0].1);
+    assert!(circle[0]
+        .0
+        .iter()
+        .all(|p| (p.0.hypot(p.1) - 2.0).abs() < 1e-9));
+
+    // a range, with a slider in it
+    let arc = trace("(t, t^2), -a <= t < a", &[1.5]);
+    assert_eq!(arc.len(), 1);
+    let xs = arc[0].0.iter().map(|p| p.0);
+    let (lo, hi) = xs.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| {
+        (lo.min(x), hi.max(x))
+    });
+    assert!((lo + 1.5).abs() < 1e-9 && (hi - 1.5).abs() < 1e-9);
+    assert!(arc[0].0.iter().all(|p| (p.1 - p.0 * p.0).abs() < 1e-9));
+}
+
+#[test]
+fn polar_curves_follow_theta() {
+    let eq = Equation::parse("r = θ", &[]).unwrap();
+    assert_eq!(eq.form(), Form::Polar);
+    let p = eq.point(PI / 2.0, &[]);
+    assert!(close(p, (0.0, PI / 2.0)), "{:?}", p);
+    // θ inside a call, comma and all
+    let eq = Equation::parse("min(θ, 1) = r", &[]).unwrap();
+    assert_eq!(eq.form(), Form::Polar);
+    assert!(close(eq.point(PI / 2.0, &[]), (0.0, 1.0)));
+
+    // two turns of a spiral: r grows with the angle around the origin
+    let spiral = trace("r = theta/2, 0 <= θ <= 4pi", &[]);
+    assert_eq!(spiral.len(), 1);
+    let r: Vec<f64> = spiral[0].0.iter().map(|p| p.0.hypot(p.1)).collect();
+    assert!(r.windows(2).all(|w| w[0] < w[1]) || r.windows(2).all(|w| w[0] > w[1]));
+    assert!((r.iter().copied().fold(0.0, f64::max) - 2.0 * PI).abs() < 1e-9);
+
+    // r = 1/cos(θ) is the line x = 1, not joined across θ = π/2 where r
+    // runs off to infinity
+    let line = trace("r = 1/cos(θ)", &[]);
+    for (points, _) in &line {
+        assert!(points.iter().all(|p| (p.0 - 1.0).abs() < 1e-9));
+        // no segment spans the view
+        for w in points.windows(2) {
+            let (lo, hi) = (w[0].1.min(w[1].1), w[0].1.max(w[1].1));
+            assert!(hi < -5.0 || lo > 5.0 || hi - lo < 0.5, "{:?}", w);
+        }
+    }
+}
+
+#[test]
+fn curve_parameters_are_not_sliders() {
+    let defs = Defs::new();
+    assert_eq!(
+        defs.free_variables("(a cos(t), sin(t)), 0 <= t <= b")
+            .unwrap(),
+        names(&["a", "b"])
+    );
+    assert_eq!(defs.free_variables("r = kθ").unwrap(), names(&["k"]));
+    // elsewhere t and r are ordinary names
+    assert_eq!(
+        defs.free_variables("y = tx + r").unwrap(),
+        names(&["t", "r"])
+    );
+    // nor is r polar next to x and y, or when it's a slider
+    assert_eq!(defs.free_variables("x^2 + y^2 = r").unwrap(), names(&["r"]));
+    let circle = Equation::parse("x^2 + y^2 = r", &names(&["r"])).unwrap();
+    assert_eq!(circle.form(), Form::Implicit);
+    assert_eq!(circle.eval(3.0, 4.0, &[25.0]), 0.0);
+    assert_eq!(
+        Equation::parse("r = 2xy", &names(&["r"])).unwrap().form(),
+        Form::Implicit
+    );
+    let eq = Equation::parse("r = 2", &names(&["r"])).unwrap();
+    assert_eq!(eq.eval(0.0, 0.0, &[2.0]), 0.0);
+    // and "r = ..." isn't a definition
+    let mut defs = Defs::new();
+    assert!(!defs.define("r = 2").unwrap());
+
+    let err = |src| Equation::parse(src, &[]).err().unwrap();
+    let e = err("(t, t), 0 <= x <= 1");
+    assert_eq!((e.start, e.end), (13, 14));
+    assert_eq!(e.message, "the range needs 't' in the middle");
+    let e = err("(t, t), 0 <= t <= 2t");
+    assert_eq!((e.start, e.end), (18, 20));
+    assert_eq!(e.message, "the range can't depend on 't'");
+    assert_eq!(err("(t, t), 0 = t").message, "a range reads: lo <= t <= hi");
+    // only a curve takes a range, so this is one missing its "(x, y)"
+    // rather than a region
+    let e = err("x = 1, 0 < t < 1");
+    assert_eq!((e.start, e.end), (0, 0));
+    assert_eq!(e.message, "missing '('");
+}
+
+#[test]
+fn curves_intersect_other_equations() {
+    let r = 0.5f64.sqrt();
+    let pts = crossings("(cos(t), sin(t))", "y = x");
+    assert_eq!(pts.len(), 2, "{:?}", pts);
+    assert!(
+        close(pts[0], (-r, -r)) && close(pts[1], (r, r)),
+        "{:?}",
+        pts
+    );
+    // either way round
+    assert_eq!(crossings("y = x", "(cos(t), sin(t))").len(), 2);
+
+    // two curves
+    let pts = crossings("r = 1", "(t, 0), -2 <= t <= 2");
+    assert_eq!(pts.len(), 2, "{:?}", pts);
+    assert!(
+        close(pts[0], (-1.0, 0.0)) && close(pts[1], (1.0, 0.0)),
+        "{:?}",
+        pts
+    );
+
+    // 1/t changes sign at t = 0 without crossing y = 0
+    assert!(crossings("(t, 1/t), -3 <= t <= 3", "y = 0").is_empty());
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{names, plot_at, SCALE, W};
+use graph_wasm::{Equation, ErrorKind};
+
+#[test]
+fn parameters_are_bound_by_name() {
+    let eq = Equation::parse("y = a*x^2 + b", &names(&["a", "b"])).unwrap();
+    // f = y - (a x^2 + b)
+    assert_eq!(eq.eval(2.0, 0.0, &[1.0, 3.0]), -7.0);
+    assert_eq!(eq.eval(2.0, 0.0, &[-0.5, 0.0]), 2.0);
+    // a missing value leaves the curve undefined rather than guessing
+    assert!(eq.eval(2.0, 0.0, &[1.0]).is_nan());
+    // built-in constants still resolve
+    let eq = Equation::parse("y = pi * x", &[]).unwrap();
+    assert_eq!(eq.eval(1.0, 0.0, &[]), -std::f64::consts::PI);
+}
+
+#[test]
+fn unknown_symbols_are_all_listed() {
+    let err = Equation::parse("y = c*x + wobble(x) + a + c", &names(&["a"]))
+        .err()
+        .unwrap();
+    // the span covers the first one, the message lists them all
+    assert_eq!(err.kind, ErrorKind::UnknownVariable);
+    assert_eq!((err.start, err.end), (4, 5));
+    assert_eq!(err.to_string(), "unknown symbols: c, wobble");
+
+    let err = Equation::parse("y = wobble (x)", &[]).err().unwrap();
+    assert_eq!(err.kind, ErrorKind::UnknownFunction);
+    assert_eq!((err.start, err.end), (4, 10));
+    assert_eq!(err.message, "unknown function 'wobble'");
+}
+
+#[test]
+fn syntax_errors_point_into_the_source() {
+    let span = |src: &str| {
+        let e = Equation::parse(src, &[]).err().unwrap();
+        (e.kind, e.start, e.end)
+    };
+    // an open paren runs to the end of its side
+    assert_eq!(span("y = (x"), (ErrorKind::Syntax, 6, 6));
+    assert_eq!(span("(x = y"), (ErrorKind::Syntax, 2, 2));
+    // offsets count characters, not bytes
+    assert_eq!(span("y = \u{3c0} # x"), (ErrorKind::Syntax, 6, 7));
+    assert_eq!(span("y = x * * 2"), (ErrorKind::Syntax, 8, 9));
+    assert_eq!(span("y = x = 1"), (ErrorKind::MultipleEquals, 6, 7));
+}
+
+#[test]
+fn replotting_with_new_values_moves_the_curve() {
+    let eq = Equation::parse("y = x + k", &names(&["k"])).unwrap();
+    let mut mean_y = vec![];
+    for k in [0.0, 2.0] {
+        let lines = plot_at(&eq, SCALE, 4, 4, &[k]).get_lines();
+        assert!(!lines.is_empty());
+        // crossings of the vertical line through the origin
+        let ys: Vec<f64> = lines
+            .chunks(4)
+            .filter(|l| (l[0] - W as f64 / 2.0).abs() < 4.0)
+            .map(|l| l[1])
+            .collect();
+        mean_y.push(ys.iter().sum::<f64>() / ys.len() as f64);
+    }
+    // k = 2 lifts the line by 2 units (screen y grows downward)
+    assert!(
+        (mean_y[0] - mean_y[1] - 2.0 * SCALE).abs() < 3.0,
+        "{:?}",
+        mean_y
+    );
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{curves, H, SCALE, W};
+use graph_wasm::{Equation, Form};
+use std::f64::consts::PI;
+
+fn px(x: f64) -> f64 {
+    W as f64 / 2.0 + x * SCALE
+}
+
+// Polylines with a segment reaching into the view, left to right.
+fn visible(lines: Vec<Vec<(f64, f64)>>) -> Vec<Vec<(f64, f64)>> {
+    let mut lines: Vec<_> = lines
+        .into_iter()
+        .filter(|l| l.iter().any(|p| (0.0..=H as f64).contains(&p.1)))
+        .collect();
+    lines.sort_by(|a, b| a[0].0.total_cmp(&b[0].0));
+    lines
+}
+
+#[test]
+fn explicit_forms_are_detected() {
+    let form = |src| Equation::parse(src, &[]).unwrap().form();
+    assert_eq!(form("y = x^2"), Form::YOfX);
+    assert_eq!(form("sin(x) = y"), Form::YOfX);
+    assert_eq!(form("x = y^2 - 1"), Form::XOfY);
+    assert_eq!(form("y = 2y + x"), Form::Implicit);
+    assert_eq!(form("x^2 + y^2 = 1"), Form::Implicit);
+    let eq = Equation::parse("x = y^2 - 1", &[]).unwrap();
+    assert_eq!(eq.solve(3.0, &[]), 8.0);
+}
+
+#[test]
+fn asymptotes_break_the_curve() {
+    // x runs over -5..5: tan has four asymptotes there, 1/x one
+    let tan = visible(curves("y = tan(x)"));
+    assert_eq!(tan.len(), 5);
+    for (line, k) in tan.iter().zip(-2..) {
+        let (lo, hi) = (px(k as f64 * PI - PI / 2.0), px(k as f64 * PI + PI / 2.0));
+        for p in line {
+            assert!(
+                p.0 >= lo - 1e-6 && p.0 <= hi + 1e-6,
+                "{:?} on branch {}",
+                p,
+                k
+            );
+            assert!((p.1 - (H as f64 / 2.0 - ((p.0 - 100.0) / SCALE).tan() * SCALE)).abs() < 1e-6);
+        }
+    }
+    let inv = visible(curves("y = 1/x"));
+    assert_eq!(inv.len(), 2);
+    assert!(inv[0].iter().all(|p| p.0 < px(0.0)));
+    assert!(inv[1].iter().all(|p| p.0 > px(0.0)));
+    // a jump without an asymptote
+    assert_eq!(visible(curves("y = sign(x)")).len(), 2);
+}
+
+#[test]
+fn steep_curves_stay_joined_and_smooth() {
+    let lines = curves("y = 1000x^3");
+    assert_eq!(lines.len(), 1);
+    // no chord drifts more than a fraction of a pixel from the curve
+    for pair in lines[0].windows(2) {
+        let mid = (pair[0].0 + pair[1].0) / 2.0;
+        let x = (mid - 100.0) / SCALE;
+        let y = H as f64 / 2.0 - 1000.0 * x.powi(3) * SCALE;
+        if (0.0..=H as f64).contains(&y) {
+            let chord = (pair[0].1 + pair[1].1) / 2.0;
+            let slope = (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0);
+            assert!((chord - y).abs() / slope.hypot(1.0) < 0.5, "{:?}", pair);
+        }
+    }
+    // sqrt is drawn right up to the end of its domain
+    let sqrt = curves("y = sqrt(x)");
+    assert_eq!(sqrt.len(), 1);
+    let first = sqrt[0].iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    assert!(first - px(0.0) < 0.1, "starts at {}", first);
+}
+
+#[test]
+fn functions_of_y_run_along_the_vertical() {
+    let lines = curves("x = y^2 - 2");
+    assert_eq!(lines.len(), 1);
+    for &(x, y) in &lines[0] {
+        let wy = (H as f64 / 2.0 - y) / SCALE;
+        assert!((x - px(wy * wy - 2.0)).abs() < 1e-6);
+    }
+    // and reaches from the top of the view to the bottom
+    let ys: Vec<f64> = lines[0].iter().map(|p| p.1).collect();
+    assert!(ys.iter().any(|&y| y <= 0.0) && ys.iter().any(|&y| y >= H as f64));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{intersect, parse, H, SCALE, W};
+
+fn crossings(a: &str, b: &str, step: i32) -> Vec<(f64, f64)> {
+    let hits = intersect(&parse(a), &parse(b), step);
+    let (world, pixels) = (hits.get_world(), hits.get_pixels());
+    for (w, p) in world.chunks(2).zip(pixels.chunks(2)) {
+        assert!((W as f64 / 2.0 + w[0] * SCALE - p[0]).abs() < 1e-9);
+        assert!((H as f64 / 2.0 - w[1] * SCALE - p[1]).abs() < 1e-9);
+    }
+    let mut pts: Vec<(f64, f64)> = world.chunks(2).map(|p| (p[0], p[1])).collect();
+    pts.sort_by(|p, q| p.0.total_cmp(&q.0));
+    pts
+}
+
+#[test]
+fn crossings_are_exact_and_unique() {
+    // the unit circle meets y = x at +-1/sqrt(2)
+    let pts = crossings("x^2 + y^2 = 1", "y = x", 8);
+    let r = 0.5f64.sqrt();
+    assert_eq!(pts.len(), 2, "{:?}", pts);
+    for (p, s) in pts.iter().zip([-1.0, 1.0]) {
+        assert!(
+            (p.0 - s * r).abs() < 1e-9 && (p.1 - s * r).abs() < 1e-9,
+            "{:?}",
+            p
+        );
+    }
+    // the result doesn't depend on the seeding grid
+    assert_eq!(crossings("x^2 + y^2 = 1", "y = x", 3).len(), 2);
+}
+
+#[test]
+fn near_misses_and_asymptotes_are_not_crossings() {
+    // the parabola clears the line by 0.01, a fraction of a pixel-ish cell
+    assert!(crossings("y = x^2 + 0.01", "y = 0", 8).is_empty());
+    // 1/x flips sign at x = 0 without ever meeting y = 0 there
+    assert!(crossings("y = 1/x", "x = 0", 8).is_empty());
+    let pts = crossings("y = tan(x)", "y = 1", 8);
+    // pi/4 - pi, pi/4, pi/4 + pi fall inside +-5
+    assert_eq!(pts.len(), 3, "{:?}", pts);
+    for (p, k) in pts.iter().zip([-1.0, 0.0, 1.0]) {
+        let x = std::f64::consts::FRAC_PI_4 + k * std::f64::consts::PI;
+        assert!((p.0 - x).abs() < 1e-9, "{:?}", p);
+    }
+}
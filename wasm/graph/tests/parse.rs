@@ -0,0 +1,119 @@
+mod common;
+
+use common::names;
+use graph_wasm::{Defs, Equation, ErrorKind};
+
+// The value of a bare expression (no "=") at (x, y).
+fn at(src: &str, x: f64, y: f64) -> f64 {
+    Equation::parse(src, &[]).unwrap().eval(x, y, &[])
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn juxtaposition_multiplies() {
+    assert_eq!(at("2x", 3.0, 0.0), 6.0);
+    assert_eq!(at("3sin(x)", 0.5, 0.0), 3.0 * 0.5f64.sin());
+    assert_eq!(at("xy", 2.0, 5.0), 10.0);
+    assert_eq!(at("2(x+1)(x-1)", 3.0, 0.0), 16.0);
+    assert_eq!(at("xsin(y)", 2.0, 1.0), 2.0 * 1.0f64.sin());
+    // powers bind tighter, and a leading minus looser
+    assert_eq!(at("2x^2", 3.0, 0.0), 18.0);
+    assert_eq!(at("-x^2", 3.0, 0.0), -9.0);
+    assert_eq!(at("2^3^2", 0.0, 0.0), 512.0);
+    assert_eq!(at("x^-1", 4.0, 0.0), 0.25);
+    assert_eq!(at("6/2x", 3.0, 0.0), 9.0);
+}
+
+#[test]
+fn bars_constants_and_functions() {
+    assert_eq!(at("|x - 5|", 2.0, 0.0), 3.0);
+    assert_eq!(at("|x| |y|", -2.0, -3.0), 6.0);
+    assert_eq!(at("| |x| - 5 |", -2.0, 0.0), 3.0);
+    assert_eq!(at("2|x|", -2.0, 0.0), 4.0);
+    // a function can take the bars as its brackets
+    assert_eq!(at("abs|x|", -2.0, 0.0), 2.0);
+    assert_eq!(at("sqrt|x| + 1", -4.0, 0.0), 3.0);
+    assert_eq!(at("xsqrt|y|", 3.0, -4.0), 6.0);
+    assert_eq!(at("|1 - abs|x||", -3.0, 0.0), 2.0);
+    // once: abs|x| is |x|, not abs(|x|)
+    let tree = |src| Equation::parse(src, &[]).unwrap().tree().clone();
+    assert_eq!(tree("y = abs|x|"), tree("y = |x|"));
+    assert!(close(at("2pi", 0.0, 0.0), std::f64::consts::TAU));
+    assert!(close(at("τ - 2π", 0.0, 0.0), 0.0));
+    assert!(close(at("e^x", 1.0, 0.0), std::f64::consts::E));
+    assert_eq!(at("max(x, y, 7)", 2.0, 9.0), 9.0);
+    assert_eq!(at("atan2(y, x)", 1.0, 0.0), 0.0);
+    assert_eq!(at("mod(x, 3)", -1.0, 0.0), 2.0);
+    assert_eq!(at("log2(x)", 8.0, 0.0), 3.0);
+}
+
+#[test]
+fn scientific_notation() {
+    assert!(close(at("1e-3*x", 1.0, 0.0), 0.001));
+    assert_eq!(at("2.5E+2", 0.0, 0.0), 250.0);
+    assert_eq!(at(".5e1x", 2.0, 0.0), 10.0);
+    // without digits after it, e is still the constant
+    assert!(close(at("2e", 0.0, 0.0), 2.0 * std::f64::consts::E));
+    assert!(close(at("2ex", 3.0, 0.0), 6.0 * std::f64::consts::E));
+    assert!(close(at("2e-x", 1.0, 0.0), 2.0 * std::f64::consts::E - 1.0));
+}
+
+#[test]
+fn multi_character_names() {
+    let eq = Equation::parse("y = k_1 x + rate", &names(&["k_1", "rate"])).unwrap();
+    assert_eq!(eq.eval(2.0, 0.0, &[3.0, 1.0]), -7.0);
+    // "ab" is one name when it's a parameter, a*b otherwise
+    let eq = Equation::parse("y = ab", &names(&["ab"])).unwrap();
+    assert_eq!(eq.eval(0.0, 0.0, &[4.0]), -4.0);
+    let eq = Equation::parse("y = ab", &names(&["a", "b"])).unwrap();
+    assert_eq!(eq.eval(0.0, 0.0, &[2.0, 3.0]), -6.0);
+}
+
+#[test]
+fn definitions_are_inlined() {
+    let mut defs = Defs::new();
+    assert!(defs.define("f(t) = a t^2").unwrap());
+    assert!(defs.define("k = 3").unwrap());
+    assert!(defs.define("g(u, v) = f(u) + kv").unwrap());
+    // equations aren't definitions
+    assert!(!defs.define("y = f(x)").unwrap());
+    assert!(!defs.define("xy = 1").unwrap());
+    assert!(!defs.define("c = x^2").unwrap());
+
+    let eq = Equation::parse_with("y = g(x, 2)", &names(&["a"]), &defs).unwrap();
+    // y - (a x^2 + 3*2)
+    assert_eq!(eq.eval(2.0, 0.0, &[0.5]), -8.0);
+    let eq = Equation::parse_with("y = f|x|", &names(&["a"]), &defs).unwrap();
+    assert_eq!(eq.eval(-2.0, 0.0, &[0.5]), -2.0);
+
+    assert_eq!(
+        defs.free_variables("y = f(x) + bx").unwrap(),
+        names(&["a", "b"])
+    );
+    assert_eq!(defs.free_variables("h(t) = t c").unwrap(), names(&["c"]));
+
+    let err = defs.define("k = 4").err().unwrap();
+    assert_eq!((err.start, err.end), (0, 1));
+    let err = defs.define("h(t) = h(t - 1)").err().unwrap();
+    assert_eq!(err.message, "'h' can't refer to itself");
+    let err = Equation::parse_with("y = f(x, 1)", &names(&["a"]), &defs)
+        .err()
+        .unwrap();
+    assert_eq!((err.kind, err.start, err.end), (ErrorKind::Syntax, 4, 11));
+}
+
+#[test]
+fn misused_names_are_reported() {
+    let err = |src: &str| Equation::parse(src, &[]).err().unwrap();
+    let e = err("y = sin x");
+    assert_eq!((e.kind, e.start, e.end), (ErrorKind::Syntax, 4, 7));
+    let e = err("y = x +");
+    assert_eq!((e.kind, e.start, e.end), (ErrorKind::Syntax, 7, 7));
+    let e = err("y = |x");
+    assert_eq!(e.message, "missing '|'");
+    let e = err("y = x)");
+    assert_eq!((e.start, e.end), (5, 6));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{parse, plot_at, points, SCALE};
+use graph_wasm::Polylines;
+
+#[test]
+fn segments_are_joined_in_order() {
+    // two segments out of order, one reversed, plus a lone one
+    let lines = [1.0, 0.0, 2.0, 0.0, 1.0, 0.0, 0.0, 0.0, 5.0, 5.0, 6.0, 6.0];
+    let p = Polylines::stitch(&lines, 0.0);
+    assert_eq!(p.len(), 2);
+    assert_eq!(p.get_offsets(), vec![0, 3, 5]);
+    assert_eq!(p.get_closed(), vec![0, 0]);
+    assert_eq!(points(&p)[0], vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]);
+}
+
+#[test]
+fn a_circle_is_one_closed_loop() {
+    let result = plot_at(&parse("x^2 + y^2 = 9"), SCALE, 4, 1, &[]);
+    let p = result.polylines(0.0);
+    assert_eq!(p.len(), 1);
+    assert_eq!(p.get_closed(), vec![1]);
+    // a loop has as many points as (non-degenerate) segments
+    let segments = result
+        .get_lines()
+        .chunks(4)
+        .filter(|s| (s[0], s[1]) != (s[2], s[3]))
+        .count();
+    assert_eq!(p.get_points().len() / 2, segments);
+
+    // a line across the view stays open
+    let p = plot_at(&parse("y = x / 2"), SCALE, 4, 1, &[]).polylines(0.0);
+    assert_eq!((p.len(), p.get_closed()), (1, vec![0]));
+}
+
+#[test]
+fn simplification_stays_within_epsilon() {
+    let result = plot_at(&parse("x^2 + y^2 = 9"), SCALE, 4, 1, &[]);
+    let full = result.polylines(0.0);
+    let simple = result.polylines(0.5);
+    assert_eq!(simple.get_closed(), vec![1]);
+    let (n, m) = (full.get_points().len(), simple.get_points().len());
+    assert!(m * 3 < n, "{} of {} points kept", m / 2, n / 2);
+    // every dropped point is still near the simplified loop
+    let kept = points(&simple).swap_remove(0);
+    for (x, y) in points(&full).swap_remove(0) {
+        let near = (0..kept.len()).any(|i| {
+            let (a, b) = (kept[i], kept[(i + 1) % kept.len()]);
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let t = (((x - a.0) * dx + (y - a.1) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+            (x - a.0 - t * dx).hypot(y - a.1 - t * dy) <= 0.5 + 1e-9
+        });
+        assert!(near, "({}, {}) strayed", x, y);
+    }
+}
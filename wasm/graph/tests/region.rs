@@ -0,0 +1,105 @@
+mod common;
+
+use common::{area, intersect, names, parse, plot, world, H};
+use graph_wasm::{Defs, Equation, Form, PlotResult};
+use std::f64::consts::PI;
+
+// `src` over the view, which must read as a region.
+fn region(src: &str) -> PlotResult {
+    let eq = parse(src);
+    assert_eq!(eq.form(), Form::Region);
+    plot(&eq, &[])
+}
+
+// World x of every segment endpoint.
+fn xs(lines: &[f64]) -> Vec<f64> {
+    lines.chunks(2).map(|p| world((p[0], p[1])).0).collect()
+}
+
+#[test]
+fn regions_are_filled() {
+    let disk = region("x^2 + y^2 < 4");
+    assert!(
+        (area(&disk.fill()) - 4.0 * PI).abs() < 0.01,
+        "{}",
+        area(&disk.fill())
+    );
+    assert!(disk.get_lines().is_empty());
+    assert!(!disk.get_dashed().is_empty());
+
+    // the whole view, or none of it
+    assert!((area(&region("x^2 + 1 > 0").fill()) - 100.0).abs() < 1e-9);
+    assert!(region("x^2 + 1 < 0").fill().is_empty());
+}
+
+#[test]
+fn strict_edges_are_dashed() {
+    let strip = region("0 <= x < 2");
+    assert!(
+        (area(&strip.fill()) - 20.0).abs() < 1e-6,
+        "{}",
+        area(&strip.fill())
+    );
+    assert!(xs(&strip.get_lines()).iter().all(|x| x.abs() < 1e-9));
+    assert!(xs(&strip.get_dashed())
+        .iter()
+        .all(|x| (x - 2.0).abs() < 1e-9));
+    assert!(!strip.get_lines().is_empty() && !strip.get_dashed().is_empty());
+
+    // ">" turns around like "<"
+    let strip = region("2 > x >= 0");
+    assert!((area(&strip.fill()) - 20.0).abs() < 1e-6);
+    assert!(xs(&strip.get_lines()).iter().all(|x| x.abs() < 1e-9));
+}
+
+#[test]
+fn and_intersects_regions() {
+    let half = region("x^2 + y^2 < 4 and y >= 0");
+    assert!(
+        (area(&half.fill()) - 2.0 * PI).abs() < 0.01,
+        "{}",
+        area(&half.fill())
+    );
+    // the flat side is solid, the arc dashed, give or take the pixel the
+    // corners are cut in
+    let lines = half.get_lines();
+    assert!(!lines.is_empty());
+    for p in lines.chunks(2) {
+        assert!((p[1] - H as f64 / 2.0).abs() <= 1.0, "{:?}", p);
+    }
+    for p in half.get_dashed().chunks(2) {
+        let r = (p[0] - 100.0).hypot(p[1] - 100.0);
+        assert!(
+            (r - 40.0).abs() <= 1.0 && p[1] <= H as f64 / 2.0 + 1.0,
+            "{:?}",
+            p
+        );
+    }
+
+    // the edge of a region meets other curves
+    let hits = intersect(&parse("x^2 + y^2 <= 4 and x > -5"), &parse("y = 0"), 8);
+    let mut xs: Vec<f64> = hits.get_world().chunks(2).map(|p| p[0]).collect();
+    xs.sort_by(f64::total_cmp);
+    assert_eq!(xs.len(), 2, "{:?}", xs);
+    assert!((xs[0] + 2.0).abs() < 1e-9 && (xs[1] - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn inequality_syntax() {
+    let defs = Defs::new();
+    assert_eq!(
+        defs.free_variables("a x < b and y > c").unwrap(),
+        names(&["a", "b", "c"])
+    );
+    let err = |src| Equation::parse(src, &[]).err().unwrap();
+    let e = err("x and y < 1");
+    assert_eq!((e.start, e.end), (2, 5));
+    assert_eq!(e.message, "expected '<', '<=', '>' or '>='");
+    let e = err("y = x and x < 1");
+    assert_eq!((e.start, e.end), (2, 3));
+    let e = err("x < 1 and");
+    assert_eq!(e.message, "expression ends early");
+    assert_eq!(err("x <= 1 <= <= 2").message, "unexpected '<='");
+    // "and" is never a product
+    assert_eq!(err("y = and").message, "unexpected 'and'");
+}
@@ -192,7 +192,7 @@ impl PongGame {
     fn on_player_scored(&mut self) -> u32 {
         let mut events = EVT_SCORE;
         self.streak += 1;
-        if self.streak % 3 == 0 {
+        if self.streak.is_multiple_of(3) {
             // swap to a different random protocol (one active at a time)
             let options: Vec<u32> = ALL_MODS
                 .iter()
@@ -421,7 +421,7 @@ impl PongGame {
 
                 let sp = (b.dx.powi(2) + b.dy.powi(2)).sqrt();
                 let new_speed = (sp * 1.03).min(max_speed);
-                b.dx = -1.0 * new_speed * bounce_angle.cos();
+                b.dx = -new_speed * bounce_angle.cos();
                 b.dy = new_speed * bounce_angle.sin();
                 b.x = self.width - self.paddle_width - self.ball_size;
                 b.spin = if self.mods & MOD_CURVE != 0 {
@@ -0,0 +1,451 @@
+// === Creatures ===
+// Agents are an overlay on the cell grid: each one sits on a cell, reacts
+// to what's in and around it, and changes the world only through `Senses`.
+// A new creature is a `Kind` variant plus a `Behavior` impl; `react` stays
+// about materials.
+
+use crate::{Error, Mat, Universe};
+
+// Keeps a runaway spawner from turning the per-tick agent pass into the
+// bottleneck.
+pub(crate) const MAX_AGENTS: usize = 1024;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Ant = 1,
+    Fish = 2,
+    Bird = 3,
+}
+
+impl Kind {
+    pub(crate) fn from_u8(v: u8) -> Option<Kind> {
+        match v {
+            1 => Some(Kind::Ant),
+            2 => Some(Kind::Fish),
+            3 => Some(Kind::Bird),
+            _ => None,
+        }
+    }
+
+    fn behavior(self) -> &'static dyn Behavior {
+        match self {
+            Kind::Ant => &Ant,
+            Kind::Fish => &Fish,
+            Kind::Bird => &Bird,
+        }
+    }
+}
+
+impl TryFrom<u8> for Kind {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Kind, Error> {
+        Kind::from_u8(v).ok_or(Error::UnknownKind(v))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Agent {
+    pub kind: Kind,
+    pub x: i32,
+    pub y: i32,
+    pub dir: i8,   // facing: -1 left, 1 right
+    pub breath: u8, // ticks left outside a breathable cell
+    pub cargo: u8, // material being carried (0 = nothing)
+}
+
+trait Behavior {
+    // Hottest cell the creature survives in.
+    fn max_temp(&self) -> f32;
+    // Materials it can breathe in, and how long it can hold its breath.
+    fn breathes(&self, m: Mat) -> bool;
+    fn lung_capacity(&self) -> u8;
+    // Materials it may step into.
+    fn can_enter(&self, m: Mat) -> bool;
+    fn color(&self) -> [u8; 3];
+    fn act(&self, s: &mut Senses);
+}
+
+// Restricted view of the world around one agent. Offsets are relative to
+// the agent's cell; anything off-grid reads as None and can't be entered.
+pub(crate) struct Senses<'a> {
+    u: &'a mut Universe,
+    a: &'a mut Agent,
+}
+
+impl Senses<'_> {
+    fn cell(&self, dx: i32, dy: i32) -> Option<usize> {
+        let (x, y) = (self.a.x + dx, self.a.y + dy);
+        if self.u.in_bounds(x, y) {
+            Some(self.u.idx(x, y))
+        } else {
+            None
+        }
+    }
+
+    fn mat(&self, dx: i32, dy: i32) -> Option<Mat> {
+        self.cell(dx, dy).map(|i| Mat::from_u8(self.u.mat[i]))
+    }
+
+    fn is(&self, dx: i32, dy: i32, m: Mat) -> bool {
+        self.mat(dx, dy) == Some(m)
+    }
+
+    fn dir(&self) -> i32 {
+        self.a.dir as i32
+    }
+
+    fn turn(&mut self) {
+        self.a.dir = -self.a.dir;
+    }
+
+    fn chance(&mut self, one_in: u32) -> bool {
+        self.u.chance(one_in)
+    }
+
+    // Offset of the closest cell within `radius` matching `pred`.
+    fn nearest(&self, radius: i32, pred: impl Fn(Mat) -> bool) -> Option<(i32, i32)> {
+        let mut best: Option<(i32, i32, i32)> = None;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let d2 = dx * dx + dy * dy;
+                if d2 > radius * radius || best.is_some_and(|b| b.2 <= d2) {
+                    continue;
+                }
+                if self.mat(dx, dy).is_some_and(&pred) {
+                    best = Some((dx, dy, d2));
+                }
+            }
+        }
+        best.map(|(dx, dy, _)| (dx, dy))
+    }
+
+    // Move one cell if the target is enterable and no other agent is there.
+    fn step(&mut self, dx: i32, dy: i32) -> bool {
+        let Some(ti) = self.cell(dx, dy) else {
+            return false;
+        };
+        let tm = Mat::from_u8(self.u.mat[ti]);
+        if self.u.agent_cells[ti] || !self.a.kind.behavior().can_enter(tm) {
+            return false;
+        }
+        let i = self.u.idx(self.a.x, self.a.y);
+        self.u.agent_cells[i] = false;
+        self.u.agent_cells[ti] = true;
+        self.a.x += dx;
+        self.a.y += dy;
+        true
+    }
+
+    // Pick up a powder grain, leaving the cell empty.
+    fn take(&mut self, dx: i32, dy: i32) -> bool {
+        if self.a.cargo != 0 {
+            return false;
+        }
+        let Some(i) = self.cell(dx, dy) else {
+            return false;
+        };
+        let m = Mat::from_u8(self.u.mat[i]);
+        if !m.is_powder() {
+            return false;
+        }
+        self.a.cargo = m as u8;
+        self.u.place(i, Mat::Empty);
+        true
+    }
+
+    // Set the carried grain down in an empty cell.
+    fn drop(&mut self, dx: i32, dy: i32) -> bool {
+        if self.a.cargo == 0 || !self.is(dx, dy, Mat::Empty) {
+            return false;
+        }
+        let Some(i) = self.cell(dx, dy) else {
+            return false;
+        };
+        self.u.place(i, Mat::from_u8(self.a.cargo));
+        self.a.cargo = 0;
+        true
+    }
+}
+
+// Ants walk the ground and dig through sand, hauling each grain back out
+// of the tunnel and dumping it on the surface.
+struct Ant;
+
+impl Behavior for Ant {
+    fn max_temp(&self) -> f32 {
+        80.0
+    }
+    fn breathes(&self, m: Mat) -> bool {
+        m == Mat::Empty
+    }
+    fn lung_capacity(&self) -> u8 {
+        120
+    }
+    fn can_enter(&self, m: Mat) -> bool {
+        m == Mat::Empty
+    }
+    fn color(&self) -> [u8; 3] {
+        [40, 22, 14]
+    }
+
+    fn act(&self, s: &mut Senses) {
+        // buried by a landslide: dig out of the grain on top of us
+        if !s.is(0, 0, Mat::Empty) {
+            if !s.step(0, -1) && !s.step(s.dir(), 0) && !s.take(0, 0) {
+                s.turn();
+            }
+            return;
+        }
+        // ants cling to tunnel walls; only open air makes them fall
+        let wall = |m: Option<Mat>| m.is_some_and(|m| m.is_solid());
+        if !wall(s.mat(-1, 0)) && !wall(s.mat(1, 0)) && s.step(0, 1) {
+            return;
+        }
+        let d = s.dir();
+        let open_sky = (1..=3).all(|h| s.mat(0, -h).is_none_or(|m| m == Mat::Empty));
+
+        if s.a.cargo != 0 {
+            // out under open sky: haul the grain a little way from the
+            // hole, then dump it on the spoil heap ahead
+            if open_sky && s.chance(6) && s.drop(d, -1) {
+                s.turn();
+                return;
+            }
+            if !s.step(d, -1) && !s.step(d, 0) && (open_sky || !s.step(0, -1)) {
+                s.turn();
+            }
+            return;
+        }
+
+        // on the surface, now and then start a new shaft; underground,
+        // keep pushing the tunnel onward and downward
+        let (dig_dx, dig_dy) = if open_sky {
+            if s.chance(20) {
+                (d, 1)
+            } else {
+                (d, 0)
+            }
+        } else {
+            match s.u.rand() % 4 {
+                0 => (0, 1),
+                1 => (d, 1),
+                _ => (d, 0),
+            }
+        };
+        if s.is(dig_dx, dig_dy, Mat::Sand) {
+            s.take(dig_dx, dig_dy);
+            s.turn(); // haul it back out the way we came
+            return;
+        }
+        if s.step(d, 0) {
+            return;
+        }
+        // climb small steps, otherwise turn around
+        if !(s.is(0, -1, Mat::Empty) && s.step(d, -1)) {
+            s.turn();
+        }
+    }
+}
+
+// Fish swim through water and suffocate when stranded out of it.
+struct Fish;
+
+impl Behavior for Fish {
+    fn max_temp(&self) -> f32 {
+        45.0
+    }
+    fn breathes(&self, m: Mat) -> bool {
+        matches!(m, Mat::Water | Mat::SaltWater)
+    }
+    fn lung_capacity(&self) -> u8 {
+        60
+    }
+    fn can_enter(&self, m: Mat) -> bool {
+        matches!(m, Mat::Empty | Mat::Water | Mat::SaltWater)
+    }
+    fn color(&self) -> [u8; 3] {
+        [245, 140, 40]
+    }
+
+    fn act(&self, s: &mut Senses) {
+        let wet = |m: Option<Mat>| matches!(m, Some(Mat::Water | Mat::SaltWater));
+        if !wet(s.mat(0, 0)) {
+            // stranded: fall, or flop toward any water nearby
+            if s.step(0, 1) {
+                return;
+            }
+            if s.chance(3) {
+                let d = s.dir();
+                if !s.step(d, 0) {
+                    s.turn();
+                }
+            }
+            return;
+        }
+        if !s.chance(2) {
+            return; // swimming is slower than falling sand
+        }
+        let d = s.dir();
+        let dy = (s.u.rand() % 3) as i32 - 1;
+        if wet(s.mat(d, dy)) && s.step(d, dy) {
+            return;
+        }
+        if wet(s.mat(d, 0)) && s.step(d, 0) {
+            return;
+        }
+        s.turn();
+    }
+}
+
+// Birds fly through open air, wander, and keep their distance from fire.
+struct Bird;
+
+impl Behavior for Bird {
+    fn max_temp(&self) -> f32 {
+        60.0
+    }
+    fn breathes(&self, m: Mat) -> bool {
+        m == Mat::Empty
+    }
+    fn lung_capacity(&self) -> u8 {
+        30
+    }
+    fn can_enter(&self, m: Mat) -> bool {
+        m == Mat::Empty
+    }
+    fn color(&self) -> [u8; 3] {
+        [235, 235, 240]
+    }
+
+    fn act(&self, s: &mut Senses) {
+        let danger = s.nearest(8, |m| matches!(m, Mat::Fire | Mat::Ember | Mat::Lava));
+        if let Some((fx, fy)) = danger {
+            // flee directly away, sliding sideways if that's blocked
+            let (ax, ay) = (-fx.signum(), -fy.signum());
+            if ax != 0 {
+                s.a.dir = ax as i8;
+            }
+            let _ = s.step(ax, ay) || s.step(ax, 0) || s.step(0, ay) || s.step(ay, ax) || s.step(-ay, -ax);
+            return;
+        }
+
+        // otherwise wander, keeping some altitude above the ground
+        let d = s.dir();
+        let low = (1..=2).any(|h| s.mat(0, h).is_some_and(|m| m != Mat::Empty));
+        let dy = if low {
+            -1
+        } else {
+            (s.u.rand() % 3) as i32 - 1
+        };
+        if s.step(d, dy) || s.step(d, 0) {
+            return;
+        }
+        s.turn();
+    }
+}
+
+// === Universe integration ===
+impl Universe {
+    pub(crate) fn update_agents(&mut self) {
+        if self.agents.is_empty() {
+            return;
+        }
+        let mut agents = std::mem::take(&mut self.agents);
+        agents.retain_mut(|a| self.update_agent(a));
+        self.agents = agents;
+    }
+
+    // Returns false if the agent died this tick.
+    fn update_agent(&mut self, a: &mut Agent) -> bool {
+        let b = a.kind.behavior();
+        let i = self.idx(a.x, a.y);
+        if self.temp[i] > b.max_temp() {
+            self.kill_agent(a, true);
+            return false;
+        }
+        if b.breathes(Mat::from_u8(self.mat[i])) {
+            a.breath = b.lung_capacity();
+        } else if a.breath == 0 {
+            self.kill_agent(a, false);
+            return false;
+        } else {
+            a.breath -= 1;
+        }
+        b.act(&mut Senses { u: self, a });
+        true
+    }
+
+    fn kill_agent(&mut self, a: &Agent, burned: bool) {
+        let i = self.idx(a.x, a.y);
+        self.agent_cells[i] = false;
+        if self.mat[i] != 0 {
+            return;
+        }
+        if a.cargo != 0 {
+            self.place(i, Mat::from_u8(a.cargo));
+        } else if burned {
+            let t = self.temp[i];
+            self.place(i, Mat::Ash);
+            self.temp[i] = t;
+        }
+    }
+
+    pub(crate) fn render_agents(&mut self) {
+        for a in &self.agents {
+            let p = self.idx(a.x, a.y) * 4;
+            let [r, g, b] = a.kind.behavior().color();
+            self.pixels[p] = r;
+            self.pixels[p + 1] = g;
+            self.pixels[p + 2] = b;
+        }
+    }
+
+    pub(crate) fn rebuild_agent_cells(&mut self) {
+        self.agent_cells.fill(false);
+        for a in &self.agents {
+            let i = (a.y * self.width + a.x) as usize;
+            self.agent_cells[i] = true;
+        }
+    }
+}
+
+impl Universe {
+    // Returns false if the cell is off-grid, taken, or the world is full.
+    pub fn spawn_agent(&mut self, x: i32, y: i32, kind: Kind) -> bool {
+        if !self.in_bounds(x, y) || self.agents.len() >= MAX_AGENTS {
+            return false;
+        }
+        let i = self.idx(x, y);
+        if self.agent_cells[i] {
+            return false;
+        }
+        self.agent_cells[i] = true;
+        let dir = if self.rand() & 1 == 0 { 1 } else { -1 };
+        self.agents.push(Agent {
+            kind,
+            x,
+            y,
+            dir,
+            breath: kind.behavior().lung_capacity(),
+            cargo: 0,
+        });
+        true
+    }
+
+    pub fn agent_count(&self) -> u32 {
+        self.agents.len() as u32
+    }
+
+    // Kind of the agent standing on a cell, if any.
+    pub fn agent_at(&self, x: i32, y: i32) -> Option<Kind> {
+        if !self.in_bounds(x, y) || !self.agent_cells[self.idx(x, y)] {
+            return None;
+        }
+        self.agents
+            .iter()
+            .find(|a| a.x == x && a.y == y)
+            .map(|a| a.kind)
+    }
+}
@@ -0,0 +1,138 @@
+// === Botany ===
+// Plants run on water, carried in `life` (hydration). A Seed resting on wet
+// Soil sprouts: the seed becomes a shoot and the soil under it the first
+// Root. Roots push down through soil, drawing its moisture (or water they
+// reach directly) and passing it on up to the plant. Shoots only grow into
+// cells with open sky above, and a dry plant left in the dark rots back
+// into soil. Soil soaks up water and holds it in `life` as well, sharing it
+// with drier soil and slowly drying out. Well-watered plants drop seeds, so
+// a garden spreads by itself.
+
+use crate::{Mat, Universe};
+
+// Soil moisture a seed needs to sprout, and a root to keep growing.
+pub(crate) const SPROUT_MOISTURE: u8 = 40;
+// Hydration above which a plant sets seed.
+pub(crate) const SEEDING: u8 = 150;
+
+impl Mat {
+    // Lets sunlight through to whatever is below.
+    fn is_transparent(&self) -> bool {
+        self.is_gas()
+            || matches!(
+                self,
+                Mat::Empty | Mat::Glass | Mat::Ice | Mat::Water | Mat::SaltWater | Mat::Plant
+            )
+    }
+}
+
+impl Universe {
+    // Nothing but air (or glass, water, leaves...) straight up to the sky.
+    pub(crate) fn sunlit(&self, x: i32, y: i32) -> bool {
+        (0..y).all(|ay| Mat::from_u8(self.mat[self.idx(x, ay)]).is_transparent())
+    }
+
+    fn touches_wood(&self, x: i32, y: i32) -> bool {
+        let dirs = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+        dirs.iter().any(|&(dx, dy)| {
+            self.in_bounds(x + dx, y + dy)
+                && Mat::from_u8(self.mat[self.idx(x + dx, y + dy)]) == Mat::Wood
+        })
+    }
+
+    // Plants grow up into open air; alongside wood they can also grow
+    // sideways, so vines climb wooden structures. Either way only into
+    // daylight.
+    pub(crate) fn try_plant_growth(&mut self, x: i32, y: i32, i: usize) {
+        const CANDIDATES: [(i32, i32); 5] = [(0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)];
+        let (dx, dy) = CANDIDATES[(self.rand() as usize) % 5];
+        let nx = x + dx;
+        let ny = y + dy;
+        if !self.in_bounds(nx, ny) {
+            return;
+        }
+        let ni = self.idx(nx, ny);
+        if self.mat[ni] != 0 {
+            return;
+        }
+        // sideways growth needs a wood trellis to cling to
+        if dy == 0 && !self.touches_wood(nx, ny) {
+            return;
+        }
+        if !self.sunlit(nx, ny) {
+            return;
+        }
+        let hydration = self.life[i];
+        self.place(ni, Mat::Plant);
+        self.life[ni] = hydration.saturating_sub(40);
+        self.life[i] = hydration.saturating_sub(50);
+        self.updated[ni] = self.gen;
+    }
+
+    // A seed on wet soil sprouts, rooting into the soil below it.
+    pub(crate) fn try_germinate(&mut self, x: i32, y: i32, i: usize) -> bool {
+        if !self.in_bounds(x, y + 1) {
+            return false;
+        }
+        let bi = self.idx(x, y + 1);
+        if self.mat[bi] != Mat::Soil as u8 || self.life[bi] < SPROUT_MOISTURE || !self.chance(20) {
+            return false;
+        }
+        let moisture = self.life[bi];
+        self.place(bi, Mat::Root);
+        self.life[bi] = moisture;
+        self.place(i, Mat::Plant);
+        self.life[i] = moisture / 2;
+        true
+    }
+
+    // Roots creep down and out into soil, toward the wettest patch in
+    // reach, taking its moisture with them.
+    pub(crate) fn try_root_growth(&mut self, x: i32, y: i32, i: usize) {
+        let mut best = None;
+        let mut wettest = 0;
+        for dx in [-1, 0, 1] {
+            if !self.in_bounds(x + dx, y + 1) {
+                continue;
+            }
+            let ni = self.idx(x + dx, y + 1);
+            // ties go to a random one of the candidates
+            let moisture = self.life[ni] as u32 * 4 + (self.rand() & 3);
+            if self.mat[ni] == Mat::Soil as u8 && (best.is_none() || moisture > wettest) {
+                best = Some(ni);
+                wettest = moisture;
+            }
+        }
+        let Some(ni) = best else {
+            return;
+        };
+        let moisture = self.life[ni];
+        self.place(ni, Mat::Root);
+        self.life[ni] = moisture;
+        self.life[i] = self.life[i].saturating_sub(20);
+        self.updated[ni] = self.gen;
+    }
+
+    // Drops a seed into open space beside or below a plant.
+    pub(crate) fn drop_seed(&mut self, x: i32, y: i32, i: usize) {
+        const CANDIDATES: [(i32, i32); 3] = [(0, 1), (-1, 0), (1, 0)];
+        let (dx, dy) = CANDIDATES[(self.rand() as usize) % 3];
+        if !self.in_bounds(x + dx, y + dy) {
+            return;
+        }
+        let ni = self.idx(x + dx, y + dy);
+        if self.mat[ni] != 0 {
+            return;
+        }
+        self.place(ni, Mat::Seed);
+        self.life[i] = self.life[i].saturating_sub(60);
+    }
+
+    // Evens out the moisture of two cells.
+    pub(crate) fn share_moisture(&mut self, a: usize, b: usize) {
+        let (hi, lo) = if self.life[a] > self.life[b] { (a, b) } else { (b, a) };
+        let d = (self.life[hi] - self.life[lo]) / 2;
+        self.life[hi] -= d;
+        self.life[lo] += d;
+    }
+}
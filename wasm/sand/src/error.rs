@@ -0,0 +1,44 @@
+// === Errors ===
+// What the public API reports when it's handed something it can't use.
+// Everything that takes raw numbers from outside (world sizes, material
+// and agent ids, brush radii, time settings) is checked up front rather
+// than clamped or quietly ignored. On the wasm side these become JS
+// exceptions.
+
+use crate::time::{MAX_SUBSTEPS, MIN_TIME_SCALE};
+use crate::{MAX_CELLS, MAX_SIDE};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    // empty, more than MAX_CELLS cells or a side over MAX_SIDE
+    BadSize { width: u32, height: u32 },
+    UnknownMat(u8),
+    UnknownKind(u8),
+    BadRadius(i32),
+    BadSubsteps(u32),
+    BadTimeScale(f32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::BadSize { width, height } => write!(
+                f,
+                "world size {}x{} is out of range (1 to {} cells, {} a side)",
+                width, height, MAX_CELLS, MAX_SIDE
+            ),
+            Error::UnknownMat(v) => write!(f, "unknown material id {}", v),
+            Error::UnknownKind(v) => write!(f, "unknown agent kind {}", v),
+            Error::BadRadius(r) => write!(f, "brush radius {} is negative", r),
+            Error::BadSubsteps(n) => {
+                write!(f, "substeps must be 1 to {}, got {}", MAX_SUBSTEPS, n)
+            }
+            Error::BadTimeScale(s) => {
+                write!(f, "time scale must be {} to 1, got {}", MIN_TIME_SCALE, s)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
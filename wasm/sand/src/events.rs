@@ -0,0 +1,117 @@
+// === Events ===
+// Things worth a sound or a screen shake, collected during `tick` so the
+// frontend doesn't have to scan the grid. Individual cell events are binned
+// into REGION x REGION blocks per kind; each bin becomes one event at the
+// centroid of what happened there.
+
+use crate::Universe;
+
+// Kind bits. `tick` returns the OR of every kind that fired; `events`
+// carries the details.
+pub const EVT_EXPLOSION: u32 = 1; // magnitude: blast radius
+pub const EVT_IGNITE: u32 = 2; // something caught fire
+pub const EVT_HISS: u32 = 4; // water meeting lava, fire or embers
+pub const EVT_GLASS: u32 = 8; // sand vitrified
+pub const EVT_IMPACT: u32 = 16; // magnitude: landing speed
+
+const KINDS: usize = 5;
+const REGION: i32 = 16;
+
+// Floats per entry in `events`: kind, x, y, count, magnitude.
+pub const EVENT_STRIDE: usize = 5;
+
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    count: u32,
+    sum_x: f32,
+    sum_y: f32,
+    magnitude: f32, // strongest single event in the bin
+}
+
+pub(crate) struct EventLog {
+    cols: usize,
+    bins: Vec<Bin>,
+    touched: Vec<usize>,
+    mask: u32,
+    out: Vec<f32>,
+}
+
+impl EventLog {
+    pub(crate) fn new(width: i32, height: i32) -> EventLog {
+        let cols = ((width + REGION - 1) / REGION) as usize;
+        let rows = ((height + REGION - 1) / REGION) as usize;
+        EventLog {
+            cols,
+            bins: vec![Bin::default(); cols * rows * KINDS],
+            touched: Vec::new(),
+            mask: 0,
+            out: Vec::new(),
+        }
+    }
+
+    fn begin(&mut self) {
+        for &b in &self.touched {
+            self.bins[b] = Bin::default();
+        }
+        self.touched.clear();
+        self.mask = 0;
+        self.out.clear();
+    }
+
+    fn push(&mut self, kind: u32, x: i32, y: i32, magnitude: f32) {
+        let k = kind.trailing_zeros() as usize;
+        let region = (y / REGION) as usize * self.cols + (x / REGION) as usize;
+        let b = region * KINDS + k;
+        let bin = &mut self.bins[b];
+        if bin.count == 0 {
+            self.touched.push(b);
+        }
+        bin.count += 1;
+        bin.sum_x += x as f32;
+        bin.sum_y += y as f32;
+        bin.magnitude = bin.magnitude.max(magnitude);
+        self.mask |= kind;
+    }
+
+    fn finish(&mut self) -> u32 {
+        for &b in &self.touched {
+            let bin = self.bins[b];
+            let n = bin.count as f32;
+            self.out.extend_from_slice(&[
+                (1u32 << (b % KINDS)) as f32,
+                bin.sum_x / n,
+                bin.sum_y / n,
+                n,
+                bin.magnitude,
+            ]);
+        }
+        self.mask
+    }
+}
+
+impl Universe {
+    pub(crate) fn begin_events(&mut self) {
+        self.events.begin();
+    }
+
+    pub(crate) fn finish_events(&mut self) -> u32 {
+        self.events.finish()
+    }
+
+    #[inline]
+    pub(crate) fn emit(&mut self, kind: u32, x: i32, y: i32, magnitude: f32) {
+        self.events.push(kind, x, y, magnitude);
+    }
+}
+
+impl Universe {
+    // Events from the last tick, EVENT_STRIDE floats each:
+    // [kind, x, y, count, magnitude, ...] with x/y in cells.
+    pub fn events(&self) -> Vec<f32> {
+        self.events.out.clone()
+    }
+
+    pub fn event_count(&self) -> u32 {
+        (self.events.out.len() / EVENT_STRIDE) as u32
+    }
+}
@@ -0,0 +1,201 @@
+// === Update hooks ===
+// Per-material callbacks registered by the host, so new behaviors can be
+// prototyped without touching `update_cell`. A hook sees a 3x3 snapshot of
+// the cell and its neighbors and queues edits on it; the edits are applied
+// to the world once the hook returns. Returning true means the hook handled
+// the cell and the built-in update is skipped.
+
+use crate::{Mat, Universe};
+
+// Marks the slots of a neighborhood that fall past the edge of the world.
+pub(crate) const OFF_GRID: u8 = 255;
+
+#[derive(Clone, Copy)]
+enum Op {
+    Swap(i32, i32),
+    Convert(i32, i32, Mat),
+    SetTemp(i32, i32, f32),
+}
+
+pub struct Neighborhood {
+    x: i32,
+    y: i32,
+    mats: [u8; 9],
+    temps: [f32; 9],
+    ops: Vec<Op>,
+    rng: u32,
+    unhook: bool,
+}
+
+// Offsets outside the 3x3 window are rejected rather than clamped.
+fn slot(dx: i32, dy: i32) -> Option<usize> {
+    if (-1..=1).contains(&dx) && (-1..=1).contains(&dy) {
+        Some(((dy + 1) * 3 + dx + 1) as usize)
+    } else {
+        None
+    }
+}
+
+impl Neighborhood {
+    #[cfg(feature = "wasm")]
+    pub(crate) fn empty() -> Neighborhood {
+        Neighborhood {
+            x: 0,
+            y: 0,
+            mats: [OFF_GRID; 9],
+            temps: [f32::NAN; 9],
+            ops: Vec::new(),
+            rng: 1,
+            unhook: false,
+        }
+    }
+
+    // Unregisters the running hook once it returns; its edits are dropped.
+    #[cfg(feature = "wasm")]
+    pub(crate) fn unhook(&mut self) {
+        self.unhook = true;
+    }
+
+    pub(crate) fn raw_mat(&self, dx: i32, dy: i32) -> u8 {
+        slot(dx, dy).map_or(OFF_GRID, |s| self.mats[s])
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    // None past the edge of the world.
+    pub fn mat(&self, dx: i32, dy: i32) -> Option<Mat> {
+        match self.raw_mat(dx, dy) {
+            OFF_GRID => None,
+            m => Some(Mat::from_u8(m)),
+        }
+    }
+
+    pub fn temp(&self, dx: i32, dy: i32) -> f32 {
+        slot(dx, dy).map_or(f32::NAN, |s| self.temps[s])
+    }
+
+    // Deterministic per-cell randomness (0..1), drawn from the world seed.
+    pub fn random(&mut self) -> f32 {
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        (x & 0xFFFF) as f32 / 65535.0
+    }
+
+    // Swap this cell with a neighbor. Returns false if it's off-grid.
+    pub fn swap(&mut self, dx: i32, dy: i32) -> bool {
+        match slot(dx, dy) {
+            Some(s) if self.mats[s] != OFF_GRID => {
+                self.mats.swap(4, s);
+                self.temps.swap(4, s);
+                self.ops.push(Op::Swap(dx, dy));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn convert(&mut self, dx: i32, dy: i32, m: Mat) -> bool {
+        match slot(dx, dy) {
+            Some(s) if self.mats[s] != OFF_GRID => {
+                self.mats[s] = m as u8;
+                self.temps[s] = m.base_temperature();
+                self.ops.push(Op::Convert(dx, dy, m));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_temp(&mut self, dx: i32, dy: i32, t: f32) -> bool {
+        match slot(dx, dy) {
+            Some(s) if self.mats[s] != OFF_GRID && t.is_finite() => {
+                self.temps[s] = t;
+                self.ops.push(Op::SetTemp(dx, dy, t));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Send, so a Universe with hooks can still move to another thread.
+pub(crate) type Hook = Box<dyn FnMut(&mut Neighborhood) -> bool + Send>;
+
+impl Universe {
+    // Native hook: `hook` runs for every `mat` cell during `tick`, in scan
+    // order, before the built-in update.
+    pub fn set_hook(&mut self, mat: Mat, hook: impl FnMut(&mut Neighborhood) -> bool + Send + 'static) {
+        self.hooks[mat as usize] = Some(Box::new(hook));
+    }
+
+    pub fn clear_hook(&mut self, mat: Mat) {
+        self.hooks[mat as usize] = None;
+    }
+
+    fn neighborhood(&mut self, x: i32, y: i32) -> Neighborhood {
+        let mut nb = Neighborhood {
+            x,
+            y,
+            mats: [OFF_GRID; 9],
+            temps: [f32::NAN; 9],
+            ops: Vec::new(),
+            rng: self.rand() | 1,
+            unhook: false,
+        };
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if self.in_bounds(x + dx, y + dy) {
+                    let i = self.idx(x + dx, y + dy);
+                    let s = ((dy + 1) * 3 + dx + 1) as usize;
+                    nb.mats[s] = self.mat[i];
+                    nb.temps[s] = self.temp[i];
+                }
+            }
+        }
+        nb
+    }
+
+    // Runs the hook for material `m` at (x, y), if any. Returns true if the
+    // hook consumed the cell.
+    pub(crate) fn run_hook(&mut self, x: i32, y: i32, m: Mat) -> bool {
+        let Some(mut hook) = self.hooks[m as usize].take() else {
+            return false;
+        };
+        let mut nb = self.neighborhood(x, y);
+        let handled = hook(&mut nb);
+        if nb.unhook {
+            return false;
+        }
+        self.hooks[m as usize] = Some(hook);
+        self.apply_ops(x, y, &nb.ops);
+        handled
+    }
+
+    fn apply_ops(&mut self, x: i32, y: i32, ops: &[Op]) {
+        let center = self.idx(x, y);
+        for &op in ops {
+            match op {
+                Op::Swap(dx, dy) => {
+                    let ni = self.idx(x + dx, y + dy);
+                    self.swap_cells(center, ni);
+                }
+                Op::Convert(dx, dy, m) => {
+                    let ni = self.idx(x + dx, y + dy);
+                    self.convert(ni, m);
+                }
+                Op::SetTemp(dx, dy, t) => {
+                    let ni = self.idx(x + dx, y + dy);
+                    self.temp[ni] = t;
+                }
+            }
+        }
+    }
+}
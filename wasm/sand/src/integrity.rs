@@ -0,0 +1,190 @@
+// === Structural integrity ===
+// Static materials hold up only while they're connected to something that
+// bears their weight: the floor, the side walls, or a heap of powder that
+// itself reaches down to solid footing. Load travels through neighboring
+// static cells; stacking straight up is free, but every step sideways (or
+// hanging down) counts against the material's `strength`, so over-long
+// spans give way. Unsupported brittle cells crumble to powder, the rest
+// drop as rigid chunks.
+
+use crate::{Mat, Universe};
+use std::collections::VecDeque;
+
+const UNSUPPORTED: u8 = u8::MAX;
+const IN_CHUNK: u8 = u8::MAX - 1;
+const DONE: u8 = u8::MAX - 2;
+
+impl Universe {
+    fn load_bearing(&self, i: usize) -> Option<u8> {
+        // molten glass and metal are flowing, not holding anything up
+        if self.is_molten(i) {
+            return None;
+        }
+        Mat::from_u8(self.mat[i]).strength()
+    }
+
+    pub(crate) fn settle_structures(&mut self) {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        let mut support = std::mem::take(&mut self.support);
+        support.fill(UNSUPPORTED);
+
+        // 0-1 BFS outward from the anchors; support[i] is the cheapest
+        // sideways reach needed to get load from cell i to an anchor.
+        // Powder on the floor is an anchor too, and carries support
+        // straight up its column (a heap bears what sits on it, but only
+        // if the heap itself is standing on something).
+        let mut queue = VecDeque::new();
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let anchored = if self.load_bearing(i).is_some() {
+                    y == h - 1 || x == 0 || x == w - 1
+                } else {
+                    y == h - 1 && Mat::from_u8(self.mat[i]).is_powder()
+                };
+                if anchored {
+                    support[i] = 0;
+                    queue.push_back(i);
+                }
+            }
+        }
+        while let Some(i) = queue.pop_front() {
+            let c = support[i];
+            let (x, y) = (i % w, i / w);
+            let on_heap = Mat::from_u8(self.mat[i]).is_powder();
+            for (dx, dy) in [(0i32, -1i32), (0, 1), (1, 0), (-1, 0)] {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if !self.in_bounds(nx, ny) || (on_heap && dy != -1) {
+                    continue;
+                }
+                let ni = ny as usize * w + nx as usize;
+                if dy == -1 && Mat::from_u8(self.mat[ni]).is_powder() {
+                    // powder resting on support passes it upward
+                    if support[ni] != 0 {
+                        support[ni] = 0;
+                        queue.push_front(ni);
+                    }
+                    continue;
+                }
+                let Some(strength) = self.load_bearing(ni) else {
+                    continue;
+                };
+                let nc = if on_heap || dy == -1 { c } else { c + 1 };
+                if nc > strength || nc >= support[ni] {
+                    continue;
+                }
+                let nc = if on_heap { 0 } else { nc };
+                support[ni] = nc;
+                if dy == -1 {
+                    queue.push_front(ni);
+                } else {
+                    queue.push_back(ni);
+                }
+            }
+        }
+
+        let mut chunk = Vec::new();
+        for i in 0..w * h {
+            if support[i] != UNSUPPORTED || self.load_bearing(i).is_none() {
+                continue;
+            }
+            let m = Mat::from_u8(self.mat[i]);
+            if let Some(r) = m.rubble() {
+                // crumble a bit at a time rather than all in one frame
+                // (hashed, so this pass leaves the reaction rng alone)
+                let roll = (i as u32).wrapping_mul(0x9E37_79B1) >> 16;
+                if (roll + self.gen as u32).is_multiple_of(3) {
+                    let t = self.temp[i];
+                    self.place(i, r);
+                    self.temp[i] = t;
+                }
+                support[i] = DONE;
+                continue;
+            }
+            self.collect_chunk(i, &mut support, &mut chunk);
+            self.drop_chunk(&mut support, &chunk);
+        }
+        self.support = support;
+    }
+
+    // Flood-fills the unsupported rigid cells connected to `start`.
+    fn collect_chunk(&self, start: usize, support: &mut [u8], chunk: &mut Vec<usize>) {
+        let w = self.width as usize;
+        chunk.clear();
+        support[start] = IN_CHUNK;
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            chunk.push(i);
+            let (x, y) = ((i % w) as i32, (i / w) as i32);
+            for (dx, dy) in [(0, -1), (0, 1), (1, 0), (-1, 0)] {
+                if !self.in_bounds(x + dx, y + dy) {
+                    continue;
+                }
+                let ni = self.idx(x + dx, y + dy);
+                if support[ni] == UNSUPPORTED
+                    && self.load_bearing(ni).is_some()
+                    && Mat::from_u8(self.mat[ni]).rubble().is_none()
+                {
+                    support[ni] = IN_CHUNK;
+                    stack.push(ni);
+                }
+            }
+        }
+    }
+
+    // Moves a chunk one cell if nothing holds it up. It sinks through
+    // lighter fluids, so wood floats and metal goes to the bottom. Magnets
+    // (see `apply_magnets`) can drag loose metal sideways or up instead,
+    // wherever their pull beats gravity.
+    fn drop_chunk(&mut self, support: &mut [u8], chunk: &[usize]) {
+        let (mut px, mut py) = (0.0, 0.0);
+        for &i in chunk {
+            if self.mat[i] == Mat::Metal as u8 {
+                px += self.vx[i];
+                py += self.vy[i];
+            }
+        }
+        px /= chunk.len() as f32;
+        py = py / chunk.len() as f32 + 1.0;
+        let pulled = if px.abs() > py.abs() {
+            (px.signum() as i32, 0)
+        } else {
+            (0, py.signum() as i32)
+        };
+        if !self.move_chunk(support, chunk, pulled) && pulled.0 != 0 {
+            self.move_chunk(support, chunk, (0, 1));
+        }
+        for &i in chunk {
+            support[i] = DONE;
+        }
+    }
+
+    fn move_chunk(&mut self, support: &mut [u8], chunk: &[usize], (dx, dy): (i32, i32)) -> bool {
+        let w = self.width;
+        let mut moves = Vec::with_capacity(chunk.len());
+        for &i in chunk {
+            let (x, y) = (i as i32 % w + dx, i as i32 / w + dy);
+            if !self.in_bounds(x, y) {
+                return false;
+            }
+            let t = self.idx(x, y);
+            let tm = Mat::from_u8(self.mat[t]);
+            let free = support[t] == IN_CHUNK
+                || tm == Mat::Empty
+                || tm.is_gas()
+                || (tm.is_liquid() && tm.density() < Mat::from_u8(self.mat[i]).density());
+            if !free {
+                return false;
+            }
+            moves.push((i, t));
+        }
+        // leading edge first, so cells don't land on each other
+        moves.sort_unstable_by_key(|&(i, _)| -((i as i32 % w) * dx + (i as i32 / w) * dy));
+        for (i, t) in moves {
+            self.swap_cells(i, t);
+            support[t] = DONE;
+        }
+        true
+    }
+}
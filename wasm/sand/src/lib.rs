@@ -1,4 +1,44 @@
-use wasm_bindgen::prelude::*;
+// Reaction rules read as "material => threshold check"; keep them nested.
+#![allow(clippy::collapsible_match)]
+
+// Tracing hooks; these compile to nothing without the `trace` feature.
+macro_rules! trace {
+    ($($hook:tt)*) => {
+        #[cfg(feature = "trace")]
+        {
+            $($hook)*;
+        }
+    };
+}
+
+mod agents;
+mod botany;
+mod error;
+mod events;
+mod hooks;
+mod integrity;
+mod lockstep;
+mod magnet;
+mod save;
+mod thermal;
+mod time;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use agents::Agent;
+use botany::{SEEDING, SPROUT_MOISTURE};
+use events::EventLog;
+use hooks::Hook;
+use lockstep::Command;
+use time::now_ms;
+pub use events::{EVENT_STRIDE, EVT_EXPLOSION, EVT_GLASS, EVT_HISS, EVT_IGNITE, EVT_IMPACT};
+pub use agents::Kind;
+pub use error::Error;
+pub use hooks::Neighborhood;
+pub use lockstep::CHUNK;
+pub use time::{HEAT, MOVEMENT, RENDER};
 
 // === Materials ===
 #[repr(u8)]
@@ -25,12 +65,33 @@ pub enum Mat {
     Ember = 18,
     Ash = 19,
     Metal = 20,
+    Rubble = 21,
+    Hydrogen = 22,
+    Methane = 23,
+    Chlorine = 24,
+    Rust = 25,
+    Magnet = 26,
+    Seed = 27,
+    Soil = 28,
+    Root = 29,
 }
 
-const MAT_COUNT: u8 = 21;
+const MAT_COUNT: u8 = 30;
+
+impl TryFrom<u8> for Mat {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Mat, Error> {
+        if v < MAT_COUNT {
+            Ok(Mat::from_u8(v))
+        } else {
+            Err(Error::UnknownMat(v))
+        }
+    }
+}
 
 impl Mat {
-    fn from_u8(v: u8) -> Mat {
+    pub(crate) fn from_u8(v: u8) -> Mat {
         if v >= MAT_COUNT {
             return Mat::Empty;
         }
@@ -38,7 +99,17 @@ impl Mat {
     }
 
     fn is_powder(&self) -> bool {
-        matches!(self, Mat::Sand | Mat::Gunpowder | Mat::Salt | Mat::Ash)
+        matches!(
+            self,
+            Mat::Sand
+                | Mat::Gunpowder
+                | Mat::Salt
+                | Mat::Ash
+                | Mat::Rubble
+                | Mat::Rust
+                | Mat::Seed
+                | Mat::Soil
+        )
     }
 
     fn is_liquid(&self) -> bool {
@@ -46,7 +117,10 @@ impl Mat {
     }
 
     fn is_gas(&self) -> bool {
-        matches!(self, Mat::Fire | Mat::Steam | Mat::Smoke)
+        matches!(
+            self,
+            Mat::Fire | Mat::Steam | Mat::Smoke | Mat::Hydrogen | Mat::Methane | Mat::Chlorine
+        )
     }
 
     fn is_static(&self) -> bool {
@@ -55,11 +129,13 @@ impl Mat {
             Mat::Stone
                 | Mat::Wood
                 | Mat::Plant
+                | Mat::Root
                 | Mat::Ice
                 | Mat::Glass
                 | Mat::Obsidian
                 | Mat::Ember
                 | Mat::Metal
+                | Mat::Magnet
         )
     }
 
@@ -67,26 +143,52 @@ impl Mat {
         self.is_powder() || self.is_static()
     }
 
+    // Cells that hold breathable air. Smoke and steam displace it, and
+    // everything denser has none to give.
+    fn carries_air(&self) -> bool {
+        matches!(self, Mat::Empty | Mat::Fire)
+    }
+
     // Relative density: heavier sinks below lighter. Empty = 0.
     fn density(&self) -> i8 {
         match self {
-            Mat::Stone | Mat::Obsidian | Mat::Metal => 100,
+            Mat::Stone | Mat::Obsidian | Mat::Metal | Mat::Magnet => 100,
             Mat::Glass => 60,
+            Mat::Rubble => 58,
             Mat::Sand => 55,
             Mat::Salt => 52,
+            Mat::Rust | Mat::Soil => 50,
             Mat::Gunpowder => 48,
             Mat::Lava => 45,
+            Mat::Seed => 35,
             Mat::SaltWater => 32,
             Mat::Water | Mat::Acid => 30,
             Mat::Ice => 25,
-            Mat::Wood | Mat::Plant | Mat::Ember => 20,
+            Mat::Wood | Mat::Plant | Mat::Ember | Mat::Root => 20,
             Mat::Ash => 15,
             Mat::Oil => 10,
+            // heavier than air but lighter than any liquid: pools on the
+            // floor and floats on water
+            Mat::Chlorine => 5,
             Mat::Fire | Mat::Steam | Mat::Smoke => -10,
+            Mat::Methane => -15,
+            Mat::Hydrogen => -20,
             Mat::Empty => 0,
         }
     }
 
+    // Fractional density lost per degree above base_temperature. Only
+    // fluids expand noticeably; this is what makes heated water rise.
+    fn expansion(&self) -> f32 {
+        match self {
+            Mat::Water | Mat::SaltWater | Mat::Acid | Mat::Oil => 0.004,
+            Mat::Lava => 0.0005,
+            Mat::Steam | Mat::Smoke | Mat::Hydrogen | Mat::Methane | Mat::Chlorine => 0.004,
+            Mat::Fire => 0.001,
+            _ => 0.0,
+        }
+    }
+
     fn base_temperature(&self) -> f32 {
         match self {
             Mat::Fire => 650.0,
@@ -103,17 +205,20 @@ impl Mat {
     fn conductivity(&self) -> f32 {
         match self {
             Mat::Metal => 0.45,
+            Mat::Magnet => 0.30,
             Mat::Water | Mat::SaltWater | Mat::Acid => 0.18,
             Mat::Lava => 0.10,
             // steam barely exchanges heat in bulk, so it can rise a long
             // way before condensing instead of raining out immediately
             Mat::Steam => 0.03,
             Mat::Smoke | Mat::Fire => 0.20,
-            Mat::Stone => 0.15,
-            Mat::Sand | Mat::Salt | Mat::Ash | Mat::Gunpowder => 0.08,
+            Mat::Hydrogen => 0.25,
+            Mat::Methane | Mat::Chlorine => 0.10,
+            Mat::Stone | Mat::Rubble => 0.15,
+            Mat::Sand | Mat::Salt | Mat::Ash | Mat::Gunpowder | Mat::Rust | Mat::Soil => 0.08,
             Mat::Obsidian | Mat::Glass => 0.05,
             Mat::Ice => 0.12,
-            Mat::Wood | Mat::Plant | Mat::Ember => 0.06,
+            Mat::Wood | Mat::Plant | Mat::Ember | Mat::Root | Mat::Seed => 0.06,
             Mat::Oil => 0.10,
             Mat::Empty => 0.12,
         }
@@ -126,6 +231,8 @@ impl Mat {
             Mat::Plant => Some(200.0),
             Mat::Oil => Some(230.0),
             Mat::Gunpowder => Some(170.0),
+            Mat::Hydrogen => Some(240.0),
+            Mat::Methane => Some(540.0),
             _ => None,
         }
     }
@@ -153,10 +260,60 @@ impl Mat {
             Mat::Ember => [185, 70, 22],
             Mat::Ash => [108, 104, 98],
             Mat::Metal => [138, 144, 155],
+            Mat::Rubble => [96, 92, 88],
+            Mat::Hydrogen => [200, 215, 235],
+            Mat::Methane => [170, 190, 150],
+            Mat::Chlorine => [190, 215, 80],
+            Mat::Rust => [140, 72, 38],
+            Mat::Magnet => [175, 45, 55],
+            Mat::Seed => [196, 160, 84],
+            Mat::Soil => [96, 66, 42],
+            Mat::Root => [168, 128, 84],
+        }
+    }
+
+    // Temperature above which a static material softens and flows like a
+    // liquid; it sets again (metal casts) once it cools back down.
+    fn melting_point(&self) -> Option<f32> {
+        match self {
+            Mat::Glass => Some(900.0),
+            Mat::Metal => Some(1000.0),
+            _ => None,
+        }
+    }
+
+    // How many cells a static material can reach out sideways (or hang
+    // down) from its support before it gives way. None: not load-bearing,
+    // never collapses (vines hang off anything).
+    fn strength(&self) -> Option<u8> {
+        match self {
+            Mat::Metal => Some(32),
+            Mat::Magnet => Some(16),
+            Mat::Wood => Some(12),
+            Mat::Obsidian => Some(10),
+            Mat::Stone => Some(8),
+            Mat::Ember | Mat::Ice => Some(6),
+            Mat::Glass => Some(4),
+            _ => None,
+        }
+    }
+
+    // What a brittle material breaks into when unsupported; everything
+    // else load-bearing falls as a rigid chunk.
+    fn rubble(&self) -> Option<Mat> {
+        match self {
+            Mat::Stone | Mat::Obsidian => Some(Mat::Rubble),
+            Mat::Glass => Some(Mat::Sand),
+            _ => None,
         }
     }
 }
 
+// Largest world `new` will make (4096x4096, or any other shape that size).
+pub const MAX_CELLS: usize = 1 << 24;
+// Longest side, as snapshots store agent positions in 16 bits.
+pub const MAX_SIDE: u32 = u16::MAX as u32;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum MoveResult {
     Moved,
@@ -165,10 +322,18 @@ enum MoveResult {
 }
 
 const GRAVITY: f32 = 0.18;
+// Smallest density difference that makes one fluid sink through another,
+// so near-equal temperatures don't churn a still pool.
+const BUOYANCY_MIN: f32 = 0.5;
 const MAX_FALL: f32 = 4.0;
 const AMBIENT: f32 = 20.0;
+const AIR_FULL: u8 = 255;
+// oxygen drawn per tick by a burning cell
+const FIRE_DRAW: u8 = 6;
+const EMBER_DRAW: u8 = 3;
+// fuel won't catch unless this much oxygen is at hand
+const IGNITE_O2: u8 = 40;
 
-#[wasm_bindgen]
 pub struct Universe {
     width: i32,
     height: i32,
@@ -176,36 +341,67 @@ pub struct Universe {
     vx: Vec<f32>,
     vy: Vec<f32>,
     temp: Vec<f32>,
-    temp_back: Vec<f32>,
+    heat_in: Vec<f32>, // scratch: energy conducted into each cell
+    latent: Vec<f32>,  // heat banked toward a phase change
     life: Vec<u8>,
+    oxygen: Vec<u8>, // only meaningful in air cells (see carries_air)
     variant: Vec<u8>,
     updated: Vec<u8>, // generation stamp of last move
     pixels: Vec<u8>,  // RGBA output
+    agents: Vec<Agent>,
+    agent_cells: Vec<bool>, // occupancy, so agents don't stack
+    support: Vec<u8>,       // scratch for the integrity pass
+    hooks: Vec<Option<Hook>>, // per-material update callbacks
+    events: EventLog,
+    commands: Vec<Command>, // queued remote input, by (frame, player)
+    frame: u32,
+    heat_sourced: f64,
+    heat_lost: f64,
     gen: u8,
     rng: u32,
     heat_view: bool,
+    paused: bool,
+    substeps: u32,   // steps per `tick`
+    time_scale: f32, // slow motion below 1
+    #[cfg(feature = "trace")]
+    tracer: trace::Tracer,
 }
 
-#[wasm_bindgen]
 impl Universe {
-    pub fn new(width: u32, height: u32) -> Universe {
-        let n = (width * height) as usize;
-        Universe {
+    pub fn new(width: u32, height: u32) -> Result<Universe, Error> {
+        let n = cells(width, height)?;
+        Ok(Universe {
             width: width as i32,
             height: height as i32,
             mat: vec![0; n],
             vx: vec![0.0; n],
             vy: vec![0.0; n],
             temp: vec![AMBIENT; n],
-            temp_back: vec![AMBIENT; n],
+            heat_in: vec![0.0; n],
+            latent: vec![0.0; n],
             life: vec![0; n],
+            oxygen: vec![AIR_FULL; n],
             variant: vec![0; n],
             updated: vec![0; n],
             pixels: vec![0; n * 4],
+            agents: Vec::new(),
+            agent_cells: vec![false; n],
+            support: vec![0; n],
+            hooks: (0..MAT_COUNT).map(|_| None).collect(),
+            events: EventLog::new(width as i32, height as i32),
+            commands: Vec::new(),
+            frame: 0,
+            heat_sourced: 0.0,
+            heat_lost: 0.0,
             gen: 0,
             rng: 0xB45BE,
             heat_view: false,
-        }
+            paused: false,
+            substeps: 1,
+            time_scale: 1.0,
+            #[cfg(feature = "trace")]
+            tracer: Default::default(),
+        })
     }
 
     pub fn width(&self) -> u32 {
@@ -214,17 +410,33 @@ impl Universe {
     pub fn height(&self) -> u32 {
         self.height as u32
     }
-    pub fn pixels(&self) -> *const u8 {
-        self.pixels.as_ptr()
+    // RGBA, row-major, as of the last `render`.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
     }
     pub fn set_heat_view(&mut self, on: bool) {
         self.heat_view = on;
     }
-    pub fn mat_at(&self, x: i32, y: i32) -> u8 {
+    pub fn mat_at(&self, x: i32, y: i32) -> Mat {
         if self.in_bounds(x, y) {
-            self.mat[self.idx(x, y)]
+            Mat::from_u8(self.mat[self.idx(x, y)])
         } else {
-            0
+            Mat::Empty
+        }
+    }
+    pub fn temp_at(&self, x: i32, y: i32) -> f32 {
+        if self.in_bounds(x, y) {
+            self.temp[self.idx(x, y)]
+        } else {
+            AMBIENT
+        }
+    }
+    // Oxygen in the cell's air, 0 for anything but air.
+    pub fn oxygen_at(&self, x: i32, y: i32) -> u8 {
+        if self.in_bounds(x, y) {
+            self.oxygen[self.idx(x, y)]
+        } else {
+            AIR_FULL
         }
     }
 
@@ -233,54 +445,52 @@ impl Universe {
         self.vx.fill(0.0);
         self.vy.fill(0.0);
         self.temp.fill(AMBIENT);
-        self.temp_back.fill(AMBIENT);
+        self.latent.fill(0.0);
         self.life.fill(0);
+        self.oxygen.fill(AIR_FULL);
         self.updated.fill(0);
+        self.agents.clear();
+        self.agent_cells.fill(false);
     }
 
-    pub fn paint(&mut self, row: i32, col: i32, mat_val: u8, radius: i32) {
-        let m = Mat::from_u8(mat_val);
-        let r2 = radius * radius;
-        for dr in -radius..=radius {
-            for dc in -radius..=radius {
+    // A disc of `m` centred on (row, col); it may hang off the grid, and a
+    // negative radius paints nothing.
+    pub fn paint(&mut self, row: i32, col: i32, m: Mat, radius: i32) {
+        let r2 = radius as i64 * radius as i64;
+        // only visit the part of the brush that's on the grid
+        let (y0, y1) = (row.saturating_sub(radius), row.saturating_add(radius));
+        let (x0, x1) = (col.saturating_sub(radius), col.saturating_add(radius));
+        for y in y0.max(0)..=y1.min(self.height - 1) {
+            for x in x0.max(0)..=x1.min(self.width - 1) {
+                let (dr, dc) = ((y - row) as i64, (x - col) as i64);
                 if dr * dr + dc * dc > r2 {
                     continue;
                 }
-                let y = row + dr;
-                let x = col + dc;
-                if !self.in_bounds(x, y) {
-                    continue;
-                }
                 // Sparse spray for powders/liquids feels much nicer with big brushes
                 if radius > 2 && m != Mat::Empty && !m.is_static() && (self.rand() & 3) == 0 {
                     continue;
                 }
                 let i = self.idx(x, y);
                 self.place(i, m);
+                // erasing lets fresh air in
+                if m == Mat::Empty {
+                    self.oxygen[i] = AIR_FULL;
+                }
             }
         }
     }
 
-    pub fn tick(&mut self) {
-        self.gen = self.gen.wrapping_add(1);
-        self.diffuse_heat();
-
-        let ltr = self.gen & 1 == 0;
-        // Bottom-up scan: falling things see free space below before it's claimed;
-        // gases rising are stamped so they're not re-updated this tick.
-        for y in (0..self.height).rev() {
-            if ltr {
-                for x in 0..self.width {
-                    self.update_cell(x, y);
-                }
-            } else {
-                for x in (0..self.width).rev() {
-                    self.update_cell(x, y);
-                }
+    // Advances the world by `substeps` steps (none while paused). Returns
+    // the EVT_* bits for everything that happened; see `events` for where.
+    pub fn tick(&mut self) -> u32 {
+        self.begin_events();
+        if !self.paused {
+            for _ in 0..self.substeps {
+                self.advance(None);
             }
         }
+        self.finish_events()
     }
-
     pub fn render(&mut self) {
         let n = (self.width * self.height) as usize;
         for i in 0..n {
@@ -303,6 +513,13 @@ impl Universe {
                         r = (r as i32 + f).clamp(0, 255) as u8;
                         g = (g as i32 + f / 2).clamp(0, 255) as u8;
                     }
+                    // wet soil is darker
+                    if m == Mat::Soil {
+                        let wet = self.life[i] as f32 / 255.0 * 0.45;
+                        r = lerp_u8(r, 0, wet);
+                        g = lerp_u8(g, 0, wet);
+                        b = lerp_u8(b, 0, wet);
+                    }
                 }
                 // incandescent glow for anything hot
                 if t > 300.0 {
@@ -326,6 +543,7 @@ impl Universe {
             self.pixels[p + 2] = b;
             self.pixels[p + 3] = 255;
         }
+        self.render_agents();
     }
 }
 
@@ -359,6 +577,10 @@ impl Universe {
         self.vx[i] = 0.0;
         self.vy[i] = 0.0;
         self.temp[i] = m.base_temperature();
+        self.latent[i] = 0.0;
+        if !m.carries_air() {
+            self.oxygen[i] = 0;
+        }
         self.variant[i] = (self.rand() & 15) as u8;
         self.life[i] = match m {
             Mat::Fire => 20 + (self.rand() % 30) as u8,
@@ -382,71 +604,147 @@ impl Universe {
         self.vx.swap(a, b);
         self.vy.swap(a, b);
         self.temp.swap(a, b);
+        self.latent.swap(a, b);
         self.life.swap(a, b);
+        self.oxygen.swap(a, b);
+        // only air holds oxygen, so nothing else carries any into a room
+        for i in [a, b] {
+            if !Mat::from_u8(self.mat[i]).carries_air() {
+                self.oxygen[i] = 0;
+            }
+        }
         self.variant.swap(a, b);
         self.updated[a] = self.gen;
         self.updated[b] = self.gen;
+        trace!(self.trace_swap(a, b));
     }
 
-    // === Heat ===
-    fn diffuse_heat(&mut self) {
-        let w = self.width;
-        let h = self.height;
+    // === Air ===
+    fn diffuse_air(&mut self) {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        // open sky: air with a clear line up to the top edge is replenished;
+        // liquids and solids seal off what's below them
+        for x in 0..w {
+            for y in 0..h {
+                let i = y * w + x;
+                let m = Mat::from_u8(self.mat[i]);
+                if m != Mat::Empty && !m.is_gas() {
+                    break;
+                }
+                if m.carries_air() {
+                    self.oxygen[i] += (AIR_FULL - self.oxygen[i]) / 4;
+                }
+            }
+        }
+        // pairwise exchange with the right/lower neighbor conserves the
+        // total, so a sealed room only has the oxygen it started with
         for y in 0..h {
             for x in 0..w {
-                let i = self.idx(x, y);
-                let m = Mat::from_u8(self.mat[i]);
+                let i = y * w + x;
+                if !Mat::from_u8(self.mat[i]).carries_air() {
+                    continue;
+                }
+                if x + 1 < w && Mat::from_u8(self.mat[i + 1]).carries_air() {
+                    self.exchange_air(i, i + 1);
+                }
+                if y + 1 < h && Mat::from_u8(self.mat[i + w]).carries_air() {
+                    self.exchange_air(i, i + w);
+                }
+            }
+        }
+    }
 
-                // Heat sources hold their temperature
-                match m {
-                    Mat::Fire => {
-                        self.temp_back[i] = 650.0;
-                        continue;
-                    }
-                    Mat::Ember => {
-                        self.temp_back[i] = 600.0;
-                        continue;
-                    }
-                    Mat::Lava => {
-                        self.temp_back[i] = 1100.0;
-                        continue;
-                    }
-                    _ => {}
+    #[inline]
+    fn exchange_air(&mut self, a: usize, b: usize) {
+        let d = (self.oxygen[a] as i16 - self.oxygen[b] as i16) / 4;
+        self.oxygen[a] = (self.oxygen[a] as i16 - d) as u8;
+        self.oxygen[b] = (self.oxygen[b] as i16 + d) as u8;
+    }
+
+    // Richest oxygen level in the cell's own air or its neighbors'.
+    fn oxygen_near(&self, x: i32, y: i32, i: usize) -> u8 {
+        let mut best = if Mat::from_u8(self.mat[i]).carries_air() {
+            self.oxygen[i]
+        } else {
+            0
+        };
+        for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+            if self.in_bounds(x + dx, y + dy) {
+                let ni = self.idx(x + dx, y + dy);
+                if Mat::from_u8(self.mat[ni]).carries_air() {
+                    best = best.max(self.oxygen[ni]);
                 }
+            }
+        }
+        best
+    }
+
+    // Combustion draws `need` oxygen from the cell's own air or from the
+    // richest neighboring air cell. Returns false if none has enough.
+    fn breathe(&mut self, x: i32, y: i32, i: usize, need: u8) -> bool {
+        let mut best = i;
+        let mut best_o2 = if Mat::from_u8(self.mat[i]).carries_air() {
+            self.oxygen[i]
+        } else {
+            0
+        };
+        for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+            if !self.in_bounds(x + dx, y + dy) {
+                continue;
+            }
+            let ni = self.idx(x + dx, y + dy);
+            if Mat::from_u8(self.mat[ni]).carries_air() && self.oxygen[ni] > best_o2 {
+                best = ni;
+                best_o2 = self.oxygen[ni];
+            }
+        }
+        if best_o2 < need {
+            return false;
+        }
+        self.oxygen[best] -= need;
+        true
+    }
+
+    // One step of the simulation. With `times`, adds the milliseconds
+    // spent in each phase (see `step`).
+    fn advance(&mut self, times: Option<&mut [f64; 3]>) {
+        trace!(self.set_cause(trace::Cause::Paint));
+        self.apply_commands();
+        trace!(self.trace_check());
+        self.gen = self.gen.wrapping_add(1);
+        let t0 = if times.is_some() { now_ms() } else { 0.0 };
+        trace!(self.set_cause(trace::Cause::Heat));
+        self.diffuse_heat();
+        self.diffuse_air();
+        trace!(self.trace_check());
+        let t1 = if times.is_some() { now_ms() } else { 0.0 };
 
-                let t = self.temp[i];
-                let mut sum = 0.0;
-                let mut cnt = 0.0;
-                if x > 0 {
-                    sum += self.temp[i - 1];
-                    cnt += 1.0;
-                }
-                if x < w - 1 {
-                    sum += self.temp[i + 1];
-                    cnt += 1.0;
-                }
-                if y > 0 {
-                    sum += self.temp[i - w as usize];
-                    cnt += 1.0;
-                }
-                if y < h - 1 {
-                    sum += self.temp[i + w as usize];
-                    cnt += 1.0;
-                }
-                let avg = sum / cnt;
-                let k = m.conductivity();
-                let mut nt = t + (avg - t) * k;
-                // slow ambient decay so the world doesn't stay hot forever
-                nt += (AMBIENT - nt) * 0.004;
-                // ice generates cold but can still be overwhelmed by real
-                // heat (fire/lava nearby), unlike a hard-pinned temperature
-                if m == Mat::Ice {
-                    nt += (-25.0 - nt) * 0.25;
-                }
-                self.temp_back[i] = nt;
+        self.apply_magnets();
+
+        let ltr = self.gen & 1 == 0;
+        // Bottom-up scan: falling things see free space below before it's claimed;
+        // gases rising are stamped so they're not re-updated this tick.
+        for y in (0..self.height).rev() {
+            if ltr {
+                for x in 0..self.width {
+                    self.update_cell(x, y);
+                }
+            } else {
+                for x in (0..self.width).rev() {
+                    self.update_cell(x, y);
+                }
             }
         }
-        std::mem::swap(&mut self.temp, &mut self.temp_back);
+        trace!(self.set_cause(trace::Cause::Other));
+        self.settle_structures();
+        self.update_agents();
+        trace!(self.trace_check());
+        self.frame = self.frame.wrapping_add(1);
+        if let Some(t) = times {
+            t[HEAT] += t1 - t0;
+            t[MOVEMENT] += now_ms() - t1;
+        }
     }
 
     // === Per-cell update ===
@@ -455,17 +753,40 @@ impl Universe {
         if self.updated[i] == self.gen {
             return;
         }
-        let m = Mat::from_u8(self.mat[i]);
+        let mut m = Mat::from_u8(self.mat[i]);
         if m == Mat::Empty {
             return;
         }
 
-        if self.react(x, y, i, m) {
+        if self.hooks[m as usize].is_some() {
+            trace!(self.set_cause(trace::Cause::Hook));
+            let handled = self.run_hook(x, y, m);
+            trace!(self.trace_check());
+            if handled {
+                return;
+            }
+            // the hook may have moved or changed this cell
+            m = Mat::from_u8(self.mat[i]);
+            if m == Mat::Empty || self.updated[i] == self.gen {
+                return;
+            }
+        }
+
+        // in slow motion a cell sits some steps out, odds and countdowns alike
+        let consumed = self.reacts_now() && self.react(x, y, i, m);
+        trace!(self.trace_check());
+        if consumed {
             return;
         }
+        trace!(self.set_cause(trace::Cause::Move));
+        self.move_cell(x, y, i, m);
+        trace!(self.trace_check());
+    }
 
-        // molten glass flows like sluggish lava; "freezes" again by cooling
-        if m == Mat::Glass && self.temp[i] > 900.0 {
+    fn move_cell(&mut self, x: i32, y: i32, i: usize, m: Mat) {
+        // molten glass and metal flow like sluggish lava; "freeze" again
+        // by cooling
+        if self.is_molten(i) {
             self.update_liquid(x, y, i, m);
             return;
         }
@@ -483,39 +804,15 @@ impl Universe {
 
     // Phase changes & chemistry. Returns true if the cell was consumed.
     fn react(&mut self, x: i32, y: i32, i: usize, m: Mat) -> bool {
+        trace!(self.set_cause(trace::Cause::Rule(m)));
         let t = self.temp[i];
 
         // -- temperature-driven phase changes --
         match m {
-            Mat::Water => {
-                if t > 100.0 && self.chance(4) {
-                    self.convert(i, Mat::Steam);
-                    return true;
-                }
-                if t < -2.0 && self.chance(6) {
-                    self.place(i, Mat::Ice);
-                    return true;
-                }
-            }
-            Mat::SaltWater => {
-                if t > 102.0 && self.chance(4) {
-                    // boiling brine leaves salt behind sometimes
-                    if self.chance(3) {
-                        self.place(i, Mat::Salt);
-                    } else {
-                        self.convert(i, Mat::Steam);
-                    }
-                    return true;
-                }
-                if t < -12.0 && self.chance(8) {
-                    self.place(i, Mat::Ice);
-                    return true;
-                }
-            }
             Mat::Steam => {
                 // freezes out quickly only near real cold (ice)
                 if t < 5.0 && self.chance(10) {
-                    self.convert(i, Mat::Water);
+                    self.change_phase(i, Mat::Water);
                     return true;
                 }
                 // otherwise steam drifts for a long time (forming clouds at
@@ -523,19 +820,12 @@ impl Universe {
                 // not by bulk cooling -- or condenses on cool surfaces
                 if self.chance(8) {
                     if self.life[i] == 0 {
-                        self.convert(i, Mat::Water);
+                        self.change_phase(i, Mat::Water);
                         return true;
                     }
                     self.life[i] -= 1;
                 }
             }
-            Mat::Ice => {
-                if t > 0.0 && self.chance(8) {
-                    self.place(i, Mat::Water);
-                    self.temp[i] = 5.0;
-                    return true;
-                }
-            }
             Mat::Acid => {
                 // boils into corrosive fumes
                 if t > 120.0 && self.chance(8) {
@@ -555,17 +845,39 @@ impl Universe {
                 if self.life[i] > 30 && self.chance(10) {
                     self.try_plant_growth(x, y, i);
                 }
+                if self.life[i] > SEEDING && self.chance(400) {
+                    self.drop_seed(x, y, i);
+                }
+                // a dry plant left in the dark rots back into soil
+                if self.life[i] == 0 && self.chance(200) && !self.sunlit(x, y) {
+                    self.place(i, Mat::Soil);
+                    self.temp[i] = t;
+                    return true;
+                }
             }
-            Mat::Lava => {
-                if t < 700.0 && self.chance(8) {
-                    self.place(i, Mat::Stone);
-                    self.temp[i] = 650.0;
+            Mat::Seed => {
+                if self.try_germinate(x, y, i) {
                     return true;
                 }
             }
-            Mat::Stone => {
-                if t > 950.0 && self.chance(40) {
-                    self.place(i, Mat::Lava);
+            Mat::Root => {
+                if self.life[i] > SPROUT_MOISTURE && self.chance(12) {
+                    self.try_root_growth(x, y, i);
+                }
+            }
+            Mat::Soil => {
+                // dries out slowly in the open, at once when baked
+                if t > 100.0 {
+                    self.life[i] = 0;
+                } else if self.life[i] > 0 && self.chance(60) {
+                    self.life[i] -= 1;
+                }
+            }
+            Mat::Magnet => {
+                // past the Curie point it's just metal
+                if t > 770.0 {
+                    self.convert(i, Mat::Metal);
+                    self.temp[i] = t;
                     return true;
                 }
             }
@@ -573,6 +885,7 @@ impl Universe {
                 if t > 800.0 && self.chance(20) {
                     self.place(i, Mat::Glass);
                     self.temp[i] = t;
+                    self.emit(EVT_GLASS, x, y, 1.0);
                     return true;
                 }
             }
@@ -588,6 +901,11 @@ impl Universe {
                     return true;
                 }
                 self.life[i] -= 1;
+                // starved flames gutter out into smoke
+                if !self.breathe(x, y, i, FIRE_DRAW) && self.chance(3) {
+                    self.convert(i, Mat::Smoke);
+                    return true;
+                }
             }
             Mat::Smoke => {
                 if self.life[i] == 0 {
@@ -608,7 +926,16 @@ impl Universe {
                     return true;
                 }
                 self.life[i] -= 1;
-                if self.chance(6) {
+                // smothered embers stop throwing flames and soon go out,
+                // leaving hot unburnt wood that flares up again if air
+                // gets back in
+                if !self.breathe(x, y, i, EMBER_DRAW) {
+                    if self.chance(12) {
+                        self.place(i, Mat::Wood);
+                        self.temp[i] = t;
+                        return true;
+                    }
+                } else if self.chance(6) {
                     let above = (x, y - 1);
                     if self.in_bounds(above.0, above.1) {
                         let ai = self.idx(above.0, above.1);
@@ -632,23 +959,35 @@ impl Universe {
             _ => {}
         }
 
-        // -- ignition by heat --
+        // -- ignition by heat (gunpowder carries its own oxidizer and
+        //    fuel gases are premixed with air; the rest needs air to catch) --
         if let Some(ign) = m.ignition_temp() {
-            if t > ign {
+            let premixed = matches!(m, Mat::Gunpowder | Mat::Hydrogen | Mat::Methane);
+            if t > ign && (premixed || self.oxygen_near(x, y, i) >= IGNITE_O2) {
                 match m {
                     Mat::Gunpowder => {
                         self.explode(x, y, 7);
                         return true;
                     }
+                    Mat::Hydrogen => {
+                        self.burn_hydrogen(x, y, i);
+                        return true;
+                    }
+                    Mat::Methane => {
+                        self.explode(x, y, 3);
+                        return true;
+                    }
                     Mat::Oil => {
                         if self.chance(2) {
                             self.convert(i, Mat::Fire);
+                            self.emit(EVT_IGNITE, x, y, 1.0);
                             return true;
                         }
                     }
                     Mat::Wood => {
                         if self.chance(6) {
                             self.place(i, Mat::Ember);
+                            self.emit(EVT_IGNITE, x, y, 1.0);
                             return true;
                         }
                     }
@@ -656,6 +995,7 @@ impl Universe {
                         if self.chance(3) {
                             self.place(i, Mat::Ember);
                             self.life[i] = 25 + (self.rand() % 40) as u8;
+                            self.emit(EVT_IGNITE, x, y, 1.0);
                             return true;
                         }
                     }
@@ -672,26 +1012,30 @@ impl Universe {
         if self.in_bounds(nx, ny) {
             let ni = self.idx(nx, ny);
             let nm = Mat::from_u8(self.mat[ni]);
+            trace!(self.set_cause(trace::Cause::Contact(m, nm)));
 
             match (m, nm) {
                 // --- direct-contact ignition (conduction alone is too weak
                 //     for a single flame to reach ignition temperatures) ---
                 (Mat::Oil, Mat::Fire) | (Mat::Oil, Mat::Ember) | (Mat::Oil, Mat::Lava) => {
-                    if self.chance(2) {
+                    if self.chance(2) && self.oxygen_near(x, y, i) >= IGNITE_O2 {
                         self.convert(i, Mat::Fire);
+                        self.emit(EVT_IGNITE, x, y, 1.0);
                         return true;
                     }
                 }
                 (Mat::Wood, Mat::Fire) | (Mat::Wood, Mat::Ember) | (Mat::Wood, Mat::Lava) => {
-                    if self.chance(10) {
+                    if self.chance(10) && self.oxygen_near(x, y, i) >= IGNITE_O2 {
                         self.place(i, Mat::Ember);
+                        self.emit(EVT_IGNITE, x, y, 1.0);
                         return true;
                     }
                 }
                 (Mat::Plant, Mat::Fire) | (Mat::Plant, Mat::Ember) | (Mat::Plant, Mat::Lava) => {
-                    if self.chance(4) {
+                    if self.chance(4) && self.oxygen_near(x, y, i) >= IGNITE_O2 {
                         self.place(i, Mat::Ember);
                         self.life[i] = 25 + (self.rand() % 40) as u8;
+                        self.emit(EVT_IGNITE, x, y, 1.0);
                         return true;
                     }
                 }
@@ -699,10 +1043,19 @@ impl Universe {
                     self.explode(x, y, 7);
                     return true;
                 }
+                (Mat::Hydrogen, Mat::Fire) | (Mat::Hydrogen, Mat::Ember) | (Mat::Hydrogen, Mat::Lava) => {
+                    self.burn_hydrogen(x, y, i);
+                    return true;
+                }
+                (Mat::Methane, Mat::Fire) | (Mat::Methane, Mat::Ember) | (Mat::Methane, Mat::Lava) => {
+                    self.explode(x, y, 3);
+                    return true;
+                }
 
                 // --- water fights fire ---
                 (Mat::Fire, Mat::Water) | (Mat::Fire, Mat::SaltWater) => {
                     self.convert(i, Mat::Smoke);
+                    self.emit(EVT_HISS, x, y, 1.0);
                     if self.chance(3) {
                         self.convert(ni, Mat::Steam);
                     }
@@ -710,6 +1063,7 @@ impl Universe {
                 }
                 (Mat::Water, Mat::Fire) | (Mat::SaltWater, Mat::Fire) => {
                     self.convert(ni, Mat::Smoke);
+                    self.emit(EVT_HISS, nx, ny, 1.0);
                     if self.chance(3) {
                         self.convert(i, Mat::Steam);
                         return true;
@@ -719,6 +1073,7 @@ impl Universe {
                     // douse: dying ember, burst of steam
                     self.place(i, Mat::Smoke);
                     self.convert(ni, Mat::Steam);
+                    self.emit(EVT_HISS, x, y, 1.0);
                     return true;
                 }
 
@@ -729,6 +1084,7 @@ impl Universe {
                     if self.chance(6) {
                         self.place(i, Mat::Glass);
                         self.temp[i] = 850.0;
+                        self.emit(EVT_GLASS, x, y, 1.0);
                         return true;
                     }
                 }
@@ -736,12 +1092,14 @@ impl Universe {
                     if self.chance(6) {
                         self.place(ni, Mat::Glass);
                         self.temp[ni] = 850.0;
+                        self.emit(EVT_GLASS, nx, ny, 1.0);
                     }
                 }
                 (Mat::Lava, Mat::Water) | (Mat::Lava, Mat::SaltWater) => {
                     // water flashes to steam; the lava survives a few such
                     // hits before finally quenching to obsidian
                     self.convert(ni, Mat::Steam);
+                    self.emit(EVT_HISS, nx, ny, 1.0);
                     if self.chance(4) {
                         self.place(i, Mat::Obsidian);
                         return true;
@@ -751,23 +1109,14 @@ impl Universe {
                 // it meets the accumulated meltwater (rule above), so an
                 // obsidian crust doesn't instantly shield the ice
                 (Mat::Ice, Mat::Lava) => {
-                    if self.chance(3) {
-                        self.convert(i, Mat::Steam);
-                    } else {
-                        self.place(i, Mat::Water);
-                        self.temp[i] = 10.0;
-                    }
+                    self.flash_boil(ni, i);
+                    self.emit(EVT_HISS, x, y, 1.0);
                     return true;
                 }
                 (Mat::Lava, Mat::Ice) => {
-                    if self.chance(3) {
-                        self.convert(ni, Mat::Steam);
-                    } else {
-                        self.place(ni, Mat::Water);
-                        self.temp[ni] = 10.0;
-                    }
+                    self.flash_boil(i, ni);
+                    self.emit(EVT_HISS, nx, ny, 1.0);
                 }
-
                 // --- salt (emergent only) ---
                 (Mat::Salt, Mat::Water) => {
                     self.convert(ni, Mat::SaltWater);
@@ -793,20 +1142,71 @@ impl Universe {
                     }
                 }
 
+                // --- soil & roots ---
+                (Mat::Soil, Mat::Water) => {
+                    if self.life[i] < 200 && self.chance(6) {
+                        // soaks in
+                        self.life[i] = self.life[i].saturating_add(100);
+                        self.place(ni, Mat::Empty);
+                    }
+                }
+                (Mat::Soil, Mat::Soil)
+                | (Mat::Root, Mat::Root)
+                | (Mat::Root, Mat::Plant)
+                | (Mat::Plant, Mat::Plant) => {
+                    self.share_moisture(i, ni);
+                }
+                (Mat::Root, Mat::Soil) => {
+                    // roots draw water in but never give it back
+                    if self.life[ni] > self.life[i] {
+                        self.share_moisture(i, ni);
+                    }
+                }
+                (Mat::Root, Mat::Water) => {
+                    if self.chance(6) {
+                        self.life[i] = 255;
+                        self.place(ni, Mat::Empty);
+                    }
+                }
+
                 // --- condensation on cool surfaces ---
                 (Mat::Steam, _) if nm.is_static() && self.temp[ni] < 60.0 => {
                     if self.chance(8) {
-                        self.convert(i, Mat::Water);
-                        self.temp[i] = 40.0;
+                        // the latent heat goes into the surface
+                        self.trade_phase(i, Mat::Water, 40.0, ni);
                         return true;
                     }
                 }
 
-                // --- cold ---
-                (Mat::Ice, Mat::Water) => {
-                    // creeping freeze
-                    if self.temp[i] < -5.0 && self.chance(20) {
-                        self.place(ni, Mat::Ice);
+                // --- corrosion ---
+                (Mat::Metal, Mat::Water) | (Mat::Metal, Mat::SaltWater) => {
+                    // metal wet by aerated water slowly rusts away, brine
+                    // much faster
+                    let odds = if nm == Mat::SaltWater { 150 } else { 600 };
+                    if self.chance(odds) && self.oxygen_near(nx, ny, ni) > 0 {
+                        self.place(i, Mat::Rust);
+                        self.temp[i] = t;
+                        return true;
+                    }
+                }
+
+                // --- chlorine ---
+                (Mat::Chlorine, Mat::Plant) => {
+                    // poisons plants, spending itself slowly
+                    if self.chance(3) {
+                        self.place(ni, Mat::Ash);
+                        if self.chance(4) {
+                            self.mat[i] = 0;
+                            return true;
+                        }
+                    }
+                }
+                (Mat::Chlorine, Mat::Water) => {
+                    // dissolves into an acidic solution
+                    if self.chance(30) {
+                        self.convert(ni, Mat::Acid);
+                        self.mat[i] = 0;
+                        return true;
                     }
                 }
 
@@ -826,8 +1226,11 @@ impl Universe {
                 }
                 (Mat::Acid, _) if nm.is_solid() && nm != Mat::Glass && nm != Mat::Obsidian => {
                     if self.chance(8) {
-                        // dissolving rock leaves mineral salts behind
-                        if nm == Mat::Stone && self.chance(3) {
+                        // dissolving rock leaves mineral salts behind;
+                        // metal fizzes off hydrogen
+                        if nm == Mat::Metal {
+                            self.place(ni, Mat::Hydrogen);
+                        } else if nm == Mat::Stone && self.chance(3) {
                             self.place(ni, Mat::Salt);
                         } else {
                             self.mat[ni] = 0;
@@ -849,13 +1252,13 @@ impl Universe {
 
     // === Movement: powders ===
     fn update_powder(&mut self, x: i32, y: i32, i: usize, m: Mat) {
-        self.vy[i] = (self.vy[i] + GRAVITY).min(MAX_FALL);
+        self.vy[i] = (self.vy[i] + GRAVITY * self.time_scale).min(MAX_FALL);
         self.vx[i] *= 0.85;
 
         match self.try_velocity_move(x, y, i, m) {
             MoveResult::Moved => return,
             // mid-air with sub-cell speed: let velocity build up
-            MoveResult::NoStep if !self.supported(x, y, m) => return,
+            MoveResult::NoStep if !self.supported(x, y) => return,
             _ => {}
         }
 
@@ -864,8 +1267,12 @@ impl Universe {
         if impact > 2.0 {
             let kick = (self.frand() - 0.5) * impact * 0.6;
             self.vx[i] += kick;
+            self.emit(EVT_IMPACT, x, y, impact);
         }
         self.vy[i] = 0.0;
+        if self.slowed(1.0) < 1.0 {
+            return;
+        }
 
         // classic diagonal settle
         let dir = if self.rand() & 1 == 0 { 1 } else { -1 };
@@ -877,7 +1284,7 @@ impl Universe {
             }
             let ni = self.idx(nx, ny);
             let nm = Mat::from_u8(self.mat[ni]);
-            if nm == Mat::Empty || (!nm.is_solid() && nm.density() < m.density()) {
+            if nm == Mat::Empty || (!nm.is_solid() && self.sinks_through(i, ni)) {
                 self.swap_cells(i, ni);
                 return;
             }
@@ -886,11 +1293,11 @@ impl Universe {
 
     // === Movement: liquids ===
     fn update_liquid(&mut self, x: i32, y: i32, i: usize, m: Mat) {
-        self.vy[i] = (self.vy[i] + GRAVITY).min(MAX_FALL);
+        self.vy[i] = (self.vy[i] + GRAVITY * self.time_scale).min(MAX_FALL);
 
         match self.try_velocity_move(x, y, i, m) {
             MoveResult::Moved => return,
-            MoveResult::NoStep if !self.supported(x, y, m) => return,
+            MoveResult::NoStep if !self.supported(x, y) => return,
             _ => {}
         }
 
@@ -900,6 +1307,9 @@ impl Universe {
             self.vx[i] += (self.frand() - 0.5) * impact * 1.0;
         }
         self.vy[i] = 0.0;
+        if self.slowed(1.0) < 1.0 {
+            return;
+        }
 
         // diagonal flow
         let dir = if self.vx[i] > 0.1 {
@@ -919,7 +1329,7 @@ impl Universe {
             }
             let ni = self.idx(nx, ny);
             let nm = Mat::from_u8(self.mat[ni]);
-            if nm == Mat::Empty || (!nm.is_solid() && nm.density() < m.density()) {
+            if nm == Mat::Empty || (!nm.is_solid() && self.sinks_through(i, ni)) {
                 self.swap_cells(i, ni);
                 return;
             }
@@ -927,10 +1337,11 @@ impl Universe {
 
         // horizontal dispersion: march up to N cells toward dir, fall into gaps
         let disp = match m {
-            Mat::Lava | Mat::Glass => 1,
-            Mat::Oil => 3,
-            _ => 4,
+            Mat::Lava | Mat::Glass => 1.0,
+            Mat::Oil => 3.0,
+            _ => 4.0,
         };
+        let disp = self.slowed(disp) as i32;
         let mut cur = i;
         let mut cx = x;
         for _ in 0..disp {
@@ -952,7 +1363,7 @@ impl Universe {
                         return;
                     }
                 }
-            } else if !nm.is_solid() && nm.density() < m.density() {
+            } else if !nm.is_solid() && self.sinks_through(cur, ni) {
                 self.swap_cells(cur, ni);
                 return;
             } else {
@@ -987,14 +1398,23 @@ impl Universe {
             }
         }
 
-
-        // buoyancy + lateral wander
+        // buoyancy (chlorine is heavier than air and sinks instead) tops out
+        // at 1.6 cells a tick either way; a pressure shove can go past that,
+        // but no faster than falling sand, so nothing skips through a wall
         let rise = match m {
+            Mat::Hydrogen => 0.5,
             Mat::Fire => 0.4,
-            Mat::Steam => 0.3,
+            Mat::Steam | Mat::Methane => 0.3,
+            Mat::Chlorine => -0.15,
             _ => 0.22,
         };
-        self.vy[i] = (self.vy[i] - rise).max(-1.6);
+        let vy = self.vy[i] - rise * self.time_scale;
+        self.vy[i] = if self.vy[i].abs() > 1.6 {
+            vy.clamp(-MAX_FALL, MAX_FALL)
+        } else {
+            vy.clamp(-1.6, 1.6)
+        };
+        // lateral wander
         self.vx[i] = (self.vx[i] + (self.frand() - 0.5) * 0.6).clamp(-1.5, 1.5);
 
         if self.try_velocity_move(x, y, i, m) == MoveResult::Moved {
@@ -1019,8 +1439,9 @@ impl Universe {
 
     // Move along the velocity vector, stepping cell by cell.
     fn try_velocity_move(&mut self, x: i32, y: i32, i: usize, m: Mat) -> MoveResult {
-        let vx = self.vx[i];
-        let vy = self.vy[i];
+        // velocity is per step at full speed; slow motion covers less
+        let vx = self.slowed(self.vx[i]);
+        let vy = self.slowed(self.vy[i]);
         // sub-cell velocity: no step this tick, keep accumulating
         if vx.abs().max(vy.abs()).round() < 1.0 {
             return MoveResult::NoStep;
@@ -1050,10 +1471,14 @@ impl Universe {
             let passable = tm == Mat::Empty
                 || (!tm.is_static()
                     && if m.is_gas() {
-                        // gases only rise through liquids
-                        tm.is_liquid()
+                        // gases rise through liquids, and light gas through
+                        // heavier gas (or heavy gas sinks through light)
+                        (tm.is_liquid() && m.density() < 0)
+                            || (tm.is_gas()
+                                && ((ty < cy && self.sinks_through(ti, cur))
+                                    || (ty > cy && self.sinks_through(cur, ti))))
                     } else {
-                        tm.density() < m.density() && !tm.is_powder()
+                        self.sinks_through(cur, ti) && !tm.is_powder()
                     });
 
             if passable {
@@ -1073,53 +1498,44 @@ impl Universe {
         if moved { MoveResult::Moved } else { MoveResult::NoStep }
     }
 
-    fn touches_wood(&self, x: i32, y: i32) -> bool {
-        let dirs = [(0, 1), (0, -1), (1, 0), (-1, 0)];
-        dirs.iter().any(|&(dx, dy)| {
-            self.in_bounds(x + dx, y + dy)
-                && Mat::from_u8(self.mat[self.idx(x + dx, y + dy)]) == Mat::Wood
-        })
-    }
-
-    // Plants grow up into open air; alongside wood they can also grow
-    // sideways, so vines climb wooden structures.
-    fn try_plant_growth(&mut self, x: i32, y: i32, i: usize) {
-        const CANDIDATES: [(i32, i32); 5] = [(0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)];
-        let (dx, dy) = CANDIDATES[(self.rand() as usize) % 5];
-        let nx = x + dx;
-        let ny = y + dy;
-        if !self.in_bounds(nx, ny) {
-            return;
-        }
-        let ni = self.idx(nx, ny);
-        if self.mat[ni] != 0 {
-            return;
-        }
-        // sideways growth needs a wood trellis to cling to
-        if dy == 0 && !self.touches_wood(nx, ny) {
-            return;
-        }
-        let hydration = self.life[i];
-        self.place(ni, Mat::Plant);
-        self.life[ni] = hydration.saturating_sub(40);
-        self.life[i] = hydration.saturating_sub(50);
-        self.updated[ni] = self.gen;
+    fn is_molten(&self, i: usize) -> bool {
+        let m = Mat::from_u8(self.mat[i]);
+        m.melting_point().is_some_and(|t| self.temp[i] > t)
     }
 
     // Is the cell resting on something it can't fall through?
-    fn supported(&self, x: i32, y: i32, m: Mat) -> bool {
+    fn supported(&self, x: i32, y: i32) -> bool {
         if y + 1 >= self.height {
             return true;
         }
-        let bm = Mat::from_u8(self.mat[self.idx(x, y + 1)]);
+        let bi = self.idx(x, y + 1);
+        let bm = Mat::from_u8(self.mat[bi]);
         if bm == Mat::Empty {
             return false;
         }
-        bm.is_static() || bm.is_powder() || bm.density() >= m.density()
+        bm.is_static() || bm.is_powder() || !self.sinks_through(self.idx(x, y), bi)
+    }
+
+    // Density of the cell at `i`, corrected for its temperature.
+    fn density_at(&self, i: usize) -> f32 {
+        let m = Mat::from_u8(self.mat[i]);
+        let d = m.density() as f32;
+        let k = m.expansion();
+        if k == 0.0 {
+            return d;
+        }
+        let dt = ((self.temp[i] - m.base_temperature()) * k).clamp(-0.5, 0.5);
+        d - d.abs() * dt
+    }
+
+    // Whether the cell at `i` is heavy enough to sink through the one at `j`.
+    fn sinks_through(&self, i: usize, j: usize) -> bool {
+        self.density_at(j) + BUOYANCY_MIN < self.density_at(i)
     }
 
     // === Explosions ===
     fn explode(&mut self, x: i32, y: i32, radius: i32) {
+        self.emit(EVT_EXPLOSION, x, y, radius as f32);
         let r2 = radius * radius;
         for dy in -radius..=radius {
             for dx in -radius..=radius {
@@ -1144,11 +1560,9 @@ impl Universe {
                     continue;
                 }
 
-                if nm == Mat::Empty {
-                    if falloff > 0.3 && self.chance(2) {
-                        let debris = if self.chance(2) { Mat::Fire } else { Mat::Smoke };
-                        self.place(ni, debris);
-                    }
+                if nm == Mat::Empty && falloff > 0.3 && self.chance(2) {
+                    let debris = if self.chance(2) { Mat::Fire } else { Mat::Smoke };
+                    self.place(ni, debris);
                 }
 
                 // shockwave: throw particles outward
@@ -1163,10 +1577,28 @@ impl Universe {
         self.life[i] = 30;
     }
 
+    // Hydrogen goes up with a sharp pop rather than a blast, and burns to
+    // water vapor.
+    fn burn_hydrogen(&mut self, x: i32, y: i32, i: usize) {
+        self.explode(x, y, 2);
+        self.place(i, Mat::Steam);
+        self.temp[i] = 600.0;
+        self.emit(EVT_IGNITE, x, y, 1.0);
+    }
+
     #[inline]
     fn chance(&mut self, one_in: u32) -> bool {
-        self.rand() % one_in == 0
+        self.rand().is_multiple_of(one_in)
+    }
+}
+
+// Cell count of a width x height world, if that's a size we can make.
+pub(crate) fn cells(width: u32, height: u32) -> Result<usize, Error> {
+    let n = width as u64 * height as u64;
+    if n == 0 || n > MAX_CELLS as u64 || width.max(height) > MAX_SIDE {
+        return Err(Error::BadSize { width, height });
     }
+    Ok(n as usize)
 }
 
 fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
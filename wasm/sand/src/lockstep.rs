@@ -0,0 +1,243 @@
+// === Lockstep ===
+// Shared worlds stay in sync by running the same simulation on every peer
+// and exchanging only inputs. The sim is already deterministic given its
+// state and rng seed, so peers need to agree on three things:
+//   - the seed (`seeded`),
+//   - which paint commands land on which frame (`queue_paint`; commands for
+//     a frame are applied in player order, whatever order they arrived in),
+//   - and nothing else touching the world behind their backs (local `paint`,
+//     `clear` and JS hooks all bypass the protocol).
+// Desyncs are caught by comparing `state_hash` and repaired by shipping only
+// the chunks whose hashes differ.
+
+use crate::save::{Reader, CELL_BYTES};
+use crate::{Error, Mat, Universe};
+
+// Chunks are CHUNK x CHUNK cells (clipped at the right/bottom edges).
+pub const CHUNK: i32 = 32;
+
+const PATCH_MAGIC: &[u8; 4] = b"SYNC";
+
+#[derive(Clone, Copy)]
+pub(crate) struct Command {
+    frame: u32,
+    player: u8,
+    row: i32,
+    col: i32,
+    mat: Mat,
+    radius: i32,
+}
+
+#[inline]
+fn mix(h: u32, word: u32) -> u32 {
+    (h ^ word).wrapping_mul(0x0100_0193)
+}
+
+impl Universe {
+    // Runs at the top of `tick`: everything scheduled for this frame.
+    pub(crate) fn apply_commands(&mut self) {
+        let due = self.commands.partition_point(|c| c.frame <= self.frame);
+        if due == 0 {
+            return;
+        }
+        let cmds: Vec<Command> = self.commands.drain(..due).collect();
+        for c in cmds {
+            self.paint(c.row, c.col, c.mat, c.radius);
+        }
+    }
+
+    fn chunks_x(&self) -> i32 {
+        (self.width + CHUNK - 1) / CHUNK
+    }
+
+    fn chunk_cells(&self, chunk: u32) -> impl Iterator<Item = usize> {
+        let cx = chunk as i32 % self.chunks_x();
+        let cy = chunk as i32 / self.chunks_x();
+        let w = self.width;
+        let x0 = cx * CHUNK;
+        let y0 = cy * CHUNK;
+        let x1 = (x0 + CHUNK).min(w);
+        let y1 = (y0 + CHUNK).min(self.height);
+        (y0..y1).flat_map(move |y| (x0..x1).map(move |x| (y * w + x) as usize))
+    }
+
+    fn chunk_hash(&self, chunk: u32) -> u32 {
+        let mut h = 0x811C_9DC5;
+        for i in self.chunk_cells(chunk) {
+            h = mix(
+                h,
+                u32::from_le_bytes([self.mat[i], self.life[i], self.variant[i], self.oxygen[i]]),
+            );
+            h = mix(h, self.temp[i].to_bits());
+            h = mix(h, self.vx[i].to_bits());
+            h = mix(h, self.vy[i].to_bits());
+            h = mix(h, self.updated[i] as u32);
+            h = mix(h, self.latent[i].to_bits());
+        }
+        h
+    }
+}
+
+impl Universe {
+    // A world whose randomness starts from `seed`; peers sharing a world
+    // must use the same one. (xorshift can't start from 0, so 0 means the
+    // default seed.)
+    pub fn seeded(width: u32, height: u32, seed: u32) -> Result<Universe, Error> {
+        let mut u = Universe::new(width, height)?;
+        if seed != 0 {
+            u.rng = seed;
+        }
+        Ok(u)
+    }
+
+    // Number of ticks simulated so far.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    // Schedules a paint for the start of `frame`. Local input should go
+    // through here too (a few frames ahead, to hide latency), never straight
+    // to `paint`. Returns false if that frame has already been simulated:
+    // the input arrived too late and the peers will need a resync.
+    pub fn queue_paint(
+        &mut self,
+        frame: u32,
+        player: u8,
+        row: i32,
+        col: i32,
+        mat: Mat,
+        radius: i32,
+    ) -> bool {
+        if frame < self.frame {
+            return false;
+        }
+        let at = self
+            .commands
+            .partition_point(|c| (c.frame, c.player) <= (frame, player));
+        self.commands.insert(
+            at,
+            Command {
+                frame,
+                player,
+                row,
+                col,
+                mat,
+                radius,
+            },
+        );
+        true
+    }
+
+    pub fn pending_commands(&self) -> u32 {
+        self.commands.len() as u32
+    }
+
+    pub fn chunk_count(&self) -> u32 {
+        (self.chunks_x() * ((self.height + CHUNK - 1) / CHUNK)) as u32
+    }
+
+    pub fn chunk_hashes(&self) -> Vec<u32> {
+        (0..self.chunk_count())
+            .map(|c| self.chunk_hash(c))
+            .collect()
+    }
+
+    // Hash of the whole simulation state, cheap enough to exchange every
+    // tick. Equal hashes on the same frame mean the peers are in sync.
+    pub fn state_hash(&self) -> u32 {
+        let mut h = mix(0x811C_9DC5, self.frame);
+        h = mix(h, self.rng);
+        h = mix(h, self.gen as u32);
+        for c in 0..self.chunk_count() {
+            h = mix(h, self.chunk_hash(c));
+        }
+        for a in &self.agents {
+            h = mix(
+                h,
+                u32::from_le_bytes([a.kind as u8, a.dir as u8, a.breath, a.cargo]),
+            );
+            h = mix(h, (a.y as u32) << 16 | a.x as u32);
+        }
+        h
+    }
+
+    // Chunks whose hash differs from `remote` (another peer's
+    // `chunk_hashes` for the same frame).
+    pub fn divergent_chunks(&self, remote: &[u32]) -> Vec<u32> {
+        if remote.len() != self.chunk_count() as usize {
+            return (0..self.chunk_count()).collect();
+        }
+        (0..self.chunk_count())
+            .filter(|&c| self.chunk_hash(c) != remote[c as usize])
+            .collect()
+    }
+
+    // Patch carrying the listed chunks plus the world-wide state (rng,
+    // generation, agents) for a peer to `apply_chunks`:
+    //   "SYNC" frame:u32 gen:u8 rng:u32 count:u32
+    //   per chunk: index:u32, then its cells as in `save`
+    //   agents as in `save`
+    pub fn encode_chunks(&self, chunks: &[u32]) -> Vec<u8> {
+        let chunks: Vec<u32> = chunks
+            .iter()
+            .copied()
+            .filter(|&c| c < self.chunk_count())
+            .collect();
+        let mut out = Vec::new();
+        out.extend_from_slice(PATCH_MAGIC);
+        out.extend_from_slice(&self.frame.to_le_bytes());
+        out.push(self.gen);
+        out.extend_from_slice(&self.rng.to_le_bytes());
+        out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        for &c in &chunks {
+            out.extend_from_slice(&c.to_le_bytes());
+            for i in self.chunk_cells(c) {
+                self.write_cell(i, &mut out);
+            }
+        }
+        self.write_agents(&mut out);
+        out
+    }
+
+    // Applies a patch from `encode_chunks`. Both peers must be on the same
+    // frame; returns false (leaving the world untouched) otherwise, or if
+    // the patch is malformed.
+    pub fn apply_chunks(&mut self, patch: &[u8]) -> bool {
+        self.try_apply_chunks(patch).is_some()
+    }
+}
+
+impl Universe {
+    fn try_apply_chunks(&mut self, patch: &[u8]) -> Option<()> {
+        let mut r = Reader::new(patch);
+        if &r.bytes::<4>()? != PATCH_MAGIC || r.u32()? != self.frame {
+            return None;
+        }
+        let gen = r.u8()?;
+        let rng = r.u32()?;
+        let count = r.u32()?;
+        if count > self.chunk_count() {
+            return None;
+        }
+        // validate everything before touching the world
+        let body = r.pos();
+        for _ in 0..count {
+            let c = r.u32().filter(|&c| c < self.chunk_count())?;
+            r.skip(self.chunk_cells(c).count() * CELL_BYTES)?;
+        }
+        let agents = self.read_agents(&mut r)?;
+
+        let mut r = Reader::new(&patch[body..]);
+        for _ in 0..count {
+            let c = r.u32()?;
+            for i in self.chunk_cells(c).collect::<Vec<_>>() {
+                self.read_cell(i, &mut r)?;
+            }
+        }
+        self.agents = agents;
+        self.rebuild_agent_cells();
+        self.gen = gen;
+        self.rng = rng;
+        Some(())
+    }
+}
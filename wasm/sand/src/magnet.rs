@@ -0,0 +1,51 @@
+// === Magnets ===
+// Each magnet cell pulls on metal within MAGNET_RANGE, falling off with the
+// square of the distance. The pull goes into the metal's velocity: molten
+// metal flows toward a magnet through the usual velocity move, and loose
+// solid metal (a chunk the integrity pass found unsupported) is dragged
+// along as a whole whenever the pull beats gravity.
+
+use crate::{Mat, Universe, MAX_FALL};
+
+const MAGNET_RANGE: i32 = 10;
+// pull at one cell's distance, in cells per tick
+const MAGNET_PULL: f32 = 6.0;
+
+impl Universe {
+    pub(crate) fn apply_magnets(&mut self) {
+        let mut magnets = Vec::new();
+        for i in 0..self.mat.len() {
+            match Mat::from_u8(self.mat[i]) {
+                // solid metal never moves by itself, so its velocity only
+                // ever holds this tick's pull
+                Mat::Metal if !self.is_molten(i) => {
+                    self.vx[i] = 0.0;
+                    self.vy[i] = 0.0;
+                }
+                Mat::Magnet => magnets.push(i),
+                _ => {}
+            }
+        }
+        let w = self.width as usize;
+        let r2 = MAGNET_RANGE * MAGNET_RANGE;
+        for i in magnets {
+            let (x, y) = ((i % w) as i32, (i / w) as i32);
+            for dy in -MAGNET_RANGE..=MAGNET_RANGE {
+                for dx in -MAGNET_RANGE..=MAGNET_RANGE {
+                    let d2 = dx * dx + dy * dy;
+                    if d2 == 0 || d2 > r2 || !self.in_bounds(x + dx, y + dy) {
+                        continue;
+                    }
+                    let ni = self.idx(x + dx, y + dy);
+                    if self.mat[ni] != Mat::Metal as u8 {
+                        continue;
+                    }
+                    let d = (d2 as f32).sqrt();
+                    let f = MAGNET_PULL / d2 as f32;
+                    self.vx[ni] = (self.vx[ni] - dx as f32 / d * f).clamp(-MAX_FALL, MAX_FALL);
+                    self.vy[ni] = (self.vy[ni] - dy as f32 / d * f).clamp(-MAX_FALL, MAX_FALL);
+                }
+            }
+        }
+    }
+}
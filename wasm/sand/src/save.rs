@@ -0,0 +1,191 @@
+// === Save / load ===
+// Flat little-endian snapshot of the whole world, agents included:
+//   "SAND" version:u8 width:u32 height:u32 gen:u8 rng:u32 frame:u32
+//   per cell: mat:u8 life:u8 variant:u8 temp:f32 vx:f32 vy:f32 oxygen:u8
+//             updated:u8 latent:f32
+//   agents:u32, per agent: kind:u8 x:u16 y:u16 dir:i8 breath:u8 cargo:u8
+
+use crate::agents::{Agent, Kind, MAX_AGENTS};
+use crate::{cells, Mat, Universe};
+use std::collections::HashSet;
+
+const MAGIC: &[u8; 4] = b"SAND";
+pub(crate) const VERSION: u8 = 4;
+
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Reader<'a> {
+        Reader { buf, pos: 0 }
+    }
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+    pub(crate) fn skip(&mut self, n: usize) -> Option<()> {
+        let end = self.pos.checked_add(n)?;
+        if end > self.buf.len() {
+            return None;
+        }
+        self.pos = end;
+        Some(())
+    }
+    pub(crate) fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let end = self.pos.checked_add(N)?;
+        let b = self.buf.get(self.pos..end)?.try_into().ok()?;
+        self.pos = end;
+        Some(b)
+    }
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|b| b[0])
+    }
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+    pub(crate) fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+}
+
+impl Universe {
+    pub fn save(&self) -> Vec<u8> {
+        let n = (self.width * self.height) as usize;
+        let mut out = Vec::with_capacity(22 + n * CELL_BYTES + 4 + self.agents.len() * 8);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&(self.width as u32).to_le_bytes());
+        out.extend_from_slice(&(self.height as u32).to_le_bytes());
+        out.push(self.gen);
+        out.extend_from_slice(&self.rng.to_le_bytes());
+        out.extend_from_slice(&self.frame.to_le_bytes());
+        for i in 0..n {
+            self.write_cell(i, &mut out);
+        }
+        self.write_agents(&mut out);
+        out
+    }
+
+    // Replaces the world with a snapshot from `save`. Returns false (and
+    // leaves the world untouched) if the data is malformed.
+    pub fn load(&mut self, data: &[u8]) -> bool {
+        match Universe::decode(data) {
+            Some(mut u) => {
+                u.heat_view = self.heat_view;
+                u.paused = self.paused;
+                u.substeps = self.substeps;
+                u.time_scale = self.time_scale;
+                u.hooks = std::mem::take(&mut self.hooks);
+                u.commands = std::mem::take(&mut self.commands);
+                *self = u;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Bytes per cell in the current format.
+pub(crate) const CELL_BYTES: usize = 21;
+
+impl Universe {
+    pub(crate) fn write_cell(&self, i: usize, out: &mut Vec<u8>) {
+        out.push(self.mat[i]);
+        out.push(self.life[i]);
+        out.push(self.variant[i]);
+        out.extend_from_slice(&self.temp[i].to_le_bytes());
+        out.extend_from_slice(&self.vx[i].to_le_bytes());
+        out.extend_from_slice(&self.vy[i].to_le_bytes());
+        out.push(self.oxygen[i]);
+        out.push(self.updated[i]);
+        out.extend_from_slice(&self.latent[i].to_le_bytes());
+    }
+
+    pub(crate) fn read_cell(&mut self, i: usize, r: &mut Reader) -> Option<()> {
+        self.mat[i] = Mat::from_u8(r.u8()?) as u8;
+        self.life[i] = r.u8()?;
+        self.variant[i] = r.u8()?;
+        self.temp[i] = r.f32()?;
+        self.vx[i] = r.f32()?;
+        self.vy[i] = r.f32()?;
+        self.oxygen[i] = r.u8()?;
+        self.updated[i] = r.u8()?;
+        self.latent[i] = r.f32()?;
+        Some(())
+    }
+
+    pub(crate) fn write_agents(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.agents.len() as u32).to_le_bytes());
+        for a in &self.agents {
+            out.push(a.kind as u8);
+            out.extend_from_slice(&(a.x as u16).to_le_bytes());
+            out.extend_from_slice(&(a.y as u16).to_le_bytes());
+            out.push(a.dir as u8);
+            out.push(a.breath);
+            out.push(a.cargo);
+        }
+    }
+
+    // Agents are parsed (and bounds-checked against this world, one per
+    // cell) without being installed, so callers can validate before
+    // committing.
+    pub(crate) fn read_agents(&self, r: &mut Reader) -> Option<Vec<Agent>> {
+        let count = r.u32()? as usize;
+        if count > MAX_AGENTS {
+            return None;
+        }
+        let mut agents = Vec::with_capacity(count);
+        let mut taken = HashSet::with_capacity(count);
+        for _ in 0..count {
+            let kind = Kind::from_u8(r.u8()?)?;
+            let x = r.u16()? as i32;
+            let y = r.u16()? as i32;
+            let dir = if r.u8()? as i8 >= 0 { 1 } else { -1 };
+            let breath = r.u8()?;
+            let cargo = r.u8()?;
+            if !self.in_bounds(x, y) || !taken.insert((x, y)) {
+                return None;
+            }
+            agents.push(Agent {
+                kind,
+                x,
+                y,
+                dir,
+                breath,
+                cargo,
+            });
+        }
+        Some(agents)
+    }
+
+    fn decode(data: &[u8]) -> Option<Universe> {
+        let mut r = Reader::new(data);
+        if &r.bytes::<4>()? != MAGIC {
+            return None;
+        }
+        if r.u8()? != VERSION {
+            return None;
+        }
+        let w = r.u32()?;
+        let h = r.u32()?;
+        let n = cells(w, h).ok()?;
+        // don't allocate a huge world for a truncated buffer
+        if data.len() < 22 + n * CELL_BYTES {
+            return None;
+        }
+        let mut u = Universe::new(w, h).ok()?;
+        u.gen = r.u8()?;
+        u.rng = r.u32()?;
+        u.frame = r.u32()?;
+        for i in 0..n {
+            u.read_cell(i, &mut r)?;
+        }
+        u.agents = u.read_agents(&mut r)?;
+        u.rebuild_agent_cells();
+        Some(u)
+    }
+}
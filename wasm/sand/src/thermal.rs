@@ -0,0 +1,249 @@
+// === Thermal model ===
+// Each cell holds heat energy `capacity * temp`. Conduction moves energy
+// between neighbors pairwise, so it's conserved; what enters or leaves the
+// world does so through heat sources (fire, embers, lava, ice) with a
+// bounded power output, and a slow loss to the surroundings.
+//
+// Phase changes cost energy too. A cell past its transition temperature is
+// held there while the excess banks up in `latent`; once a full latent heat
+// has been absorbed (or released, going the other way) it changes phase.
+// Ice melting therefore draws heat from whatever touches it, and water
+// needs a sustained input to boil off.
+
+use crate::{Mat, Universe, AMBIENT};
+
+// Fraction of the difference to AMBIENT lost to the surroundings per tick,
+// so the world doesn't stay hot forever.
+const HEAT_LOSS: f32 = 0.004;
+
+impl Mat {
+    // Heat capacity of a cell, relative to water.
+    pub(crate) fn heat_capacity(&self) -> f32 {
+        match self {
+            Mat::Water => 1.0,
+            Mat::SaltWater => 0.95,
+            Mat::Acid | Mat::Plant | Mat::Root => 0.9,
+            Mat::Lava => 1.0,
+            Mat::Salt => 0.85,
+            Mat::Stone | Mat::Rubble | Mat::Obsidian | Mat::Glass => 0.8,
+            // loose mineral grains
+            Mat::Sand | Mat::Soil | Mat::Ash => 0.8,
+            Mat::Wood | Mat::Ember | Mat::Gunpowder | Mat::Rust | Mat::Seed => 0.6,
+            Mat::Ice | Mat::Oil => 0.5,
+            Mat::Metal | Mat::Magnet | Mat::Steam => 0.45,
+            Mat::Empty | Mat::Fire | Mat::Smoke | Mat::Methane | Mat::Chlorine => 0.3,
+            Mat::Hydrogen => 0.35,
+        }
+    }
+
+    // Heat sources: the temperature they drive toward and the most energy
+    // they can put in per tick (negative power cools).
+    pub(crate) fn heat_source(&self) -> Option<(f32, f32)> {
+        match self {
+            Mat::Fire => Some((650.0, 100.0)),
+            Mat::Ember => Some((600.0, 80.0)),
+            Mat::Lava => Some((1100.0, 200.0)),
+            Mat::Ice => Some((-25.0, -4.0)),
+            _ => None,
+        }
+    }
+
+    // Latent heat absorbed on turning into this material from its colder
+    // phase (and released going back).
+    pub(crate) fn latent_heat(&self) -> f32 {
+        match self {
+            Mat::Water | Mat::SaltWater => 40.0,
+            Mat::Steam => 100.0,
+            Mat::Lava => 60.0,
+            _ => 0.0,
+        }
+    }
+
+    // Phase reached by heating past the given temperature.
+    pub(crate) fn phase_up(&self) -> Option<(f32, Mat)> {
+        match self {
+            Mat::Ice => Some((0.0, Mat::Water)),
+            Mat::Water => Some((100.0, Mat::Steam)),
+            Mat::SaltWater => Some((102.0, Mat::Steam)),
+            Mat::Stone | Mat::Rubble => Some((950.0, Mat::Lava)),
+            _ => None,
+        }
+    }
+
+    // Phase reached by cooling past the given temperature.
+    pub(crate) fn phase_down(&self) -> Option<(f32, Mat)> {
+        match self {
+            Mat::Water => Some((0.0, Mat::Ice)),
+            Mat::SaltWater => Some((-12.0, Mat::Ice)),
+            Mat::Lava => Some((700.0, Mat::Stone)),
+            _ => None,
+        }
+    }
+
+    // Energy of this phase at 0 degrees, relative to its coldest phase, so
+    // `enthalpy` is continuous across phase changes.
+    fn enthalpy_offset(&self) -> f32 {
+        let below = match self {
+            Mat::Water | Mat::SaltWater => Mat::Ice,
+            Mat::Steam => Mat::Water,
+            Mat::Lava => Mat::Stone,
+            _ => return 0.0,
+        };
+        let (at, _) = below.phase_up().unwrap_or((0.0, *self));
+        below.enthalpy_offset()
+            + self.latent_heat()
+            + (below.heat_capacity() - self.heat_capacity()) * at
+    }
+}
+
+impl Universe {
+    // Total heat content of cell `i`, latent heat included.
+    pub(crate) fn enthalpy(&self, i: usize) -> f32 {
+        let m = Mat::from_u8(self.mat[i]);
+        m.heat_capacity() * self.temp[i] + self.latent[i] + m.enthalpy_offset()
+    }
+
+    // Turns cell `i` into `into` without creating or destroying heat.
+    pub(crate) fn change_phase(&mut self, i: usize, into: Mat) {
+        let e = self.enthalpy(i);
+        self.convert(i, into);
+        self.latent[i] = 0.0;
+        self.temp[i] = (e - into.enthalpy_offset()) / into.heat_capacity();
+    }
+
+    // Turns cell `i` into `into` at temperature `t`, with `other` (a cell
+    // it's touching) absorbing or supplying the difference in heat.
+    pub(crate) fn trade_phase(&mut self, i: usize, into: Mat, t: f32, other: usize) {
+        let e = self.enthalpy(i);
+        self.convert(i, into);
+        self.temp[i] = t;
+        let q = e - self.enthalpy(i);
+        let m = Mat::from_u8(self.mat[other]);
+        self.temp[other] += q / m.heat_capacity();
+        self.settle_phase(other, m);
+    }
+
+    // Contact with something far hotter boils cell `to` off in one go,
+    // paid for by the heat of `from`.
+    pub(crate) fn flash_boil(&mut self, from: usize, to: usize) {
+        self.trade_phase(to, Mat::Steam, 100.0, from);
+    }
+
+    pub(crate) fn diffuse_heat(&mut self) {
+        let w = self.width as usize;
+        let h = self.height as usize;
+
+        // conduction between each cell and its right and lower neighbor;
+        // the conductance is the harmonic mean of both sides
+        let scale = self.time_scale;
+        let flow = &mut self.heat_in;
+        flow.fill(0.0);
+        let kc = |m: u8| {
+            let m = Mat::from_u8(m);
+            m.conductivity() * m.heat_capacity()
+        };
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let a = kc(self.mat[i]);
+                for j in [i + 1, i + w] {
+                    if (j == i + 1 && x + 1 == w) || j >= w * h {
+                        continue;
+                    }
+                    let b = kc(self.mat[j]);
+                    let g = 0.5 * a * b / (a + b);
+                    let q = g * (self.temp[j] - self.temp[i]) * scale;
+                    flow[i] += q;
+                    flow[j] -= q;
+                }
+            }
+        }
+
+        let (mut sourced, mut lost) = (0.0f64, 0.0f64);
+        for i in 0..w * h {
+            let m = Mat::from_u8(self.mat[i]);
+            let c = m.heat_capacity();
+            let mut e = self.heat_in[i];
+            if let Some((target, power)) = m.heat_source() {
+                let want = (target - self.temp[i] - e / c) * c;
+                let power = power * scale;
+                let s = if power > 0.0 {
+                    want.clamp(0.0, power)
+                } else {
+                    want.clamp(power, 0.0)
+                };
+                e += s;
+                sourced += s as f64;
+            }
+            let loss = (self.temp[i] + e / c - AMBIENT) * HEAT_LOSS * c * scale;
+            e -= loss;
+            lost += loss as f64;
+            self.temp[i] += e / c;
+            self.settle_phase(i, m);
+        }
+        self.heat_sourced = sourced;
+        self.heat_lost = lost;
+    }
+
+    // Banks heat past a transition temperature in `latent`, changing phase
+    // once enough has built up (or paying it back if the cell cools again).
+    fn settle_phase(&mut self, i: usize, m: Mat) {
+        let c = m.heat_capacity();
+        let t = self.temp[i];
+        let latent = self.latent[i];
+        if let Some((at, into)) = m.phase_up() {
+            if t > at || latent > 0.0 {
+                let e = (t - at) * c + latent;
+                if e >= into.latent_heat() {
+                    // boiling brine leaves salt behind sometimes
+                    let into = if m == Mat::SaltWater && self.chance(3) {
+                        Mat::Salt
+                    } else {
+                        into
+                    };
+                    self.change_phase(i, into);
+                } else if e > 0.0 {
+                    self.temp[i] = at;
+                    self.latent[i] = e;
+                } else {
+                    self.temp[i] = at + e / c;
+                    self.latent[i] = 0.0;
+                }
+                return;
+            }
+        }
+        if let Some((at, into)) = m.phase_down() {
+            if t < at || latent < 0.0 {
+                let e = (t - at) * c + latent;
+                if e <= -m.latent_heat() {
+                    self.change_phase(i, into);
+                } else if e < 0.0 {
+                    self.temp[i] = at;
+                    self.latent[i] = e;
+                } else {
+                    self.temp[i] = at + e / c;
+                    self.latent[i] = 0.0;
+                }
+            }
+        }
+    }
+}
+
+impl Universe {
+    // Heat content of the whole world. Between ticks it changes by exactly
+    // `heat_sourced() - heat_lost()`, except where chemistry (burning,
+    // explosions, painting) creates or destroys cells outright.
+    pub fn thermal_energy(&self) -> f64 {
+        (0..self.mat.len()).map(|i| self.enthalpy(i) as f64).sum()
+    }
+
+    // Energy put in by heat sources during the last tick.
+    pub fn heat_sourced(&self) -> f64 {
+        self.heat_sourced
+    }
+
+    // Energy lost to the surroundings during the last tick.
+    pub fn heat_lost(&self) -> f64 {
+        self.heat_lost
+    }
+}
@@ -0,0 +1,110 @@
+// === Time controls ===
+// `tick` runs `substeps` steps per call, so the host can speed the world
+// up without calling it more often, and does nothing while paused (painting
+// and rendering still work). `time_scale` slows things down instead: it
+// scales gravity, buoyancy, how far things move and every heat flow, and a
+// cell only reacts on that share of steps, so reaction odds and countdowns
+// like a flame's life slow down with it. `step` runs the world by hand,
+// pause or not, and reports where the time went.
+
+use crate::{Error, Universe};
+
+// Indices into the timings `step` returns.
+pub const HEAT: usize = 0; // heat and air diffusion
+pub const MOVEMENT: usize = 1; // cell updates, structures, agents
+pub const RENDER: usize = 2;
+
+pub(crate) const MAX_SUBSTEPS: u32 = 16;
+pub(crate) const MIN_TIME_SCALE: f32 = 0.05;
+
+// Milliseconds since some fixed point, for timing phases.
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub(crate) fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.performance())
+        .map_or_else(js_sys::Date::now, |p| p.now())
+}
+
+// A bare wasm32 build has no clock to read, so every phase times as zero.
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+pub(crate) fn now_ms() -> f64 {
+    0.0
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+impl Universe {
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    // Steps per `tick`, 1 to 16.
+    pub fn set_substeps(&mut self, n: u32) -> Result<(), Error> {
+        if !(1..=MAX_SUBSTEPS).contains(&n) {
+            return Err(Error::BadSubsteps(n));
+        }
+        self.substeps = n;
+        Ok(())
+    }
+
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+
+    // Simulation speed within each step: 0.5 is half-speed slow motion.
+    // Limited to 0.05..=1; run more substeps to go faster.
+    pub fn set_time_scale(&mut self, scale: f32) -> Result<(), Error> {
+        if !(MIN_TIME_SCALE..=1.0).contains(&scale) {
+            return Err(Error::BadTimeScale(scale));
+        }
+        self.time_scale = scale;
+        Ok(())
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    // Whole cells to cover this step for something moving `cells` a step
+    // at full speed. In slow motion the fraction carries over from step to
+    // step, so it averages out to `cells * time_scale`.
+    pub(crate) fn slowed(&self, cells: f32) -> f32 {
+        if self.time_scale >= 1.0 {
+            return cells;
+        }
+        let d = (cells * self.time_scale) as f64;
+        let f = self.frame as f64;
+        ((d * (f + 1.0)).floor() - (d * f).floor()) as f32
+    }
+
+    // Whether a cell gets to react this step: always at full speed, else
+    // with odds of `time_scale`.
+    pub(crate) fn reacts_now(&mut self) -> bool {
+        self.time_scale >= 1.0 || (self.rand() as f32) < self.time_scale * u32::MAX as f32
+    }
+
+    // Runs `n` steps even while paused, then renders. Returns the
+    // milliseconds spent per phase, indexed by HEAT, MOVEMENT and RENDER.
+    pub fn step(&mut self, n: u32) -> Vec<f64> {
+        let mut times = [0.0; 3];
+        self.begin_events();
+        for _ in 0..n {
+            self.advance(Some(&mut times));
+        }
+        self.finish_events();
+        let t = now_ms();
+        self.render();
+        times[RENDER] = now_ms() - t;
+        times.to_vec()
+    }
+}
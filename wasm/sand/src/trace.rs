@@ -0,0 +1,252 @@
+// === Tracing ===
+// Debug builds (the `trace` feature) can follow a handful of cells through
+// the simulation. A watched cell is followed as it moves, so watching a
+// grain of sand tracks that grain rather than the spot where it started.
+// Every change to a watched cell's material, temperature or velocity gets
+// logged along with what caused it: the reaction arm that fired (a cell's
+// own rule, or a contact rule such as "Lava+Water"), a swap, heat flow, a
+// hook, paint, or anything else in the step. Without the feature none of
+// this is compiled in; see the `trace!` hooks in lib.rs.
+
+use crate::{Mat, Universe};
+use std::fmt::Write;
+
+const MAX_WATCHED: usize = 64;
+// the log stops growing here rather than eating all memory
+const MAX_ENTRIES: usize = 100_000;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Cause {
+    Paint,
+    Heat,
+    Hook,
+    Rule(Mat),
+    Contact(Mat, Mat),
+    Move,
+    Swap(usize),
+    Other,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Snap {
+    mat: u8,
+    temp: f32,
+    vx: f32,
+    vy: f32,
+}
+
+struct Entry {
+    frame: u32,
+    cell: usize,
+    cause: Cause,
+    before: Snap,
+    after: Snap,
+}
+
+pub(crate) struct Tracer {
+    watched: Vec<usize>, // where each watched cell is now
+    last: Vec<Snap>,     // its state as last logged
+    log: Vec<Entry>,
+    cause: Cause,
+}
+
+impl Default for Tracer {
+    fn default() -> Tracer {
+        Tracer {
+            watched: Vec::new(),
+            last: Vec::new(),
+            log: Vec::new(),
+            cause: Cause::Other,
+        }
+    }
+}
+
+impl Universe {
+    fn snap(&self, i: usize) -> Snap {
+        Snap {
+            mat: self.mat[i],
+            temp: self.temp[i],
+            vx: self.vx[i],
+            vy: self.vy[i],
+        }
+    }
+
+    fn record(&mut self, w: usize, cause: Cause) {
+        let i = self.tracer.watched[w];
+        let after = self.snap(i);
+        let before = self.tracer.last[w];
+        self.tracer.last[w] = after;
+        if self.tracer.log.len() < MAX_ENTRIES {
+            self.tracer.log.push(Entry {
+                frame: self.frame,
+                cell: i,
+                cause,
+                before,
+                after,
+            });
+        }
+    }
+
+    // Blames whatever comes next on `cause`.
+    pub(crate) fn set_cause(&mut self, cause: Cause) {
+        self.tracer.cause = cause;
+    }
+
+    // Logs every watched cell that changed since it was last logged.
+    pub(crate) fn trace_check(&mut self) {
+        for w in 0..self.tracer.watched.len() {
+            if self.snap(self.tracer.watched[w]) != self.tracer.last[w] {
+                self.record(w, self.tracer.cause);
+            }
+        }
+    }
+
+    // Called after cells `a` and `b` trade places.
+    pub(crate) fn trace_swap(&mut self, a: usize, b: usize) {
+        for w in 0..self.tracer.watched.len() {
+            let from = self.tracer.watched[w];
+            let to = if from == a {
+                b
+            } else if from == b {
+                a
+            } else {
+                continue;
+            };
+            self.tracer.watched[w] = to;
+            self.record(w, Cause::Swap(from));
+        }
+    }
+
+    fn cell_json(&self, out: &mut String, i: usize) {
+        let w = self.width as usize;
+        let _ = write!(out, "\"x\":{},\"y\":{}", i % w, i / w);
+    }
+}
+
+fn num(out: &mut String, v: f32) {
+    if v.is_finite() {
+        let _ = write!(out, "{}", v);
+    } else {
+        out.push_str("null");
+    }
+}
+
+fn pair(out: &mut String, key: &str, a: f32, b: f32) {
+    let _ = write!(out, ",\"{}\":[", key);
+    num(out, a);
+    out.push(',');
+    num(out, b);
+    out.push(']');
+}
+
+impl Universe {
+    // Starts following the cell at (x, y). Returns false if it's off the
+    // grid, already watched, or too many cells are.
+    pub fn watch_cell(&mut self, x: i32, y: i32) -> bool {
+        if !self.in_bounds(x, y) || self.tracer.watched.len() >= MAX_WATCHED {
+            return false;
+        }
+        let i = self.idx(x, y);
+        if self.tracer.watched.contains(&i) {
+            return false;
+        }
+        self.tracer.watched.push(i);
+        self.tracer.last.push(self.snap(i));
+        true
+    }
+
+    pub fn unwatch_all(&mut self) {
+        self.tracer.watched.clear();
+        self.tracer.last.clear();
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.tracer.log.clear();
+    }
+
+    pub fn trace_len(&self) -> u32 {
+        self.tracer.log.len() as u32
+    }
+
+    // The log as a JSON array, one object per change:
+    //   {"frame":12,"x":3,"y":40,"cause":"contact","rule":"Lava+Water",
+    //    "mat":["Water","Steam"],"temp":[20,100],"vx":[0,0],"vy":[0,0]}
+    // Swaps carry "from":[x,y]; only reactions carry "rule".
+    pub fn trace_json(&self) -> String {
+        let w = self.width as usize;
+        let mut out = String::from("[");
+        for (n, e) in self.tracer.log.iter().enumerate() {
+            if n > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{{\"frame\":{},", e.frame);
+            self.cell_json(&mut out, e.cell);
+            let cause = match e.cause {
+                Cause::Paint => "paint",
+                Cause::Heat => "heat",
+                Cause::Hook => "hook",
+                Cause::Rule(_) => "rule",
+                Cause::Contact(..) => "contact",
+                Cause::Move => "move",
+                Cause::Swap(_) => "swap",
+                Cause::Other => "other",
+            };
+            let _ = write!(out, ",\"cause\":\"{}\"", cause);
+            match e.cause {
+                Cause::Rule(m) => {
+                    let _ = write!(out, ",\"rule\":\"{:?}\"", m);
+                }
+                Cause::Contact(a, b) => {
+                    let _ = write!(out, ",\"rule\":\"{:?}+{:?}\"", a, b);
+                }
+                Cause::Swap(from) => {
+                    let _ = write!(out, ",\"from\":[{},{}]", from % w, from / w);
+                }
+                _ => {}
+            }
+            let _ = write!(
+                out,
+                ",\"mat\":[\"{:?}\",\"{:?}\"]",
+                Mat::from_u8(e.before.mat),
+                Mat::from_u8(e.after.mat)
+            );
+            pair(&mut out, "temp", e.before.temp, e.after.temp);
+            pair(&mut out, "vx", e.before.vx, e.after.vx);
+            pair(&mut out, "vy", e.before.vy, e.after.vy);
+            out.push('}');
+        }
+        out.push(']');
+        out
+    }
+
+    // Everything about one cell right now, as a JSON object.
+    pub fn inspect(&self, x: i32, y: i32) -> String {
+        if !self.in_bounds(x, y) {
+            return "null".to_string();
+        }
+        let i = self.idx(x, y);
+        let mut out = String::from("{");
+        self.cell_json(&mut out, i);
+        let _ = write!(
+            out,
+            ",\"mat\":\"{:?}\",\"temp\":",
+            Mat::from_u8(self.mat[i])
+        );
+        num(&mut out, self.temp[i]);
+        out.push_str(",\"latent\":");
+        num(&mut out, self.latent[i]);
+        out.push_str(",\"vx\":");
+        num(&mut out, self.vx[i]);
+        out.push_str(",\"vy\":");
+        num(&mut out, self.vy[i]);
+        let _ = write!(
+            out,
+            ",\"life\":{},\"oxygen\":{},\"updated\":{},\"watched\":{}}}",
+            self.life[i],
+            self.oxygen[i],
+            self.updated[i],
+            self.tracer.watched.contains(&i)
+        );
+        out
+    }
+}
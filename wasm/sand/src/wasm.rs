@@ -0,0 +1,291 @@
+// === JS bindings ===
+// The browser build (`wasm` feature, on by default) wraps the engine for
+// wasm-bindgen. JS still sees a `Universe` class taking raw material and
+// agent ids; bad ids, sizes and settings throw an `Error` whose message says
+// what was wrong. Everything here is a thin shim over the Rust API in the
+// rest of the crate.
+
+use crate::{Error, Kind, Mat, Neighborhood, Universe};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+// The Rust API treats a negative radius as an empty brush; from JS it's
+// almost certainly a bug.
+fn brush(radius: i32) -> Result<i32, Error> {
+    if radius < 0 {
+        Err(Error::BadRadius(radius))
+    } else {
+        Ok(radius)
+    }
+}
+
+#[wasm_bindgen(js_name = Universe)]
+pub struct JsUniverse {
+    u: Universe,
+}
+
+#[wasm_bindgen(js_class = Universe)]
+impl JsUniverse {
+    pub fn new(width: u32, height: u32) -> Result<JsUniverse, JsError> {
+        Ok(JsUniverse {
+            u: Universe::new(width, height)?,
+        })
+    }
+
+    pub fn seeded(width: u32, height: u32, seed: u32) -> Result<JsUniverse, JsError> {
+        Ok(JsUniverse {
+            u: Universe::seeded(width, height, seed)?,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.u.width()
+    }
+    pub fn height(&self) -> u32 {
+        self.u.height()
+    }
+    // For a zero-copy view onto wasm memory.
+    pub fn pixels(&self) -> *const u8 {
+        self.u.pixels().as_ptr()
+    }
+    pub fn set_heat_view(&mut self, on: bool) {
+        self.u.set_heat_view(on);
+    }
+    pub fn mat_at(&self, x: i32, y: i32) -> u8 {
+        self.u.mat_at(x, y) as u8
+    }
+    pub fn temp_at(&self, x: i32, y: i32) -> f32 {
+        self.u.temp_at(x, y)
+    }
+    pub fn oxygen_at(&self, x: i32, y: i32) -> u8 {
+        self.u.oxygen_at(x, y)
+    }
+    pub fn clear(&mut self) {
+        self.u.clear();
+    }
+    pub fn paint(&mut self, row: i32, col: i32, mat: u8, radius: i32) -> Result<(), JsError> {
+        self.u.paint(row, col, Mat::try_from(mat)?, brush(radius)?);
+        Ok(())
+    }
+    pub fn tick(&mut self) -> u32 {
+        self.u.tick()
+    }
+    pub fn render(&mut self) {
+        self.u.render();
+    }
+
+    // --- agents ---
+    // False if the cell is off-grid or taken, or the world is full.
+    pub fn spawn_agent(&mut self, x: i32, y: i32, kind: u8) -> Result<bool, JsError> {
+        Ok(self.u.spawn_agent(x, y, Kind::try_from(kind)?))
+    }
+    pub fn agent_count(&self) -> u32 {
+        self.u.agent_count()
+    }
+    // 0 if no agent is there.
+    pub fn agent_at(&self, x: i32, y: i32) -> u8 {
+        self.u.agent_at(x, y).map_or(0, |k| k as u8)
+    }
+
+    // --- events ---
+    pub fn events(&self) -> Vec<f32> {
+        self.u.events()
+    }
+    pub fn event_count(&self) -> u32 {
+        self.u.event_count()
+    }
+
+    // --- hooks ---
+    // `f(view)` is called for every `mat` cell during `tick`; a truthy
+    // return skips the built-in update for that cell.
+    pub fn set_hook(&mut self, mat: u8, f: js_sys::Function) -> Result<(), JsError> {
+        let mat = Mat::try_from(mat)?;
+        let mut hook = JsHook::new(f);
+        self.u.set_hook(mat, move |nb| hook.call(nb));
+        Ok(())
+    }
+    pub fn clear_hook(&mut self, mat: u8) -> Result<(), JsError> {
+        self.u.clear_hook(Mat::try_from(mat)?);
+        Ok(())
+    }
+
+    // --- lockstep ---
+    pub fn frame(&self) -> u32 {
+        self.u.frame()
+    }
+    pub fn queue_paint(
+        &mut self,
+        frame: u32,
+        player: u8,
+        row: i32,
+        col: i32,
+        mat: u8,
+        radius: i32,
+    ) -> Result<bool, JsError> {
+        let mat = Mat::try_from(mat)?;
+        Ok(self
+            .u
+            .queue_paint(frame, player, row, col, mat, brush(radius)?))
+    }
+    pub fn pending_commands(&self) -> u32 {
+        self.u.pending_commands()
+    }
+    pub fn chunk_count(&self) -> u32 {
+        self.u.chunk_count()
+    }
+    pub fn chunk_hashes(&self) -> Vec<u32> {
+        self.u.chunk_hashes()
+    }
+    pub fn state_hash(&self) -> u32 {
+        self.u.state_hash()
+    }
+    pub fn divergent_chunks(&self, remote: &[u32]) -> Vec<u32> {
+        self.u.divergent_chunks(remote)
+    }
+    pub fn encode_chunks(&self, chunks: &[u32]) -> Vec<u8> {
+        self.u.encode_chunks(chunks)
+    }
+    pub fn apply_chunks(&mut self, patch: &[u8]) -> bool {
+        self.u.apply_chunks(patch)
+    }
+
+    // --- save ---
+    pub fn save(&self) -> Vec<u8> {
+        self.u.save()
+    }
+    pub fn load(&mut self, data: &[u8]) -> bool {
+        self.u.load(data)
+    }
+
+    // --- heat ---
+    pub fn thermal_energy(&self) -> f64 {
+        self.u.thermal_energy()
+    }
+    pub fn heat_sourced(&self) -> f64 {
+        self.u.heat_sourced()
+    }
+    pub fn heat_lost(&self) -> f64 {
+        self.u.heat_lost()
+    }
+
+    // --- time ---
+    pub fn set_paused(&mut self, paused: bool) {
+        self.u.set_paused(paused);
+    }
+    pub fn paused(&self) -> bool {
+        self.u.paused()
+    }
+    pub fn set_substeps(&mut self, n: u32) -> Result<(), JsError> {
+        Ok(self.u.set_substeps(n)?)
+    }
+    pub fn substeps(&self) -> u32 {
+        self.u.substeps()
+    }
+    pub fn set_time_scale(&mut self, scale: f32) -> Result<(), JsError> {
+        Ok(self.u.set_time_scale(scale)?)
+    }
+    pub fn time_scale(&self) -> f32 {
+        self.u.time_scale()
+    }
+    pub fn step(&mut self, n: u32) -> Vec<f64> {
+        self.u.step(n)
+    }
+}
+
+#[cfg(feature = "trace")]
+#[wasm_bindgen(js_class = Universe)]
+impl JsUniverse {
+    pub fn watch_cell(&mut self, x: i32, y: i32) -> bool {
+        self.u.watch_cell(x, y)
+    }
+    pub fn unwatch_all(&mut self) {
+        self.u.unwatch_all();
+    }
+    pub fn clear_trace(&mut self) {
+        self.u.clear_trace();
+    }
+    pub fn trace_len(&self) -> u32 {
+        self.u.trace_len()
+    }
+    pub fn trace_json(&self) -> String {
+        self.u.trace_json()
+    }
+    pub fn inspect(&self, x: i32, y: i32) -> String {
+        self.u.inspect(x, y)
+    }
+}
+
+// A JS function as a native hook. The view object is created once and
+// refilled for every call, so the JS side doesn't allocate (or leak) a
+// wrapper per cell.
+struct JsHook {
+    f: js_sys::Function,
+    shared: Rc<RefCell<Neighborhood>>,
+    view: JsValue,
+}
+
+// Hooks must be Send, but JS values never leave the one thread wasm runs on.
+unsafe impl Send for JsHook {}
+
+impl JsHook {
+    fn new(f: js_sys::Function) -> JsHook {
+        let shared = Rc::new(RefCell::new(Neighborhood::empty()));
+        let view = JsValue::from(HookView {
+            inner: shared.clone(),
+        });
+        JsHook { f, shared, view }
+    }
+
+    fn call(&mut self, nb: &mut Neighborhood) -> bool {
+        std::mem::swap(&mut *self.shared.borrow_mut(), nb);
+        let res = self.f.call1(&JsValue::NULL, &self.view);
+        std::mem::swap(&mut *self.shared.borrow_mut(), nb);
+        match res {
+            Ok(v) => v.is_truthy(),
+            Err(e) => {
+                // a throwing hook would throw every cell, every
+                // tick: report it once and unregister it
+                web_sys::console::error_1(&e);
+                nb.unhook();
+                false
+            }
+        }
+    }
+}
+
+// JS handle onto the shared neighborhood passed to a JS hook. `mat` reports
+// OFF_GRID (255) past the edge of the world.
+#[wasm_bindgen]
+pub struct HookView {
+    inner: Rc<RefCell<Neighborhood>>,
+}
+
+#[wasm_bindgen]
+impl HookView {
+    pub fn x(&self) -> i32 {
+        self.inner.borrow().x()
+    }
+    pub fn y(&self) -> i32 {
+        self.inner.borrow().y()
+    }
+    pub fn mat(&self, dx: i32, dy: i32) -> u8 {
+        self.inner.borrow().raw_mat(dx, dy)
+    }
+    pub fn temp(&self, dx: i32, dy: i32) -> f32 {
+        self.inner.borrow().temp(dx, dy)
+    }
+    pub fn random(&self) -> f32 {
+        self.inner.borrow_mut().random()
+    }
+    pub fn swap(&self, dx: i32, dy: i32) -> bool {
+        self.inner.borrow_mut().swap(dx, dy)
+    }
+    pub fn convert(&self, dx: i32, dy: i32, mat: u8) -> Result<bool, JsError> {
+        let mat = Mat::try_from(mat)?;
+        Ok(self.inner.borrow_mut().convert(dx, dy, mat))
+    }
+    pub fn set_temp(&self, dx: i32, dy: i32, t: f32) -> bool {
+        self.inner.borrow_mut().set_temp(dx, dy, t)
+    }
+}
@@ -0,0 +1,165 @@
+use sand::{Kind, Mat, Universe};
+
+const W: i32 = 64;
+const H: i32 = 64;
+
+fn count(u: &Universe, mat: Mat) -> usize {
+    let mut n = 0;
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == mat {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+fn find_agent(u: &Universe, kind: Kind) -> Option<(i32, i32)> {
+    for y in 0..H {
+        for x in 0..W {
+            if u.agent_at(x, y) == Some(kind) {
+                return Some((x, y));
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn ants_dig_through_sand() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        for dy in 1..12 {
+            u.paint(H - dy, x, Mat::Sand, 0); // deep sand bed
+        }
+    }
+    for x in (10..54).step_by(8) {
+        assert!(u.spawn_agent(x, H - 13, Kind::Ant));
+    }
+    let sand_before = count(&u, Mat::Sand);
+    for _ in 0..3000 {
+        u.tick();
+    }
+    // holes inside the bed (below the original surface) are tunnels
+    let mut holes = 0;
+    for y in H - 9..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == Mat::Empty {
+                holes += 1;
+            }
+        }
+    }
+    assert!(holes > 5, "ants never dug into the sand ({} holes)", holes);
+    // grains are carried, not destroyed
+    let sand_after = count(&u, Mat::Sand);
+    assert!(
+        sand_after + u.agent_count() as usize >= sand_before,
+        "sand lost ({} -> {})",
+        sand_before,
+        sand_after
+    );
+}
+
+#[test]
+fn fish_swim_in_water_and_die_out_of_it() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0); // stone floor
+    }
+    for dy in 2..12 {
+        u.paint(H - dy, 9, Mat::Stone, 0); // pond walls
+        u.paint(H - dy, 30, Mat::Stone, 0);
+    }
+    for x in 10..30 {
+        for dy in 2..10 {
+            u.paint(H - dy, x, Mat::Water, 0); // pond
+        }
+    }
+    assert!(u.spawn_agent(20, H - 5, Kind::Fish));
+    assert!(u.spawn_agent(50, H - 5, Kind::Fish)); // stranded on dry land
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(u.agent_count(), 1, "stranded fish survived or pond fish died");
+    let (x, y) = find_agent(&u, Kind::Fish).expect("pond fish missing");
+    assert_eq!(u.mat_at(x, y), Mat::Water, "surviving fish left the water");
+}
+
+#[test]
+fn birds_flee_fire() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0); // stone floor
+    }
+    for x in 26..38 {
+        u.paint(H - 2, x, Mat::Lava, 0); // lava pool
+    }
+    assert!(u.spawn_agent(32, H - 8, Kind::Bird));
+    for _ in 0..200 {
+        u.tick();
+    }
+    let (x, y) = find_agent(&u, Kind::Bird).expect("bird died near the fire");
+    let (dx, dy) = (x - 32, y - (H - 2));
+    assert!(dx * dx + dy * dy > 64, "bird stayed near the lava at {},{}", x, y);
+}
+
+#[test]
+fn creatures_burn_up() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    assert!(u.spawn_agent(32, H - 2, Kind::Ant));
+    u.paint(H - 2, 33, Mat::Lava, 0); // lava right next to it
+    for _ in 0..100 {
+        u.tick();
+    }
+    assert_eq!(u.agent_count(), 0, "ant survived lava");
+}
+
+#[test]
+fn agents_survive_save_and_load() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    u.spawn_agent(10, H - 2, Kind::Ant);
+    u.spawn_agent(40, 20, Kind::Bird);
+    for _ in 0..20 {
+        u.tick();
+    }
+    let snap = u.save();
+
+    let mut v = Universe::new(8, 8).unwrap();
+    assert!(v.load(&snap));
+    assert_eq!((v.width(), v.height()), (W as u32, H as u32));
+    assert_eq!(v.agent_count(), 2);
+    for y in 0..H {
+        for x in 0..W {
+            assert_eq!(v.mat_at(x, y), u.mat_at(x, y));
+            assert_eq!(v.agent_at(x, y), u.agent_at(x, y));
+        }
+    }
+    // identical state (rng included) keeps evolving identically
+    for _ in 0..50 {
+        u.tick();
+        v.tick();
+    }
+    assert_eq!(u.save(), v.save());
+
+    assert!(!v.load(&snap[..snap.len() / 2]), "truncated snapshot accepted");
+    assert_eq!(v.agent_count(), 2);
+
+    // only the current format loads
+    let mut old = snap.clone();
+    old[4] -= 1;
+    assert!(!v.load(&old), "older snapshot accepted");
+
+    // the last agent moved onto the cell of the one before it
+    let mut stacked = snap.clone();
+    let n = stacked.len();
+    stacked.copy_within(n - 15..n - 11, n - 7);
+    assert!(!v.load(&stacked), "two agents in one cell accepted");
+    assert_eq!(v.agent_count(), 2);
+}
@@ -1,22 +1,35 @@
-use sand::Universe;
-use std::time::Instant;
+use sand::{Mat, Universe, HEAT, MOVEMENT, RENDER};
 
 #[test]
 fn bench_full_grid() {
-    let mut u = Universe::new(256, 256);
+    let mut u = Universe::new(256, 256).unwrap();
     // fill half the grid with mixed materials
     for x in 0..256 {
         for y in 128..256 {
-            let m = match (x + y) % 5 { 0 => 1, 1 => 2, 2 => 7, 3 => 15, _ => 3 };
+            let m = match (x + y) % 5 {
+                0 => Mat::Sand,
+                1 => Mat::Water,
+                2 => Mat::Oil,
+                3 => Mat::Gunpowder,
+                _ => Mat::Stone,
+            };
             u.paint(y, x, m, 0);
         }
     }
-    let t = Instant::now();
+    let mut total = [0.0; 3];
     for _ in 0..600 {
-        u.tick();
-        u.render();
+        let t = u.step(1);
+        for p in 0..3 {
+            total[p] += t[p];
+        }
     }
-    let per = t.elapsed().as_secs_f64() * 1000.0 / 600.0;
-    println!("avg tick+render: {:.3} ms", per);
+    let per = total.iter().sum::<f64>() / 600.0;
+    println!(
+        "avg tick+render: {:.3} ms (heat {:.3}, movement {:.3}, render {:.3})",
+        per,
+        total[HEAT] / 600.0,
+        total[MOVEMENT] / 600.0,
+        total[RENDER] / 600.0
+    );
     assert!(per < 16.0, "too slow: {:.3} ms/frame", per);
 }
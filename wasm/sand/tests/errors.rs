@@ -0,0 +1,78 @@
+use sand::{Error, Kind, Mat, Universe, MAX_CELLS, MAX_SIDE};
+
+#[test]
+fn worlds_must_have_a_sane_size() {
+    assert!(Universe::new(1, 1).is_ok());
+    assert!(Universe::new(4096, 4096).is_ok());
+    assert!(Universe::new(MAX_SIDE, 1).is_ok());
+    for (w, h) in [
+        (0, 0),
+        (0, 64),
+        (64, 0),
+        (4097, 4096),
+        // few enough cells, but too long a side for a snapshot
+        (70000, 10),
+        (10, MAX_SIDE + 1),
+        (65536, 65536),
+        (u32::MAX, 2),
+    ] {
+        assert_eq!(
+            Universe::new(w, h).err(),
+            Some(Error::BadSize {
+                width: w,
+                height: h
+            }),
+            "{}x{} was accepted",
+            w,
+            h
+        );
+    }
+    assert!(Universe::seeded(0, 10, 7).is_err());
+    let msg = Universe::new(0, 0).err().unwrap().to_string();
+    assert!(
+        msg.contains("0x0") && msg.contains(&MAX_CELLS.to_string()),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn raw_ids_are_checked() {
+    assert_eq!(Mat::try_from(2), Ok(Mat::Water));
+    assert_eq!(Mat::try_from(29), Ok(Mat::Root));
+    assert_eq!(Mat::try_from(30), Err(Error::UnknownMat(30)));
+    assert_eq!(Mat::try_from(255), Err(Error::UnknownMat(255)));
+    assert_eq!(Kind::try_from(3), Ok(Kind::Bird));
+    assert_eq!(Kind::try_from(0), Err(Error::UnknownKind(0)));
+    assert_eq!(Error::UnknownMat(31).to_string(), "unknown material id 31");
+}
+
+#[test]
+fn bad_settings_are_rejected_and_leave_the_old_value() {
+    let mut u = Universe::new(16, 16).unwrap();
+    u.set_substeps(4).unwrap();
+    assert_eq!(u.set_substeps(0), Err(Error::BadSubsteps(0)));
+    assert_eq!(u.set_substeps(17), Err(Error::BadSubsteps(17)));
+    assert_eq!(u.substeps(), 4);
+
+    u.set_time_scale(0.5).unwrap();
+    for bad in [0.0, 0.01, 1.5, f32::INFINITY, -1.0] {
+        assert_eq!(u.set_time_scale(bad), Err(Error::BadTimeScale(bad)));
+    }
+    assert!(u.set_time_scale(f32::NAN).is_err());
+    assert_eq!(u.time_scale(), 0.5);
+}
+
+#[test]
+fn brushes_clip_to_the_grid() {
+    let mut u = Universe::new(32, 16).unwrap();
+    u.paint(8, 8, Mat::Stone, -3);
+    assert_eq!(u.mat_at(8, 8), Mat::Empty, "negative radius painted");
+    // a huge brush covers the world without visiting every cell it spans
+    u.paint(-1000, 5000, Mat::Stone, i32::MAX);
+    for y in 0..16 {
+        for x in 0..32 {
+            assert_eq!(u.mat_at(x, y), Mat::Stone);
+        }
+    }
+}
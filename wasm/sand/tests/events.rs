@@ -0,0 +1,97 @@
+use sand::{Mat, Universe, EVENT_STRIDE, EVT_EXPLOSION, EVT_HISS, EVT_IMPACT};
+
+const W: i32 = 64;
+const H: i32 = 64;
+
+// (kind, x, y, count, magnitude) for each event of the last tick.
+fn events(u: &Universe) -> Vec<(u32, f32, f32, f32, f32)> {
+    u.events()
+        .chunks(EVENT_STRIDE)
+        .map(|e| (e[0] as u32, e[1], e[2], e[3], e[4]))
+        .collect()
+}
+
+#[test]
+fn explosion_reports_position_and_radius() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    u.paint(H - 2, 20, Mat::Gunpowder, 0); // gunpowder
+    u.paint(H - 3, 20, Mat::Lava, 0); // lava on top to set it off
+    let mut found = None;
+    for _ in 0..60 {
+        if u.tick() & EVT_EXPLOSION != 0 {
+            found = events(&u).into_iter().find(|e| e.0 == EVT_EXPLOSION);
+            break;
+        }
+    }
+    let (_, x, y, _, radius) = found.expect("gunpowder never went off");
+    assert!(
+        (x - 20.0).abs() <= 3.0 && (y - (H - 2) as f32).abs() <= 3.0,
+        "blast at {},{}",
+        x,
+        y
+    );
+    assert_eq!(radius, 7.0);
+}
+
+#[test]
+fn lava_meeting_water_hisses() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    for x in 20..30 {
+        u.paint(H - 2, x, Mat::Lava, 0);
+        u.paint(H - 3, x, Mat::Water, 0);
+    }
+    let mut hissed = false;
+    for _ in 0..30 {
+        if u.tick() & EVT_HISS != 0 {
+            hissed = true;
+            for e in events(&u).into_iter().filter(|e| e.0 == EVT_HISS) {
+                assert!(
+                    e.1 > 10.0 && e.1 < 40.0,
+                    "hiss far from the pool at x={}",
+                    e.1
+                );
+            }
+        }
+    }
+    assert!(hissed, "no hiss from lava under water");
+}
+
+#[test]
+fn impacts_are_aggregated_per_region() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    // a whole slab of sand dropped from high up lands at once
+    for y in 0..8 {
+        for x in 0..W {
+            u.paint(y, x, Mat::Sand, 0);
+        }
+    }
+    let mut merged = false;
+    let mut landed = false;
+    for _ in 0..120 {
+        let bits = u.tick();
+        let impacts: Vec<_> = events(&u)
+            .into_iter()
+            .filter(|e| e.0 == EVT_IMPACT)
+            .collect();
+        assert_eq!(bits & EVT_IMPACT != 0, !impacts.is_empty());
+        for e in &impacts {
+            landed = true;
+            assert!(e.4 > 2.0, "soft landing reported as impact");
+        }
+        let grains: f32 = impacts.iter().map(|e| e.3).sum();
+        // one event per 16x16 region, however many grains hit
+        assert!(impacts.len() <= ((W / 16) * (H / 16)) as usize);
+        merged |= grains > impacts.len() as f32;
+    }
+    assert!(landed, "falling sand never reported an impact");
+    assert!(merged, "impacts were never merged");
+}
@@ -0,0 +1,113 @@
+use sand::{Mat, Universe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const W: i32 = 64;
+const H: i32 = 64;
+
+fn count(u: &Universe, mat: Mat) -> usize {
+    let mut n = 0;
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == mat {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+#[test]
+fn hook_replaces_builtin_behavior() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    // anti-gravity sand: rises instead of falling
+    u.set_hook(Mat::Sand, |nb| {
+        if nb.mat(0, -1) == Some(Mat::Empty) {
+            nb.swap(0, -1);
+        }
+        true
+    });
+    u.paint(H - 5, 32, Mat::Sand, 2);
+    let total = count(&u, Mat::Sand);
+    for _ in 0..200 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Mat::Sand), total, "hook lost or duplicated sand");
+    for y in 10..H {
+        for x in 0..W {
+            assert_ne!(
+                u.mat_at(x, y),
+                Mat::Sand,
+                "sand fell at {},{} despite hook",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn hook_edits_neighbors() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    // "frost stone": freezes any water touching it
+    u.set_hook(Mat::Stone, |nb| {
+        for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+            if nb.mat(dx, dy) == Some(Mat::Water) {
+                nb.convert(dx, dy, Mat::Ice);
+                nb.set_temp(dx, dy, -20.0);
+            }
+        }
+        false
+    });
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    for x in 20..30 {
+        u.paint(H - 2, x, Mat::Water, 0);
+    }
+    for _ in 0..50 {
+        u.tick();
+    }
+    assert!(count(&u, Mat::Ice) > 0, "hook never froze the water");
+}
+
+#[test]
+fn unhandled_hook_falls_through_to_builtin() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = calls.clone();
+    u.set_hook(Mat::Sand, move |nb| {
+        seen.fetch_add(1, Ordering::Relaxed);
+        // the window stops at the world's edge
+        if nb.y() == H - 1 {
+            assert_eq!(nb.mat(0, 1), None);
+        }
+        assert_eq!(nb.mat(2, 0), None);
+        false
+    });
+    u.paint(5, 32, Mat::Sand, 0);
+    for _ in 0..200 {
+        u.tick();
+    }
+    assert!(calls.load(Ordering::Relaxed) > 0, "hook never ran");
+    assert_eq!(u.mat_at(32, H - 1), Mat::Sand, "sand didn't fall normally");
+
+    u.clear_hook(Mat::Sand);
+    let before = calls.load(Ordering::Relaxed);
+    u.tick();
+    assert_eq!(calls.load(Ordering::Relaxed), before, "cleared hook still ran");
+}
+
+#[test]
+fn hooked_worlds_move_between_threads() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.set_hook(Mat::Sand, |nb| nb.swap(0, -1));
+    u.paint(H - 5, 32, Mat::Sand, 0);
+    let u = std::thread::spawn(move || {
+        u.tick();
+        u
+    })
+    .join()
+    .unwrap();
+    assert_eq!(u.mat_at(32, H - 6), Mat::Sand);
+}
@@ -0,0 +1,148 @@
+use sand::{Mat, Universe};
+
+const W: i32 = 96;
+const H: i32 = 64;
+const SEED: u32 = 0x5EED;
+
+// (frame, player, row, col, mat, radius)
+type Cmd = (u32, u8, i32, i32, Mat, i32);
+
+fn script() -> Vec<Cmd> {
+    let mut cmds = vec![];
+    for x in 0..W {
+        cmds.push((0, 0, H - 1, x, Mat::Stone, 0)); // stone floor
+    }
+    for f in 0..60 {
+        cmds.push((f, 0, 4, 20 + (f as i32 % 7), Mat::Sand, 1)); // player 0 pours sand
+        cmds.push((f, 1, 4, 70 - (f as i32 % 5), Mat::Water, 1)); // player 1 pours water
+    }
+    cmds.push((30, 1, H - 6, 48, Mat::Lava, 2)); // lava between them
+    cmds.push((30, 0, H - 6, 48, Mat::Oil, 1)); // and oil on the same frame
+    cmds
+}
+
+fn pair() -> (Universe, Universe) {
+    (
+        Universe::seeded(W as u32, H as u32, SEED).unwrap(),
+        Universe::seeded(W as u32, H as u32, SEED).unwrap(),
+    )
+}
+
+#[test]
+fn peers_stay_in_sync_whatever_the_arrival_order() {
+    let (mut a, mut b) = pair();
+    let cmds = script();
+    for &(f, p, r, c, m, rad) in &cmds {
+        assert!(a.queue_paint(f, p, r, c, m, rad));
+    }
+    // the other peer hears everything from player 1 before player 0
+    let late: Vec<Cmd> = cmds
+        .iter()
+        .filter(|c| c.1 == 1)
+        .chain(cmds.iter().filter(|c| c.1 == 0))
+        .copied()
+        .collect();
+    for &(f, p, r, c, m, rad) in &late {
+        assert!(b.queue_paint(f, p, r, c, m, rad));
+    }
+
+    for _ in 0..200 {
+        a.tick();
+        b.tick();
+        assert_eq!(a.frame(), b.frame());
+        assert_eq!(
+            a.state_hash(),
+            b.state_hash(),
+            "desync at frame {}",
+            a.frame()
+        );
+    }
+    assert_eq!(a.pending_commands(), 0);
+    assert_eq!(a.save(), b.save());
+    assert!(a.mat_at(20, H - 2) != Mat::Empty, "nothing was painted");
+}
+
+#[test]
+fn seed_changes_the_outcome() {
+    let mut a = Universe::seeded(W as u32, H as u32, 1).unwrap();
+    let mut b = Universe::seeded(W as u32, H as u32, 2).unwrap();
+    for u in [&mut a, &mut b] {
+        for f in 0..40 {
+            u.queue_paint(f, 0, 4, 40, Mat::Sand, 2);
+        }
+    }
+    for _ in 0..100 {
+        a.tick();
+        b.tick();
+    }
+    assert_ne!(a.state_hash(), b.state_hash());
+}
+
+#[test]
+fn late_commands_are_rejected() {
+    let (mut a, _) = pair();
+    for _ in 0..10 {
+        a.tick();
+    }
+    assert!(!a.queue_paint(9, 0, 10, 10, Mat::Sand, 0));
+    assert!(a.queue_paint(10, 0, 10, 10, Mat::Sand, 0));
+    a.tick();
+    assert_eq!(a.mat_at(10, 10), Mat::Sand);
+}
+
+#[test]
+fn resync_ships_only_divergent_chunks() {
+    let (mut a, mut b) = pair();
+    for &(f, p, r, c, m, rad) in &script() {
+        a.queue_paint(f, p, r, c, m, rad);
+        b.queue_paint(f, p, r, c, m, rad);
+    }
+    for _ in 0..80 {
+        a.tick();
+        b.tick();
+    }
+    assert_eq!(a.state_hash(), b.state_hash());
+
+    // a stray local edit on one peer only
+    b.paint(10, 85, Mat::Stone, 1);
+    assert_ne!(a.state_hash(), b.state_hash());
+
+    let stale = b.divergent_chunks(&a.chunk_hashes());
+    assert!(!stale.is_empty());
+    assert!(
+        stale.len() < b.chunk_count() as usize / 2,
+        "{} chunks resent",
+        stale.len()
+    );
+    let patch = a.encode_chunks(&stale);
+    assert!(patch.len() < a.save().len() / 2);
+    assert!(b.apply_chunks(&patch));
+    assert_eq!(a.state_hash(), b.state_hash());
+
+    for _ in 0..100 {
+        a.tick();
+        b.tick();
+    }
+    assert_eq!(
+        a.state_hash(),
+        b.state_hash(),
+        "diverged again after resync"
+    );
+}
+
+#[test]
+fn bad_patches_leave_the_world_alone() {
+    let (mut a, mut b) = pair();
+    a.paint(10, 10, Mat::Stone, 2);
+    let patch = a.encode_chunks(&[0]);
+    let before = b.save();
+    assert!(
+        !b.apply_chunks(&patch[..patch.len() - 3]),
+        "truncated patch accepted"
+    );
+    assert_eq!(b.save(), before);
+
+    // a patch from another frame is stale
+    b.tick();
+    assert!(!b.apply_chunks(&patch));
+}
@@ -1,9 +1,9 @@
-use sand::Universe;
+use sand::{Mat, Universe, EVT_EXPLOSION};
 
 const W: i32 = 64;
 const H: i32 = 64;
 
-fn count(u: &Universe, mat: u8) -> usize {
+fn count(u: &Universe, mat: Mat) -> usize {
     let mut n = 0;
     for y in 0..H {
         for x in 0..W {
@@ -17,55 +17,55 @@ fn count(u: &Universe, mat: u8) -> usize {
 
 #[test]
 fn sand_falls_and_piles() {
-    let mut u = Universe::new(W as u32, H as u32);
-    u.paint(5, 32, 1, 2); // sand blob high up
-    let total = count(&u, 1);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.paint(5, 32, Mat::Sand, 2); // sand blob high up
+    let total = count(&u, Mat::Sand);
     assert!(total > 0);
     for _ in 0..200 {
         u.tick();
     }
     // conservation (sand can become glass only via heat; none here)
-    assert_eq!(count(&u, 1), total, "sand lost or duplicated");
+    assert_eq!(count(&u, Mat::Sand), total, "sand lost or duplicated");
     // everything should have landed in the bottom rows
     for y in 0..H - 10 {
         for x in 0..W {
-            assert_ne!(u.mat_at(x, y), 1, "sand stuck mid-air at {},{}", x, y);
+            assert_ne!(u.mat_at(x, y), Mat::Sand, "sand stuck mid-air at {},{}", x, y);
         }
     }
 }
 
 #[test]
 fn water_spreads_flat() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     // column of water in the middle
     for y in 20..40 {
-        u.paint(y, 32, 2, 0);
+        u.paint(y, 32, Mat::Water, 0);
     }
     for _ in 0..400 {
         u.tick();
     }
     // water (2) + any steam (6) should still exist
-    assert!(count(&u, 2) + count(&u, 6) > 0);
+    assert!(count(&u, Mat::Water) + count(&u, Mat::Steam) > 0);
     // should have spread out: bottom row occupied across a decent span
-    let bottom_span: usize = (0..W).filter(|&x| u.mat_at(x, H - 1) == 2).count();
+    let bottom_span: usize = (0..W).filter(|&x| u.mat_at(x, H - 1) == Mat::Water).count();
     assert!(bottom_span > 10, "water did not spread (span={})", bottom_span);
     // no tall column left in the middle
-    assert_eq!(u.mat_at(32, 30), 0, "water column never collapsed");
+    assert_eq!(u.mat_at(32, 30), Mat::Empty, "water column never collapsed");
 }
 
 #[test]
 fn lava_meets_water() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 20..30 {
-        u.paint(H - 1, x, 9, 0); // lava on floor
-        u.paint(H - 3, x, 2, 0); // water above
+        u.paint(H - 1, x, Mat::Lava, 0); // lava on floor
+        u.paint(H - 3, x, Mat::Water, 0); // water above
     }
     for _ in 0..300 {
         u.tick();
     }
-    let obsidian = count(&u, 14);
-    let steam = count(&u, 6);
-    let stone = count(&u, 3);
+    let obsidian = count(&u, Mat::Obsidian);
+    let steam = count(&u, Mat::Steam);
+    let stone = count(&u, Mat::Stone);
     assert!(
         obsidian + steam + stone > 0,
         "no reaction products (obsidian={} steam={} stone={})",
@@ -75,113 +75,113 @@ fn lava_meets_water() {
 
 #[test]
 fn gunpowder_explodes_near_lava() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 28..36 {
-        u.paint(H - 1, x, 15, 0); // gunpowder on floor
+        u.paint(H - 1, x, Mat::Gunpowder, 0); // gunpowder on floor
     }
-    u.paint(H - 1, 27, 9, 1); // lava beside it
-    let before = count(&u, 15);
+    u.paint(H - 1, 27, Mat::Lava, 1); // lava beside it
+    let before = count(&u, Mat::Gunpowder);
     assert!(before > 0);
     for _ in 0..300 {
         u.tick();
     }
-    assert!(count(&u, 15) < before, "gunpowder never ignited");
+    assert!(count(&u, Mat::Gunpowder) < before, "gunpowder never ignited");
 }
 
 #[test]
 fn wood_burns_to_ash_or_smoke() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 28..36 {
-        u.paint(H - 1, x, 4, 0); // wood floor strip
+        u.paint(H - 1, x, Mat::Wood, 0); // wood floor strip
     }
-    u.paint(H - 2, 30, 9, 1); // lava on top
+    u.paint(H - 2, 30, Mat::Lava, 1); // lava on top
     for _ in 0..600 {
         u.tick();
     }
-    let wood = count(&u, 4);
+    let wood = count(&u, Mat::Wood);
     assert!(wood < 8, "wood never burned (still {} cells)", wood);
 }
 
 #[test]
 fn water_freezes_near_ice_then_melts() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 0..W {
-        u.paint(H - 1, x, 11, 0); // ice floor
+        u.paint(H - 1, x, Mat::Ice, 0); // ice floor
     }
     for x in 20..40 {
-        u.paint(H - 2, x, 2, 0); // shallow water on it
+        u.paint(H - 2, x, Mat::Water, 0); // shallow water on it
     }
     for _ in 0..600 {
         u.tick();
     }
-    assert!(count(&u, 11) > W as usize, "no water froze");
+    assert!(count(&u, Mat::Ice) > W as usize, "no water froze");
 }
 
 #[test]
 fn fire_spreads_across_oil() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 10..54 {
-        u.paint(H - 1, x, 7, 0); // oil strip on the floor
+        u.paint(H - 1, x, Mat::Oil, 0); // oil strip on the floor
     }
-    u.paint(H - 2, 12, 9, 0); // single lava cell at one end
+    u.paint(H - 2, 12, Mat::Lava, 0); // single lava cell at one end
     for _ in 0..400 {
         u.tick();
     }
-    let oil = count(&u, 7);
+    let oil = count(&u, Mat::Oil);
     assert!(oil < 20, "fire failed to spread across oil ({} cells left)", oil);
 }
 
 #[test]
 fn fire_melts_ice() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 20..44 {
         for dy in 1..4 {
-            u.paint(H - dy, x, 11, 0); // ice slab
+            u.paint(H - dy, x, Mat::Ice, 0); // ice slab
         }
     }
     for x in 28..36 {
-        u.paint(H - 5, x, 9, 0); // lava on top
+        u.paint(H - 5, x, Mat::Lava, 0); // lava on top
     }
-    let ice_before = count(&u, 11);
+    let ice_before = count(&u, Mat::Ice);
     // measure mid-burn: later, steam condenses, rains down and re-freezes
     // on the slab (emergent water cycle), masking the melt
     let mut min_ice = ice_before;
     for _ in 0..600 {
         u.tick();
-        min_ice = min_ice.min(count(&u, 11));
+        min_ice = min_ice.min(count(&u, Mat::Ice));
     }
     assert!(min_ice < ice_before, "heat never melted ice ({} -> min {})", ice_before, min_ice);
 }
 
 #[test]
 fn water_extinguishes_burning_wood() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 20..44 {
-        u.paint(H - 1, x, 4, 0); // wood floor
+        u.paint(H - 1, x, Mat::Wood, 0); // wood floor
     }
-    u.paint(H - 2, 32, 9, 0); // lava starts the fire
+    u.paint(H - 2, 32, Mat::Lava, 0); // lava starts the fire
     for _ in 0..60 {
         u.tick();
     }
     // dump water on the whole thing
     for x in 16..48 {
         for dy in 6..14 {
-            u.paint(H - dy, x, 2, 0);
+            u.paint(H - dy, x, Mat::Water, 0);
         }
     }
     for _ in 0..400 {
         u.tick();
     }
     // fire should be out (no fire or embers left burning)
-    assert_eq!(count(&u, 5), 0, "fire still burning under water");
+    assert_eq!(count(&u, Mat::Fire), 0, "fire still burning under water");
 }
 
 #[test]
 fn steam_rises_high() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 28..36 {
-        u.paint(H - 1, x, 9, 0); // lava floor
-        u.paint(H - 3, x, 2, 0); // water above -> steam
+        u.paint(H - 1, x, Mat::Lava, 0); // lava floor
+        u.paint(H - 3, x, Mat::Water, 0); // water above -> steam
     }
     let mut max_height = 0;
     for t in 0..400 {
@@ -189,7 +189,7 @@ fn steam_rises_high() {
         let _ = t;
         for y in 0..H {
             for x in 0..W {
-                if u.mat_at(x, y) == 6 {
+                if u.mat_at(x, y) == Mat::Steam {
                     let height = H - y;
                     if height > max_height {
                         max_height = height;
@@ -204,34 +204,34 @@ fn steam_rises_high() {
 
 #[test]
 fn lava_glazes_sand_surface() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 10..54 {
         for dy in 1..5 {
-            u.paint(H - dy, x, 1, 0); // sand bed
+            u.paint(H - dy, x, Mat::Sand, 0); // sand bed
         }
     }
     for x in 28..36 {
-        u.paint(H - 7, x, 9, 0); // lava poured on top
+        u.paint(H - 7, x, Mat::Lava, 0); // lava poured on top
     }
     for _ in 0..400 {
         u.tick();
     }
-    assert!(count(&u, 13) > 0, "lava never glazed sand into glass");
+    assert!(count(&u, Mat::Glass) > 0, "lava never glazed sand into glass");
 }
 
 #[test]
 fn steam_forms_clouds_at_top() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 24..40 {
-        u.paint(H - 1, x, 9, 0); // lava floor
-        u.paint(H - 3, x, 2, 0); // water above
+        u.paint(H - 1, x, Mat::Lava, 0); // lava floor
+        u.paint(H - 3, x, Mat::Water, 0); // water above
     }
     let mut cloud_seen = false;
     for _ in 0..800 {
         u.tick();
         for y in 0..6 {
             for x in 0..W {
-                if u.mat_at(x, y) == 6 {
+                if u.mat_at(x, y) == Mat::Steam {
                     cloud_seen = true;
                 }
             }
@@ -242,18 +242,18 @@ fn steam_forms_clouds_at_top() {
 
 #[test]
 fn vines_climb_wood() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 0..W {
-        u.paint(H - 1, x, 3, 0); // stone floor
+        u.paint(H - 1, x, Mat::Stone, 0); // stone floor
     }
     for dy in 2..20 {
-        u.paint(H - dy, 32, 4, 0); // wood pillar
+        u.paint(H - dy, 32, Mat::Wood, 0); // wood pillar
     }
-    u.paint(H - 2, 31, 10, 0); // plant seed at the base
+    u.paint(H - 2, 31, Mat::Plant, 0); // plant seed at the base
     // drip water onto the plant to keep it hydrated
     for t in 0..1500 {
         if t % 12 == 0 {
-            u.paint(H - 8, 30, 2, 0);
+            u.paint(H - 8, 30, Mat::Water, 0);
         }
         u.tick();
     }
@@ -261,7 +261,7 @@ fn vines_climb_wood() {
     let mut highest = 0;
     for y in 0..H {
         for x in 0..W {
-            if u.mat_at(x, y) == 10 {
+            if u.mat_at(x, y) == Mat::Plant {
                 highest = highest.max(H - 1 - y);
             }
         }
@@ -271,20 +271,20 @@ fn vines_climb_wood() {
 
 #[test]
 fn lava_melts_through_ice() {
-    let mut u = Universe::new(W as u32, H as u32);
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
     for x in 16..48 {
         for dy in 1..8 {
-            u.paint(H - dy, x, 11, 0); // thick ice slab
+            u.paint(H - dy, x, Mat::Ice, 0); // thick ice slab
         }
     }
-    let ice_before = count(&u, 11);
+    let ice_before = count(&u, Mat::Ice);
     for x in 28..36 {
-        u.paint(H - 10, x, 9, 0); // lava poured on top
+        u.paint(H - 10, x, Mat::Lava, 0); // lava poured on top
     }
     let mut min_ice = ice_before;
     for _ in 0..800 {
         u.tick();
-        min_ice = min_ice.min(count(&u, 11));
+        min_ice = min_ice.min(count(&u, Mat::Ice));
     }
     // lava should carve out a real chunk, not just scratch the surface
     assert!(
@@ -294,3 +294,563 @@ fn lava_melts_through_ice() {
         min_ice
     );
 }
+
+// Stone box with a smouldering wood floor; returns wood left after
+// the fire has run its course.
+fn burn_in_box(sealed: bool) -> usize {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 20..32 {
+        u.paint(H - 1, x, Mat::Stone, 0); // floor
+        if sealed {
+            u.paint(H - 8, x, Mat::Stone, 0); // roof
+        }
+    }
+    for dy in 1..8 {
+        u.paint(H - dy, 20, Mat::Stone, 0); // walls
+        u.paint(H - dy, 31, Mat::Stone, 0);
+    }
+    for x in 21..31 {
+        u.paint(H - 2, x, Mat::Wood, 0); // wood floor inside
+    }
+    u.paint(H - 2, 25, Mat::Ember, 0); // a couple of embers to start it
+    u.paint(H - 2, 26, Mat::Ember, 0);
+    for _ in 0..1500 {
+        u.tick();
+    }
+    count(&u, Mat::Wood)
+}
+
+#[test]
+fn fire_smothers_in_sealed_room() {
+    let open = burn_in_box(false);
+    let sealed = burn_in_box(true);
+    assert!(open < 3, "open fire never took hold ({} wood left)", open);
+    assert!(
+        sealed > open + 3,
+        "sealed room burned like an open one (sealed {} vs open {})",
+        sealed,
+        open
+    );
+}
+
+#[test]
+fn moving_solids_carry_no_oxygen() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.paint(4, 16, Mat::Sand, 2);
+    u.paint(4, 32, Mat::Water, 2);
+    u.paint(H - 2, 48, Mat::Fire, 1); // burns out into rising smoke
+    for _ in 0..60 {
+        u.tick();
+    }
+    assert!(count(&u, Mat::Smoke) > 0, "no smoke to check");
+    // only air (and the fire breathing it) holds oxygen, however far the
+    // rest has moved through it
+    for y in 0..H {
+        for x in 0..W {
+            let m = u.mat_at(x, y);
+            if m != Mat::Empty && m != Mat::Fire {
+                assert_eq!(u.oxygen_at(x, y), 0, "{:?} at {},{} holds oxygen", m, x, y);
+            }
+        }
+    }
+    assert!(u.oxygen_at(16, 4) > 0 && u.oxygen_at(32, 4) > 0, "air left behind is empty");
+}
+
+#[test]
+fn gunpowder_explodes_without_air() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 20..40 {
+        for dy in 1..6 {
+            u.paint(H - dy, x, Mat::Stone, 0); // solid stone block
+        }
+    }
+    for x in 25..35 {
+        u.paint(H - 3, x, Mat::Gunpowder, 0); // gunpowder sealed inside it
+    }
+    u.paint(H - 3, 24, Mat::Lava, 0); // lava touching the end of the charge
+    let before = count(&u, Mat::Gunpowder);
+    for _ in 0..200 {
+        u.tick();
+    }
+    assert!(count(&u, Mat::Gunpowder) < before, "sealed gunpowder never went off");
+}
+
+#[test]
+fn floating_stone_crumbles_to_rubble() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 28..36 {
+        for y in 20..24 {
+            u.paint(y, x, Mat::Stone, 0); // stone block hanging in mid-air
+        }
+    }
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Mat::Stone), 0, "unsupported stone never broke up");
+    assert_eq!(count(&u, Mat::Rubble), 32, "rubble lost or duplicated");
+    assert_eq!(u.mat_at(32, H - 1), Mat::Rubble, "rubble never hit the floor");
+}
+
+#[test]
+fn long_bridges_collapse_short_ones_stand() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for dy in 1..20 {
+        for x in [2, 10, 61] {
+            u.paint(H - dy, x, Mat::Stone, 0); // stone pillars
+        }
+    }
+    for x in 2..62 {
+        u.paint(H - 20, x, Mat::Stone, 0); // one deck across both gaps
+    }
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(u.mat_at(6, H - 20), Mat::Stone, "short span fell");
+    assert_ne!(u.mat_at(36, H - 20), Mat::Stone, "long span held");
+}
+
+#[test]
+fn wood_falls_as_one_piece_and_floats() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        for dy in 1..6 {
+            u.paint(H - dy, x, Mat::Water, 0); // pool
+        }
+    }
+    for x in 20..30 {
+        u.paint(10, x, Mat::Wood, 0); // plank in the sky
+        u.paint(12, x + 20, Mat::Metal, 0); // metal bar
+    }
+    for _ in 0..300 {
+        u.tick();
+    }
+    // the plank lands on the water in one row, the metal sinks to the floor
+    let plank_rows: Vec<i32> = (0..H).filter(|&y| (0..W).any(|x| u.mat_at(x, y) == Mat::Wood)).collect();
+    assert_eq!(plank_rows.len(), 1, "plank broke up: rows {:?}", plank_rows);
+    assert!(plank_rows[0] >= H - 7, "plank never fell (row {})", plank_rows[0]);
+    assert_eq!(count(&u, Mat::Wood), 10);
+    assert_eq!((40..50).filter(|&x| u.mat_at(x, H - 1) == Mat::Metal).count(), 10, "metal didn't sink");
+}
+
+// Mean and spread of water temperature along row y.
+fn water_row_temps(u: &Universe, y: i32) -> (f32, f32) {
+    let temps: Vec<f32> = (0..W).filter(|&x| u.mat_at(x, y) == Mat::Water).map(|x| u.temp_at(x, y)).collect();
+    let mean = temps.iter().sum::<f32>() / temps.len().max(1) as f32;
+    let (lo, hi) = temps.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &t| (lo.min(t), hi.max(t)));
+    (mean, hi - lo)
+}
+
+#[test]
+fn convection_cells_form_above_lava() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Lava, 0); // lava floor
+        u.paint(H - 2, x, Mat::Lava, 0);
+        u.paint(H - 3, x, Mat::Metal, 0); // metal hotplate
+        for dy in 4..7 {
+            u.paint(H - dy, x, Mat::Ash, 0); // ash to slow the heat down
+        }
+    }
+    let (top, bottom) = (H - 27, H - 7);
+    for y in top - 3..=bottom {
+        u.paint(y, 8, Mat::Stone, 0); // tank walls
+        u.paint(y, 55, Mat::Stone, 0);
+    }
+    for y in top..=bottom {
+        for x in 9..55 {
+            u.paint(y, x, Mat::Water, 0);
+        }
+    }
+    let water = count(&u, Mat::Water);
+
+    let (mut upper, mut middle, mut plumes) = (0.0, 0.0, 0);
+    for t in 0..2000 {
+        u.tick();
+        if t >= 600 && t % 50 == 0 {
+            upper += (top..top + 3).map(|y| water_row_temps(&u, y).0).sum::<f32>() / 3.0;
+            let (mid, spread) = water_row_temps(&u, (top + bottom) / 2);
+            middle += mid;
+            if spread > 5.0 {
+                plumes += 1;
+            }
+        }
+    }
+    // heat is carried up rather than piling up and boiling the bottom away
+    assert!(count(&u, Mat::Water) * 10 >= water * 9, "tank boiled away");
+    let samples = 28.0;
+    assert!(upper / samples > 45.0, "surface never warmed ({:.0})", upper / samples);
+    // warm water collects at the top over cooler water: conduction alone
+    // can't do that
+    assert!(upper > middle, "no overturn ({:.0} over {:.0})", upper / samples, middle / samples);
+    assert!(plumes > 14, "no rising/sinking plumes ({} samples)", plumes);
+}
+
+// Mean row of all cells of a material.
+fn mean_row(u: &Universe, mat: Mat) -> f32 {
+    let (mut sum, mut n) = (0, 0);
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == mat {
+                sum += y;
+                n += 1;
+            }
+        }
+    }
+    sum as f32 / n.max(1) as f32
+}
+
+// A closed metal room with its ceiling on row 20.
+fn gas_trap(u: &mut Universe) {
+    for x in 16..48 {
+        u.paint(20, x, Mat::Metal, 0);
+        u.paint(H - 1, x, Mat::Metal, 0);
+    }
+    for y in 20..H {
+        u.paint(y, 16, Mat::Metal, 0);
+        u.paint(y, 47, Mat::Metal, 0);
+    }
+}
+
+#[test]
+fn light_gas_pools_under_a_ceiling() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    gas_trap(&mut u);
+    for y in 44..50 {
+        for x in 28..36 {
+            u.paint(y, x, Mat::Methane, 0); // methane
+        }
+    }
+    let gas = count(&u, Mat::Methane);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Mat::Methane), gas, "methane leaked or vanished");
+    let row = mean_row(&u, Mat::Methane);
+    assert!(row > 20.0 && row < 24.0, "methane sits at row {}", row);
+}
+
+#[test]
+fn methane_explodes_with_fire() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    for y in H - 8..H - 4 {
+        for x in 20..30 {
+            u.paint(y, x, Mat::Methane, 0);
+        }
+    }
+    u.paint(H - 2, 25, Mat::Fire, 0); // a flame under the cloud
+    let mut blasts = 0;
+    for _ in 0..60 {
+        if u.tick() & EVT_EXPLOSION != 0 {
+            blasts += 1;
+        }
+    }
+    assert!(blasts > 0, "methane never went off");
+    assert!(count(&u, Mat::Methane) < 10, "{} methane left", count(&u, Mat::Methane));
+}
+
+#[test]
+fn hydrogen_burns_to_steam() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    gas_trap(&mut u);
+    for y in 40..44 {
+        for x in 24..40 {
+            u.paint(y, x, Mat::Hydrogen, 0);
+        }
+    }
+    for _ in 0..100 {
+        u.tick();
+    }
+    let gas = count(&u, Mat::Hydrogen);
+    u.paint(22, 32, Mat::Fire, 0); // a spark in the pooled gas
+    let mut steam = false;
+    for _ in 0..60 {
+        u.tick();
+        steam |= count(&u, Mat::Steam) > 0;
+    }
+    assert!(steam, "burning hydrogen made no steam");
+    assert!(count(&u, Mat::Hydrogen) < gas / 4, "{} of {} hydrogen left", count(&u, Mat::Hydrogen), gas);
+}
+
+#[test]
+fn chlorine_sinks_and_kills_plants() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+        u.paint(H - 2, x, Mat::Plant, 0); // a lawn
+    }
+    for y in 10..16 {
+        for x in 24..40 {
+            u.paint(y, x, Mat::Chlorine, 0);
+        }
+    }
+    let plants = count(&u, Mat::Plant);
+    for _ in 0..300 {
+        u.tick();
+    }
+    assert!(mean_row(&u, Mat::Chlorine) > (H / 2) as f32 || count(&u, Mat::Chlorine) == 0, "chlorine floated");
+    assert!(count(&u, Mat::Plant) < plants / 2, "{} of {} plants survived", count(&u, Mat::Plant), plants);
+}
+
+#[test]
+fn fast_chlorine_does_not_tunnel() {
+    // chlorine already falling fast, a few cells above a floor one cell
+    // thick
+    let (w, h) = (8, 40);
+    let mut u = Universe::new(w as u32, h as u32).unwrap();
+    for x in 0..w {
+        u.paint(30, x, Mat::Metal, 0);
+    }
+    u.paint(26, 4, Mat::Chlorine, 0);
+    // header 22 bytes, 21 per cell, vy 11 bytes in
+    let mut snap = u.save();
+    let at = 22 + (26 * w as usize + 4) * 21 + 11;
+    snap[at..at + 4].copy_from_slice(&20f32.to_le_bytes());
+    assert!(u.load(&snap));
+    for _ in 0..20 {
+        u.tick();
+    }
+    for y in 31..h {
+        for x in 0..w {
+            assert_ne!(u.mat_at(x, y), Mat::Chlorine, "chlorine passed the floor");
+        }
+    }
+    assert_eq!(count(&u, Mat::Chlorine), 1);
+}
+
+#[test]
+fn acid_on_metal_gives_off_hydrogen() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for y in H - 4..H {
+        for x in 0..W {
+            u.paint(y, x, Mat::Metal, 0);
+        }
+    }
+    let metal = count(&u, Mat::Metal);
+    for x in 16..48 {
+        u.paint(H - 5, x, Mat::Acid, 0);
+    }
+    let mut hydrogen = 0;
+    for _ in 0..200 {
+        u.tick();
+        hydrogen = hydrogen.max(count(&u, Mat::Hydrogen));
+    }
+    assert!(count(&u, Mat::Metal) < metal, "acid didn't touch the metal");
+    assert!(hydrogen > 0, "no hydrogen from corroding metal");
+}
+
+#[test]
+fn metal_melts_in_lava_and_casts_back() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 10..40 {
+        u.paint(H - 1, x, Mat::Obsidian, 0); // obsidian won't melt
+    }
+    for y in H - 12..H - 1 {
+        u.paint(y, 10, Mat::Obsidian, 0);
+        u.paint(y, 39, Mat::Obsidian, 0);
+    }
+    for y in H - 8..H - 1 {
+        for x in 11..39 {
+            u.paint(y, x, Mat::Lava, 0); // lava basin
+        }
+    }
+    for y in H - 14..H - 10 {
+        for x in 23..27 {
+            u.paint(y, x, Mat::Metal, 0); // an ingot dropped in
+        }
+    }
+    let metal = count(&u, Mat::Metal);
+    let mut molten = false;
+    for _ in 0..400 {
+        u.tick();
+        for y in 0..H {
+            for x in 0..W {
+                molten |= u.mat_at(x, y) == Mat::Metal && u.temp_at(x, y) > 1000.0;
+            }
+        }
+    }
+    assert!(molten, "metal never melted");
+    // drain the lava and let the pool cool
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == Mat::Lava {
+                u.paint(y, x, Mat::Empty, 0);
+            }
+        }
+    }
+    for _ in 0..600 {
+        u.tick();
+    }
+    assert_eq!(count(&u, Mat::Metal), metal, "metal lost or duplicated");
+    let rows: Vec<i32> = (0..H)
+        .filter(|&y| (0..W).any(|x| u.mat_at(x, y) == Mat::Metal))
+        .collect();
+    // it flowed out flat across the basin floor
+    assert!(rows.len() < 4, "metal still spans rows {:?}", rows);
+    assert_eq!(*rows.last().unwrap(), H - 2);
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == Mat::Metal {
+                assert!(u.temp_at(x, y) < 1000.0, "metal still molten at {},{}", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn wet_metal_rusts_at_the_waterline() {
+    let mut rust = vec![];
+    for liquid in [Mat::Water, Mat::SaltWater] {
+        let mut u = Universe::new(W as u32, H as u32).unwrap();
+        for y in H - 12..H {
+            for x in (4..W).step_by(8) {
+                u.paint(y, x, Mat::Metal, 0); // posts standing in the water
+                u.paint(y, x + 1, Mat::Metal, 0);
+            }
+        }
+        for y in H - 6..H {
+            for x in 0..W {
+                if u.mat_at(x, y) == Mat::Empty {
+                    u.paint(y, x, liquid, 0);
+                }
+            }
+        }
+        for _ in 0..1500 {
+            u.tick();
+        }
+        rust.push(count(&u, Mat::Rust));
+        // rusting needs air as well as water
+        for y in H - 3..H {
+            for x in (4..W).step_by(8) {
+                assert_eq!(u.mat_at(x, y), Mat::Metal, "rust below the waterline");
+            }
+        }
+    }
+    assert!(rust[0] > 0, "wet metal never rusted");
+    assert!(rust[1] > rust[0], "brine rusted {} cells, water {}", rust[1], rust[0]);
+}
+
+#[test]
+fn magnet_catches_falling_metal() {
+    let mut caught = vec![];
+    for with_magnet in [false, true] {
+        let mut u = Universe::new(W as u32, H as u32).unwrap();
+        if with_magnet {
+            for y in 20..24 {
+                for x in 0..3 {
+                    u.paint(y, x, Mat::Magnet, 0); // on the left wall
+                }
+            }
+        }
+        for y in 20..23 {
+            for x in 8..11 {
+                u.paint(y, x, Mat::Metal, 0);
+            }
+        }
+        for _ in 0..100 {
+            u.tick();
+        }
+        caught.push((0..30).any(|y| u.mat_at(3, y) == Mat::Metal));
+        assert_eq!(count(&u, Mat::Metal), 9);
+    }
+    assert_eq!(caught, [false, true]);
+}
+
+// A bed of soil `depth` rows deep on a stone floor.
+fn garden(u: &mut Universe, depth: i32) {
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+        for dy in 2..depth + 2 {
+            u.paint(H - dy, x, Mat::Soil, 0);
+        }
+    }
+}
+
+#[test]
+fn seeds_sprout_only_on_wet_soil() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    garden(&mut u, 6);
+    let top = H - 7;
+    for x in 32..W {
+        for y in top - 4..top - 1 {
+            u.paint(y, x, Mat::Water, 0); // water the right half
+        }
+    }
+    for _ in 0..60 {
+        u.tick();
+    }
+    for x in (4..W).step_by(4) {
+        u.paint(top - 1, x, Mat::Seed, 0);
+    }
+    for _ in 0..400 {
+        u.tick();
+    }
+    let sprouted = |xs: std::ops::Range<i32>| xs.filter(|&x| (0..=top).any(|y| u.mat_at(x, y) == Mat::Plant)).count();
+    assert_eq!(sprouted(0..30), 0, "seeds sprouted on dry soil");
+    assert!(sprouted(34..W) >= 4, "only {} shoots on wet soil", sprouted(34..W));
+    assert!(count(&u, Mat::Root) > 0, "no roots");
+}
+
+#[test]
+fn roots_seek_water_and_shoots_seek_light() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    garden(&mut u, 16);
+    let top = H - 17;
+    for x in 20..44 {
+        u.paint(H - 2, x, Mat::Water, 0); // wet layer at the bottom of the bed
+        u.paint(top - 1, x, Mat::Water, 0); // and a watering to start the seeds
+    }
+    for x in 36..W {
+        u.paint(top - 8, x, Mat::Metal, 0); // a metal shelf shading the right end
+    }
+    for x in (22..44).step_by(3) {
+        u.paint(top - 2, x, Mat::Seed, 0);
+    }
+    for t in 0..2500 {
+        if t % 40 == 0 {
+            u.paint(top - 12, 28, Mat::Water, 1); // a light rain on the open part
+        }
+        u.tick();
+    }
+    let deepest = (0..H)
+        .filter(|&y| (0..W).any(|x| u.mat_at(x, y) == Mat::Root))
+        .max()
+        .unwrap_or(0);
+    assert!(deepest > top + 6, "roots only reached row {} (surface {})", deepest, top);
+    let tallest = (22..34)
+        .filter_map(|x| (0..top).find(|&y| u.mat_at(x, y) == Mat::Plant))
+        .min();
+    assert!(tallest.unwrap_or(top) < top - 8, "open plants only reached row {:?}", tallest);
+    // in the shade the seeds sprout but don't grow
+    let shaded = (37..44).map(|x| (top - 7..top).filter(|&y| u.mat_at(x, y) == Mat::Plant).count()).sum::<usize>();
+    assert!(shaded <= 3, "{} plant cells grew under the shelf", shaded);
+}
+
+#[test]
+fn buried_plants_rot_into_soil() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Stone, 0);
+        u.paint(H - 2, x, Mat::Plant, 0);
+        u.paint(H - 3, x, Mat::Plant, 0);
+    }
+    for y in H - 12..H - 1 {
+        u.paint(y, W / 2, Mat::Stone, 0);
+    }
+    for x in 0..W / 2 {
+        for y in H - 10..H - 3 {
+            u.paint(y, x, Mat::Sand, 0); // bury the left half under sand
+        }
+    }
+    let plants = count(&u, Mat::Plant);
+    for _ in 0..1500 {
+        u.tick();
+    }
+    let buried = (0..W / 2).filter(|&x| u.mat_at(x, H - 2) == Mat::Plant).count();
+    let open = (W / 2 + 1..W).filter(|&x| u.mat_at(x, H - 2) == Mat::Plant).count();
+    assert!(buried < 4, "{} buried plants survived", buried);
+    assert_eq!(open, (W / 2 - 1) as usize, "plants in the open rotted");
+    assert!(count(&u, Mat::Soil) + count(&u, Mat::Plant) >= plants * 3 / 4);
+}
@@ -0,0 +1,134 @@
+use sand::{Mat, Universe};
+
+const W: i32 = 64;
+const H: i32 = 64;
+
+fn count(u: &Universe, mat: Mat) -> usize {
+    let mut n = 0;
+    for y in 0..H {
+        for x in 0..W {
+            if u.mat_at(x, y) == mat {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+// Lava under a metal hotplate, with a block of ice on the plate.
+fn hotplate_with_ice() -> Universe {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Lava, 0);
+        u.paint(H - 2, x, Mat::Metal, 0);
+    }
+    for y in H - 12..H - 2 {
+        for x in 20..44 {
+            u.paint(y, x, Mat::Ice, 0);
+        }
+    }
+    u
+}
+
+#[test]
+fn energy_is_accounted_for() {
+    let mut u = hotplate_with_ice();
+    let mut e = u.thermal_energy();
+    for t in 0..400 {
+        u.tick();
+        let now = u.thermal_energy();
+        let expected = e + u.heat_sourced() - u.heat_lost();
+        assert!(
+            (now - expected).abs() < 1e-4 * now.abs().max(1.0),
+            "tick {}: energy {} but sources/losses account for {}",
+            t,
+            now,
+            expected
+        );
+        e = now;
+    }
+    assert!(count(&u, Mat::Ice) < 240, "ice never melted");
+}
+
+#[test]
+fn melting_ice_holds_at_zero() {
+    let mut u = hotplate_with_ice();
+    let mut melted = false;
+    for _ in 0..300 {
+        u.tick();
+        for y in 0..H {
+            for x in 0..W {
+                if u.mat_at(x, y) == Mat::Ice {
+                    // heat goes into melting, not into warming the ice
+                    assert!(u.temp_at(x, y) <= 0.0, "ice at {} degrees", u.temp_at(x, y));
+                }
+            }
+        }
+        melted |= count(&u, Mat::Water) > 0;
+    }
+    assert!(melted);
+}
+
+#[test]
+fn boiling_takes_sustained_heat() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for x in 0..W {
+        u.paint(H - 1, x, Mat::Lava, 0);
+        u.paint(H - 2, x, Mat::Metal, 0);
+    }
+    for y in H - 8..H - 2 {
+        for x in 0..W {
+            u.paint(y, x, Mat::Water, 0);
+        }
+    }
+    let (mut boiling_at, mut dry_at) = (None, None);
+    for t in 0..600 {
+        u.tick();
+        for y in 0..H {
+            for x in 0..W {
+                if u.mat_at(x, y) == Mat::Water {
+                    assert!(
+                        u.temp_at(x, y) <= 100.0 + 1e-3,
+                        "water at {} degrees",
+                        u.temp_at(x, y)
+                    );
+                }
+            }
+        }
+        if boiling_at.is_none() && count(&u, Mat::Steam) > 0 {
+            boiling_at = Some(t);
+        }
+        if dry_at.is_none() && count(&u, Mat::Water) == 0 {
+            dry_at = Some(t);
+        }
+    }
+    let start = boiling_at.expect("water never boiled");
+    let end = dry_at.expect("water never boiled off");
+    assert!(start > 10, "boiled after {} ticks", start);
+    // the pan boils off over many ticks, not in one flash
+    assert!(end - start > 10, "boiled off in {} ticks", end - start);
+}
+
+#[test]
+fn heat_sources_have_bounded_power() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    for y in H - 20..H {
+        for x in 20..40 {
+            u.paint(y, x, Mat::Metal, 0); // a block of metal
+        }
+    }
+    u.paint(H - 10, 30, Mat::Lava, 0); // one lava cell buried in it
+    let e = u.thermal_energy();
+    let mut total = 0.0;
+    for _ in 0..100 {
+        u.tick();
+        let p = u.heat_sourced();
+        assert!(p > 0.0 && p <= 200.0, "lava put out {}", p);
+        total += p;
+    }
+    assert!(
+        u.temp_at(30, H - 8) > 100.0,
+        "metal around the lava stayed cold"
+    );
+    assert!(u.thermal_energy() - e < total);
+}
@@ -0,0 +1,154 @@
+use sand::{Mat, Universe, HEAT, MOVEMENT, RENDER};
+
+const W: i32 = 64;
+const H: i32 = 64;
+
+fn lowest(u: &Universe, mat: Mat) -> i32 {
+    (0..H)
+        .filter(|&y| (0..W).any(|x| u.mat_at(x, y) == mat))
+        .max()
+        .unwrap_or(-1)
+}
+
+#[test]
+fn paused_world_still_takes_paint() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.set_paused(true);
+    u.paint(5, 32, Mat::Sand, 0);
+    for _ in 0..20 {
+        assert_eq!(u.tick(), 0);
+    }
+    assert_eq!(u.frame(), 0);
+    assert_eq!(u.mat_at(32, 5), Mat::Sand, "sand fell while paused");
+    u.render();
+
+    u.set_paused(false);
+    for _ in 0..20 {
+        u.tick();
+    }
+    assert_eq!(u.mat_at(32, 5), Mat::Empty);
+}
+
+#[test]
+fn substeps_match_single_ticks() {
+    let mut a = Universe::new(W as u32, H as u32).unwrap();
+    let mut b = Universe::new(W as u32, H as u32).unwrap();
+    for u in [&mut a, &mut b] {
+        u.paint(10, 20, Mat::Sand, 3);
+        u.paint(10, 44, Mat::Water, 3);
+        u.paint(H - 2, 32, Mat::Lava, 2);
+    }
+    a.set_substeps(3).unwrap();
+    for _ in 0..40 {
+        a.tick();
+    }
+    for _ in 0..120 {
+        b.tick();
+    }
+    assert_eq!(a.frame(), 120);
+    assert_eq!(a.state_hash(), b.state_hash());
+}
+
+#[test]
+fn slow_motion_slows_falling_and_heating() {
+    let mut fell = vec![];
+    let mut warmed = vec![];
+    for scale in [1.0, 0.25] {
+        let mut u = Universe::new(W as u32, H as u32).unwrap();
+        u.set_time_scale(scale).unwrap();
+        u.paint(2, 10, Mat::Sand, 0);
+        for x in 40..50 {
+            u.paint(H - 1, x, Mat::Lava, 0);
+            u.paint(H - 2, x, Mat::Metal, 0);
+        }
+        for _ in 0..30 {
+            u.tick();
+        }
+        fell.push(lowest(&u, Mat::Sand));
+        // the metal sinks through the lava to the floor, where it stays
+        // put at either speed (the lava flowing over it doesn't)
+        assert_eq!(u.mat_at(45, H - 1), Mat::Metal);
+        warmed.push(u.temp_at(45, H - 1));
+    }
+    assert!(
+        fell[1] < fell[0],
+        "grain fell to {} in slow motion, {} at speed",
+        fell[1],
+        fell[0]
+    );
+    assert!(
+        warmed[1] < warmed[0],
+        "metal reached {} in slow motion, {} at speed",
+        warmed[1],
+        warmed[0]
+    );
+}
+
+#[test]
+fn slow_motion_slows_falling_past_terminal_speed() {
+    // long enough a drop that both reach top speed well before landing
+    let (w, h) = (16, 400);
+    let mut fell = vec![];
+    for (scale, ticks) in [(1.0, 70), (0.25, 280)] {
+        let mut u = Universe::new(w as u32, h as u32).unwrap();
+        u.set_time_scale(scale).unwrap();
+        u.paint(2, 4, Mat::Sand, 0);
+        u.paint(2, 12, Mat::Water, 0);
+        for _ in 0..ticks {
+            u.tick();
+        }
+        let at = |mat| (0..h).find(|&y| (0..w).any(|x| u.mat_at(x, y) == mat));
+        fell.push((at(Mat::Sand).unwrap(), at(Mat::Water).unwrap()));
+    }
+    // a quarter of the speed for four times as long covers the same ground
+    let ((sand, water), (slow_sand, slow_water)) = (fell[0], fell[1]);
+    assert!(sand > 200 && sand < h - 10, "grain fell to {sand}");
+    assert!(
+        (slow_sand - sand).abs() <= 8,
+        "grain fell to {slow_sand} in slow motion, {sand} at speed"
+    );
+    assert!(
+        (slow_water - water).abs() <= 8,
+        "water fell to {slow_water} in slow motion, {water} at speed"
+    );
+}
+
+#[test]
+fn slow_motion_slows_reactions() {
+    let count = |u: &Universe, mat| {
+        (0..H)
+            .flat_map(|y| (0..W).map(move |x| (x, y)))
+            .filter(|&(x, y)| u.mat_at(x, y) == mat)
+            .count()
+    };
+    let mut left = vec![];
+    for (scale, ticks) in [(1.0, 60), (0.25, 60), (0.25, 400)] {
+        let mut u = Universe::new(W as u32, H as u32).unwrap();
+        u.set_time_scale(scale).unwrap();
+        for x in 20..44 {
+            u.paint(H - 2, x, Mat::Fire, 0);
+        }
+        for _ in 0..ticks {
+            u.tick();
+        }
+        left.push(count(&u, Mat::Fire));
+    }
+    // a flame lives 20 to 50 steps at full speed, four times that slowed
+    assert_eq!(left[0], 0, "flames outlived their life at speed");
+    assert!(left[1] > 0, "flames burnt out as fast in slow motion");
+    assert_eq!(left[2], 0, "flames never burnt out in slow motion");
+}
+
+#[test]
+fn step_runs_while_paused_and_reports_phases() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.paint(5, 32, Mat::Sand, 0);
+    u.set_paused(true);
+    let times = u.step(4);
+    assert_eq!(u.frame(), 4);
+    assert_eq!(times.len(), 3);
+    for phase in [HEAT, MOVEMENT, RENDER] {
+        assert!(times[phase] >= 0.0);
+    }
+    assert!(lowest(&u, Mat::Sand) > 5, "step didn't move the sand");
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "trace")]
+
+use sand::{Mat, Universe};
+
+const W: i32 = 32;
+const H: i32 = 32;
+
+#[test]
+fn falling_grain_is_followed_through_its_swaps() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    u.paint(2, 16, Mat::Sand, 0);
+    assert!(u.watch_cell(16, 2));
+    assert!(!u.watch_cell(16, 2), "watched twice");
+    assert!(!u.watch_cell(-1, 0));
+    for _ in 0..60 {
+        u.tick();
+    }
+    let json = u.trace_json();
+    assert!(json.starts_with('[') && json.ends_with(']'));
+    assert!(json.contains("\"cause\":\"swap\""), "{}", json);
+    assert!(!json.contains("\"Empty\""), "lost the grain: {}", json);
+
+    // the watch ended up wherever the grain came to rest
+    let rest = (0..H)
+        .rev()
+        .find(|&y| u.mat_at(16, y) == Mat::Sand)
+        .unwrap();
+    assert!(rest > 20);
+    assert!(u.inspect(16, rest).contains("\"watched\":true"));
+    assert!(u.inspect(16, 2).contains("\"watched\":false"));
+
+    u.clear_trace();
+    assert_eq!(u.trace_len(), 0);
+    assert_eq!(u.inspect(W, 0), "null");
+}
+
+#[test]
+fn reaction_records_the_arm_that_fired() {
+    let mut u = Universe::new(W as u32, H as u32).unwrap();
+    // a single water cell in a stone cup, with salt dropped onto it
+    for x in 14..19 {
+        u.paint(H - 1, x, Mat::Stone, 0);
+    }
+    u.paint(H - 2, 15, Mat::Stone, 0);
+    u.paint(H - 2, 17, Mat::Stone, 0);
+    u.paint(H - 2, 16, Mat::Water, 0);
+    u.paint(H - 4, 16, Mat::Salt, 0);
+    assert!(u.watch_cell(16, H - 2));
+    for _ in 0..10 {
+        u.tick();
+    }
+    assert_eq!(u.mat_at(16, H - 2), Mat::SaltWater);
+    let json = u.trace_json();
+    assert!(
+        json.contains("\"cause\":\"contact\",\"rule\":\"Salt+Water\""),
+        "{}",
+        json
+    );
+    assert!(
+        json.contains("\"mat\":[\"Water\",\"SaltWater\"]"),
+        "{}",
+        json
+    );
+    assert_eq!(u.trace_len(), 1);
+}